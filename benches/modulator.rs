@@ -0,0 +1,60 @@
+use std::hint::black_box;
+
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use software_modem::ofdm::{
+    CyclicPrefix,
+    modulator::{OFDMModulator, OFDMModulatorConfig},
+};
+
+fn modulate_symbols(c: &mut Criterion) {
+    let mut group = c.benchmark_group("modulate 1000 symbols");
+    for num_subcarriers in [64, 1024] {
+        let modulator = OFDMModulator::new(OFDMModulatorConfig {
+            num_subcarriers,
+            cyclic_prefix: CyclicPrefix::Samples(num_subcarriers / 4),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let data: Vec<u8> = (0..modulator.bytes_per_symbol()).map(|i| i as u8).collect();
+        let mut symbol = vec![0.0; modulator.get_symbol_length()];
+
+        group.throughput(Throughput::Bytes(1000 * data.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(num_subcarriers),
+            &data,
+            |b, data| {
+                b.iter(|| {
+                    for _ in 0..1000 {
+                        modulator
+                            .modulate_buffer_as_symbol(black_box(data), &mut symbol)
+                            .unwrap();
+                    }
+                    black_box(&symbol);
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+fn modulate_packet(c: &mut Criterion) {
+    let modulator = OFDMModulator::new(OFDMModulatorConfig {
+        num_subcarriers: 64,
+        cyclic_prefix: CyclicPrefix::Samples(16),
+        ..Default::default()
+    })
+    .unwrap();
+
+    // compare with and without the rayon feature to measure the parallel speedup
+    let data: Vec<u8> = (0..1000 * modulator.bytes_per_symbol())
+        .map(|i| i as u8)
+        .collect();
+
+    c.bench_function("modulate a packet of 1000 symbols", |b| {
+        b.iter(|| black_box(modulator.modulate_packet(black_box(&data)).unwrap()))
+    });
+}
+
+criterion_group!(benches, modulate_symbols, modulate_packet);
+criterion_main!(benches);
@@ -0,0 +1,46 @@
+use std::hint::black_box;
+
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use realfft::num_complex::Complex32;
+use software_modem::qam::{QAMModem, QAMOrder};
+
+fn modulate(c: &mut Criterion) {
+    let data: Vec<u8> = (0..100_000).map(|i| (i * 37 % 251) as u8).collect();
+
+    let mut group = c.benchmark_group("modulate 100000 bytes");
+    group.throughput(Throughput::Bytes(data.len() as u64));
+    for order in [QAMOrder::BPSK, QAMOrder::QAM4, QAMOrder::QAM16] {
+        let modem = QAMModem::new(order);
+        group.bench_with_input(BenchmarkId::from_parameter(order), &data, |b, data| {
+            b.iter(|| black_box(modem.modulate(black_box(data))))
+        });
+    }
+    group.finish();
+}
+
+fn demodulate(c: &mut Criterion) {
+    let modem = QAMModem::new(QAMOrder::QAM16);
+    // the same points as a custom constellation, which searches all of them
+    let points: Vec<Complex32> = (0..16u8)
+        .map(|value| modem.modulate(&[value << 4])[0])
+        .collect();
+    let searching = QAMModem::from_constellation(points);
+
+    let data: Vec<u8> = (0..100_000).map(|i| (i * 37 % 251) as u8).collect();
+    let symbols: Vec<Complex32> = modem
+        .modulate(&data)
+        .iter()
+        .enumerate()
+        .map(|(i, symbol)| symbol + Complex32::new((i % 7) as f32 * 0.1, (i % 5) as f32 * -0.1))
+        .collect();
+
+    c.bench_function("demodulate 200000 QAM-16 symbols", |b| {
+        b.iter(|| black_box(modem.demodulate(black_box(&symbols))))
+    });
+    c.bench_function("demodulate 200000 symbols of a custom constellation", |b| {
+        b.iter(|| black_box(searching.demodulate(black_box(&symbols))))
+    });
+}
+
+criterion_group!(benches, modulate, demodulate);
+criterion_main!(benches);
@@ -0,0 +1,47 @@
+use std::hint::black_box;
+
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use software_modem::ofdm::{
+    CyclicPrefix,
+    demodulator::{OFDMDemodulator, OFDMDemodulatorConfig},
+    modulator::{OFDMModulator, OFDMModulatorConfig},
+};
+
+fn packet_roundtrip(c: &mut Criterion) {
+    let mut group = c.benchmark_group("round trip of a packet of 100 symbols");
+    for num_subcarriers in [64, 1024] {
+        let cyclic_prefix = CyclicPrefix::Samples(num_subcarriers / 4);
+        let modulator = OFDMModulator::new(OFDMModulatorConfig {
+            num_subcarriers,
+            cyclic_prefix,
+            ..Default::default()
+        })
+        .unwrap();
+        let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+            num_subcarriers,
+            cyclic_prefix,
+            ..Default::default()
+        })
+        .unwrap();
+
+        let data: Vec<u8> = (0..100 * modulator.bytes_per_symbol())
+            .map(|i| (i * 37 % 251) as u8)
+            .collect();
+
+        group.throughput(Throughput::Bytes(data.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(num_subcarriers),
+            &data,
+            |b, data| {
+                b.iter(|| {
+                    let (samples, padding) = modulator.modulate_packet(black_box(data)).unwrap();
+                    black_box(demodulator.demodulate_packet(&samples, padding))
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, packet_roundtrip);
+criterion_main!(benches);
@@ -0,0 +1,81 @@
+//! Sends a message through a simulated noisy channel and receives it again.
+//!
+//! Run `cargo run --example loopback -- "Hello, OFDM!" 10` to send a message at 10 dB SNR
+//! (defaults to `Hello, OFDM!` at 12 dB).
+//! The message is framed, modulated behind a preamble, passed through the AWGN channel,
+//! found by the preamble, demodulated and deframed,
+//! and the bit error rate of the received frame is printed.
+
+use software_modem::{
+    channel::add_awgn,
+    frame::{deframe, frame},
+    metrics::bit_error_rate,
+    ofdm::{
+        CyclicPrefix,
+        demodulator::{OFDMDemodulator, OFDMDemodulatorConfig},
+        modulator::{OFDMModulator, OFDMModulatorConfig},
+    },
+};
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let message = args.next().unwrap_or_else(|| "Hello, OFDM!".to_string());
+    let snr_db: f32 = args
+        .next()
+        .map(|arg| arg.parse().expect("the SNR has to be a number of dB"))
+        .unwrap_or(12.0);
+
+    let modulator = OFDMModulator::new(OFDMModulatorConfig {
+        num_subcarriers: 64,
+        cyclic_prefix: CyclicPrefix::Samples(16),
+        convolutional_coding: true,
+        scrambling: true,
+        interleaving: true,
+        ..Default::default()
+    })
+    .unwrap();
+    let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+        num_subcarriers: 64,
+        cyclic_prefix: CyclicPrefix::Samples(16),
+        convolutional_coding: true,
+        scrambling: true,
+        interleaving: true,
+        ..Default::default()
+    })
+    .unwrap();
+
+    // the frame is modulated with padding 0, as its length field tells the receiver where it ends
+    let framed = frame(message.as_bytes(), 0);
+    let (packet, _) = modulator.modulate_packet(&framed).unwrap();
+
+    // some silence around the packet, which the receiver has to find on its own
+    let silence = vec![0.0; 1000];
+    let mut samples = silence.clone();
+    samples.extend(modulator.generate_preamble());
+    samples.extend(&packet);
+    samples.extend(silence);
+
+    // the SNR is relative to the mean power of the whole signal, silence included
+    add_awgn(&mut samples, snr_db, &mut rand::rng());
+    println!("Sent {:?} at {snr_db} dB SNR", message);
+
+    let Some(start) = demodulator.find_frame_start(&samples) else {
+        println!("No preamble found at {snr_db} dB SNR");
+        return;
+    };
+    let data_start = start + modulator.get_symbol_length();
+    let Some(received) = samples.get(data_start..data_start + packet.len()) else {
+        println!("The packet after the preamble at sample {start} is incomplete");
+        return;
+    };
+    let received = demodulator.demodulate_packet(received, 0);
+
+    println!(
+        "Bit error rate of the frame: {:.2e}",
+        bit_error_rate(&framed, &received)
+    );
+    match deframe(&received) {
+        Ok((seq, payload)) => println!("Received #{seq}: {:?}", String::from_utf8_lossy(&payload)),
+        Err(error) => println!("Frame lost: {error}"),
+    }
+}
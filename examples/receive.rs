@@ -0,0 +1,56 @@
+//! Receives messages sent by the `transmit` example through the microphone.
+//!
+//! Run `cargo run --example receive --features audio -- 10` to record for 10 seconds (default 5)
+//! and start the transmitter within that time.
+
+use std::time::Duration;
+
+use software_modem::{
+    audio::record,
+    ofdm::{
+        CyclicPrefix,
+        demodulator::{OFDMDemodulator, OFDMDemodulatorConfig},
+        streaming::StreamingDemodulator,
+    },
+};
+
+const SAMPLE_RATE: u32 = 48000;
+
+fn main() {
+    let seconds = std::env::args()
+        .nth(1)
+        .map(|arg| {
+            arg.parse()
+                .expect("the duration has to be a number of seconds")
+        })
+        .unwrap_or(5.0);
+
+    // has to match the configuration of the transmitter
+    let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+        num_subcarriers: 64,
+        cyclic_prefix: CyclicPrefix::Samples(32),
+        window_length: 16,
+        null_subcarrier_indices: [1, 2].into_iter().chain(43..=64).collect(),
+        convolutional_coding: true,
+        scrambling: true,
+        interleaving: true,
+        ..Default::default()
+    })
+    .unwrap();
+
+    println!("Recording for {seconds} seconds");
+    let samples =
+        record(Duration::from_secs_f64(seconds), SAMPLE_RATE).expect("failed to record the signal");
+
+    let mut streaming = StreamingDemodulator::new(demodulator, 4096);
+    streaming.push(&samples);
+
+    let mut received = false;
+    for (seq, payload) in streaming.payloads() {
+        println!("Received #{seq}: {:?}", String::from_utf8_lossy(&payload));
+        received = true;
+    }
+    if !received {
+        println!("No message received");
+    }
+}
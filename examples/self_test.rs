@@ -0,0 +1,153 @@
+//! Checks that a signal gets from the speakers to the microphone of this machine.
+//!
+//! Run `cargo run --example self_test --features audio` with the speakers audible to the microphone.
+//! A known pseudo random pattern is played as a frame while recording,
+//! the recording runs through the whole receive pipeline
+//! and the bit error rate and the SNR of the recording are reported.
+//! The process exits with an error code if the pattern was not received without errors.
+
+use std::{error::Error, fmt, thread, time::Duration};
+
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use software_modem::{
+    audio::{play, record},
+    error::RxError,
+    metrics::bit_error_rate,
+    ofdm::{
+        CyclicPrefix,
+        demodulator::{OFDMDemodulator, OFDMDemodulatorConfig},
+        modulator::{OFDMModulator, OFDMModulatorConfig},
+    },
+};
+
+const SAMPLE_RATE: u32 = 48000;
+/// Length of the test pattern in bytes.
+const PATTERN_LENGTH: usize = 256;
+
+/// Outcome of a [self_test].
+#[derive(Debug)]
+pub struct SelfTestReport {
+    /// Sample of the recording at which the frame was found, `None` if no preamble was detected.
+    pub frame_start: Option<usize>,
+    /// SNR of the recording in dB, the power of the frame against the noise recorded before it.
+    pub snr_db: Option<f32>,
+    /// Share of wrong bits of the received pattern, or the reason it could not be received.
+    pub bit_error_rate: Result<f64, RxError>,
+}
+
+impl SelfTestReport {
+    /// Returns whether the pattern was received without any bit error.
+    pub fn passed(&self) -> bool {
+        self.bit_error_rate == Ok(0.0)
+    }
+}
+
+impl fmt::Display for SelfTestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.frame_start {
+            Some(start) => writeln!(f, "Frame found at sample {start}")?,
+            None => writeln!(f, "No frame found")?,
+        }
+        if let Some(snr_db) = self.snr_db {
+            writeln!(f, "SNR of the recording: {snr_db:.1} dB")?;
+        }
+        match &self.bit_error_rate {
+            Ok(ber) => write!(f, "Bit error rate: {ber:.2e}")?,
+            Err(error) => write!(f, "Reception failed: {error}")?,
+        }
+        Ok(())
+    }
+}
+
+/// Plays a known pattern while recording it and runs the recording through the receive pipeline.
+///
+/// # Errors
+/// If the audio devices can not be opened or fail while playing or recording.
+pub fn self_test() -> Result<SelfTestReport, Box<dyn Error>> {
+    // the same band as the transmit and receive examples, only subcarriers 3 to 42 are used
+    let modulator = OFDMModulator::new(OFDMModulatorConfig {
+        num_subcarriers: 64,
+        cyclic_prefix: CyclicPrefix::Samples(32),
+        window_length: 16,
+        null_subcarrier_indices: [1, 2].into_iter().chain(43..=64).collect(),
+        convolutional_coding: true,
+        scrambling: true,
+        interleaving: true,
+        ..Default::default()
+    })?;
+    let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+        num_subcarriers: 64,
+        cyclic_prefix: CyclicPrefix::Samples(32),
+        window_length: 16,
+        null_subcarrier_indices: [1, 2].into_iter().chain(43..=64).collect(),
+        convolutional_coding: true,
+        scrambling: true,
+        interleaving: true,
+        ..Default::default()
+    })?;
+
+    let mut pattern = vec![0; PATTERN_LENGTH];
+    StdRng::seed_from_u64(0).fill(&mut pattern[..]);
+
+    // half a second of silence in front lets the devices settle and holds the noise floor
+    let silence = vec![0.0; SAMPLE_RATE as usize / 2];
+    let frame = modulator.build_frame(&pattern)?;
+    let mut samples = silence.clone();
+    samples.extend(&frame);
+    samples.extend(&silence);
+
+    // leave some headroom below full scale
+    let peak = samples.iter().map(|s| s.abs()).fold(0.0, f32::max);
+    samples.iter_mut().for_each(|s| *s *= 0.8 / peak);
+
+    // record a second longer than the signal to cover the latency of both devices
+    let duration = Duration::from_secs_f64(samples.len() as f64 / SAMPLE_RATE as f64 + 1.0);
+    let recording = thread::scope(|scope| {
+        let recorder = scope.spawn(|| record(duration, SAMPLE_RATE));
+        play(&samples, SAMPLE_RATE)?;
+        recorder.join().expect("the recording thread panicked")
+    })?;
+
+    Ok(analyze(&demodulator, &pattern, frame.len(), &recording))
+}
+
+/// Receives the pattern from the recording and measures the link.
+fn analyze(
+    demodulator: &OFDMDemodulator,
+    pattern: &[u8],
+    frame_length: usize,
+    recording: &[f32],
+) -> SelfTestReport {
+    let frame_start = demodulator.find_frame_start(recording);
+    let snr_db = frame_start.and_then(|start| {
+        // keep clear of the preamble, the timing of which is only known to a few samples
+        let noise = &recording[..start.saturating_sub(demodulator.get_symbol_length())];
+        let frame = &recording[start..recording.len().min(start + frame_length)];
+        let power = |samples: &[f32]| {
+            samples.iter().map(|s| s * s).sum::<f32>() / samples.len().max(1) as f32
+        };
+        let noise_power = power(noise);
+        (noise_power > 0.0).then(|| 10.0 * ((power(frame) - noise_power) / noise_power).log10())
+    });
+    let bit_error_rate = demodulator.receive_frame(recording).map(|received| {
+        // a pattern cut short counts its missing bits as errors
+        let missing = pattern.len().saturating_sub(received.len()) as f64 / pattern.len() as f64;
+        bit_error_rate(pattern, &received) * (1.0 - missing) + missing
+    });
+
+    SelfTestReport {
+        frame_start,
+        snr_db,
+        bit_error_rate,
+    }
+}
+
+fn main() {
+    println!("Playing a test frame, keep the speakers audible to the microphone");
+    let report = self_test().expect("failed to play or record the test frame");
+    println!("{report}");
+
+    if !report.passed() {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,52 @@
+//! Transmits a message through the speakers.
+//!
+//! Start the receiver first, then run
+//! `cargo run --example transmit --features audio -- "Hello, OFDM!"`.
+//! The signal stays between 1 kHz and 16 kHz, which most speakers and microphones cover.
+
+use software_modem::{
+    audio::play,
+    ofdm::{
+        CyclicPrefix,
+        modulator::{OFDMModulator, OFDMModulatorConfig},
+    },
+};
+
+const SAMPLE_RATE: u32 = 48000;
+
+fn main() {
+    let message = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "Hello, OFDM!".to_string());
+
+    // 375 Hz subcarrier spacing, only subcarriers 3 to 42 are used
+    let modulator = OFDMModulator::new(OFDMModulatorConfig {
+        num_subcarriers: 64,
+        cyclic_prefix: CyclicPrefix::Samples(32),
+        window_length: 16,
+        null_subcarrier_indices: [1, 2].into_iter().chain(43..=64).collect(),
+        convolutional_coding: true,
+        scrambling: true,
+        interleaving: true,
+        ..Default::default()
+    })
+    .unwrap();
+
+    // half a second of silence around the packet lets the devices settle
+    let silence = vec![0.0; SAMPLE_RATE as usize / 2];
+    let mut samples = silence.clone();
+    samples.extend(modulator.generate_preamble());
+    samples.extend(
+        modulator
+            .modulate_framed_packet(message.as_bytes(), 0)
+            .unwrap(),
+    );
+    samples.extend(silence);
+
+    // leave some headroom below full scale
+    let peak = samples.iter().map(|s| s.abs()).fold(0.0, f32::max);
+    samples.iter_mut().for_each(|s| *s *= 0.8 / peak);
+
+    println!("Transmitting {:?}", message);
+    play(&samples, SAMPLE_RATE).expect("failed to play the signal");
+}
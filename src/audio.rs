@@ -0,0 +1,192 @@
+//! This module provides playback and recording of samples on the default audio devices.
+//!
+//! It is only available with the `audio` feature enabled, using [cpal](https://crates.io/crates/cpal).
+//! Use [play] to send the output of the [OFDM Modulator](crate::ofdm::modulator) through the speakers
+//! and [record] to capture a signal from the microphone for the [OFDM Demodulator](crate::ofdm::demodulator).
+//!
+//! Both work with mono samples at the requested sample rate.
+//! If the device does not support that rate, the stream runs at the default rate of the device
+//! and the samples are [resampled](crate::dsp::resample) in between.
+
+use std::{sync::mpsc, time::Duration};
+
+use cpal::{
+    Device, Error, ErrorKind, FromSample, Sample, SampleFormat, SizedSample, StreamConfig,
+    SupportedStreamConfig, SupportedStreamConfigRange,
+    traits::{DeviceTrait, HostTrait, StreamTrait},
+};
+
+use crate::dsp::resample;
+
+/// Sample formats the streams are built with, in order of preference.
+const SAMPLE_FORMATS: [SampleFormat; 3] = [SampleFormat::F32, SampleFormat::I32, SampleFormat::I16];
+
+/// Plays the samples on the default output device and returns once they were played.
+///
+/// The samples are sent to every channel of the device and are expected within ±1.0,
+/// the output of the modulator has to be scaled down accordingly.
+///
+/// # Errors
+/// - [ErrorKind::DeviceNotAvailable] if there is no output device.
+/// - [ErrorKind::UnsupportedConfig] if the device supports none of the `f32`, `i32` and `i16` sample formats.
+/// - Any other error cpal reports while opening or running the stream.
+pub fn play(samples: &[f32], sample_rate: u32) -> Result<(), Error> {
+    let device = cpal::default_host()
+        .default_output_device()
+        .ok_or(Error::from(ErrorKind::DeviceNotAvailable))?;
+
+    let config = match negotiate_config(device.supported_output_configs()?, sample_rate) {
+        Some(config) => config,
+        None => device.default_output_config()?,
+    };
+    let samples = resample(samples, sample_rate, config.sample_rate());
+
+    match config.sample_format() {
+        SampleFormat::F32 => play_as::<f32>(&device, config.config(), samples),
+        SampleFormat::I32 => play_as::<i32>(&device, config.config(), samples),
+        SampleFormat::I16 => play_as::<i16>(&device, config.config(), samples),
+        _ => Err(ErrorKind::UnsupportedConfig.into()),
+    }
+}
+
+/// Records from the default input device for the given duration.
+///
+/// Only the first channel of the device is recorded.
+///
+/// # Errors
+/// - [ErrorKind::DeviceNotAvailable] if there is no input device.
+/// - [ErrorKind::UnsupportedConfig] if the device supports none of the `f32`, `i32` and `i16` sample formats.
+/// - Any other error cpal reports while opening or running the stream.
+pub fn record(duration: Duration, sample_rate: u32) -> Result<Vec<f32>, Error> {
+    let device = cpal::default_host()
+        .default_input_device()
+        .ok_or(Error::from(ErrorKind::DeviceNotAvailable))?;
+
+    let config = match negotiate_config(device.supported_input_configs()?, sample_rate) {
+        Some(config) => config,
+        None => device.default_input_config()?,
+    };
+    let num_frames = (duration.as_secs_f64() * config.sample_rate() as f64).round() as usize;
+
+    let recording = match config.sample_format() {
+        SampleFormat::F32 => record_as::<f32>(&device, config.config(), num_frames),
+        SampleFormat::I32 => record_as::<i32>(&device, config.config(), num_frames),
+        SampleFormat::I16 => record_as::<i16>(&device, config.config(), num_frames),
+        _ => Err(ErrorKind::UnsupportedConfig.into()),
+    }?;
+
+    Ok(resample(&recording, config.sample_rate(), sample_rate))
+}
+
+/// Plays the samples with a stream of the sample type `T`.
+fn play_as<T>(device: &Device, config: StreamConfig, samples: Vec<f32>) -> Result<(), Error>
+where
+    T: SizedSample + FromSample<f32>,
+{
+    let channels = config.channels as usize;
+    let (events, received) = mpsc::channel();
+    let errors = events.clone();
+
+    let mut position = 0;
+    let mut finished = false;
+    let stream = device.build_output_stream(
+        config,
+        move |data: &mut [T], _: &_| {
+            // the device asks for more after the last samples were handed over
+            if position >= samples.len() && !finished {
+                finished = true;
+                let _ = events.send(Ok(()));
+            }
+
+            for frame in data.chunks_mut(channels) {
+                let sample = samples.get(position).copied().unwrap_or(0.0);
+                frame.fill(T::from_sample(sample));
+                position += 1;
+            }
+        },
+        move |error| {
+            let _ = errors.send(Err(error));
+        },
+        None,
+    )?;
+    stream.play()?;
+
+    loop {
+        match received.recv() {
+            Ok(Err(error)) if is_fatal(&error) => return Err(error),
+            Ok(Err(_)) => continue,
+            Ok(Ok(())) | Err(_) => return Ok(()),
+        }
+    }
+}
+
+/// Records the first channel of `num_frames` frames with a stream of the sample type `T`.
+fn record_as<T>(device: &Device, config: StreamConfig, num_frames: usize) -> Result<Vec<f32>, Error>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let channels = config.channels as usize;
+    let (chunks, received) = mpsc::channel();
+    let errors = chunks.clone();
+
+    let stream = device.build_input_stream(
+        config,
+        move |data: &[T], _: &_| {
+            let chunk: Vec<f32> = data
+                .chunks(channels)
+                .map(|frame| f32::from_sample(frame[0]))
+                .collect();
+            let _ = chunks.send(Ok(chunk));
+        },
+        move |error| {
+            let _ = errors.send(Err(error));
+        },
+        None,
+    )?;
+    stream.play()?;
+
+    let mut recording = Vec::with_capacity(num_frames);
+    while recording.len() < num_frames {
+        match received.recv() {
+            Ok(Ok(chunk)) => recording.extend(chunk),
+            Ok(Err(error)) if is_fatal(&error) => return Err(error),
+            Ok(Err(_)) => continue,
+            Err(_) => break,
+        }
+    }
+    recording.truncate(num_frames);
+
+    Ok(recording)
+}
+
+/// Picks a supported configuration running at the sample rate,
+/// preferring the sample formats in the order of [SAMPLE_FORMATS] and as few channels as possible.
+fn negotiate_config(
+    ranges: impl Iterator<Item = SupportedStreamConfigRange>,
+    sample_rate: u32,
+) -> Option<SupportedStreamConfig> {
+    let ranges: Vec<SupportedStreamConfigRange> = ranges
+        .filter(|range| {
+            range.min_sample_rate() <= sample_rate && sample_rate <= range.max_sample_rate()
+        })
+        .collect();
+
+    SAMPLE_FORMATS.iter().find_map(|&format| {
+        ranges
+            .iter()
+            .filter(|range| range.sample_format() == format)
+            .min_by_key(|range| range.channels())
+            .and_then(|&range| range.try_with_sample_rate(sample_rate))
+    })
+}
+
+/// Whether a stream error ends the playback or recording.
+///
+/// Rerouting, buffer under- or overruns and a denied realtime priority only disturb the stream.
+fn is_fatal(error: &Error) -> bool {
+    !matches!(
+        error.kind(),
+        ErrorKind::DeviceChanged | ErrorKind::Xrun | ErrorKind::RealtimeDenied
+    )
+}
@@ -0,0 +1,321 @@
+//! This module provides packing of bit groups of arbitrary width into bytes.
+//!
+//! QAM symbols carry a fixed number of bits each, which does not have to divide a byte,
+//! e.g. the 6 bits of QAM-64. The [BitReader] splits a byte stream into such groups
+//! and the [BitWriter] joins them back into bytes.
+//!
+//! Bits are taken most significant bit first by default, across byte boundaries.
+//! With [BitOrder::LsbFirst], the bytes and groups are read from the least significant bit instead,
+//! e.g. the low nibble of a byte becomes the first group of 4 bits.
+//! A byte stream which does not divide into whole groups is padded with zero bits at the end,
+//! so the last group of the [BitReader] ends with zeros.
+//! The [BitWriter] pads an incomplete last byte with zeros as well,
+//! use [into_whole_bytes](BitWriter::into_whole_bytes) to drop it instead.
+//!
+//! # Example
+//! ```
+//! use software_modem::bits::{BitReader, BitWriter};
+//!
+//! let data: Vec<u8> = (0..=255).collect();
+//! for bits_per_symbol in [2, 4, 6, 8] {
+//!     let groups: Vec<u32> = BitReader::new(&data, bits_per_symbol).collect();
+//!     assert_eq!(groups.len(), (data.len() * 8).div_ceil(bits_per_symbol as usize));
+//!     assert!(groups.iter().all(|&group| group < 1 << bits_per_symbol));
+//!
+//!     let mut writer = BitWriter::new();
+//!     for &group in &groups {
+//!         writer.write(group, bits_per_symbol);
+//!     }
+//!     assert_eq!(writer.into_whole_bytes(), data);
+//! }
+//! ```
+
+/// The order in which the bits of a byte stream are split into groups.
+///
+/// # Example
+/// ```
+/// use software_modem::bits::{BitOrder, BitReader, BitWriter};
+///
+/// let msb_first: Vec<u32> = BitReader::new(&[0xab, 0xcd], 4).collect();
+/// assert_eq!(msb_first, [0xa, 0xb, 0xc, 0xd]);
+///
+/// let lsb_first: Vec<u32> = BitReader::new(&[0xab, 0xcd], 4)
+///     .with_bit_order(BitOrder::LsbFirst)
+///     .collect();
+/// assert_eq!(lsb_first, [0xb, 0xa, 0xd, 0xc]);
+///
+/// // the writer joins the groups in the same order
+/// let mut writer = BitWriter::new().with_bit_order(BitOrder::LsbFirst);
+/// for group in lsb_first {
+///     writer.write(group, 4);
+/// }
+/// assert_eq!(writer.into_bytes(), [0xab, 0xcd]);
+/// ```
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BitOrder {
+    /// The most significant bit of a byte comes first and is the most significant bit of its group.
+    #[default]
+    MsbFirst,
+    /// The least significant bit of a byte comes first and is the least significant bit of its group.
+    LsbFirst,
+}
+
+/// Iterates over the groups of `bits_per_group` bits of a byte slice.
+///
+/// Every group is returned in the lowest bits of a `u32`, the first bit of the group being the most significant one.
+/// The last group is padded with zero bits if the data does not divide into whole groups.
+///
+/// # Example
+/// ```
+/// use software_modem::bits::BitReader;
+///
+/// let groups: Vec<u32> = BitReader::new(&[0b1011_0011, 0b1100_0000], 6).collect();
+/// assert_eq!(groups, [0b101100, 0b111100, 0b000000]);
+///
+/// // the last group is padded with zeros
+/// let groups: Vec<u32> = BitReader::new(&[0xff], 6).collect();
+/// assert_eq!(groups, [0b111111, 0b110000]);
+/// ```
+pub struct BitReader<'a> {
+    data: &'a [u8],
+    bits_per_group: u32,
+    bit_order: BitOrder,
+    next_byte: usize,
+    /// Bits read from the data but not returned yet, in the lowest `num_buffered` bits.
+    buffer: u64,
+    num_buffered: u32,
+}
+
+impl<'a> BitReader<'a> {
+    /// Creates a reader returning groups of `bits_per_group` bits of `data`.
+    ///
+    /// # Panics
+    /// If `bits_per_group` is zero or larger than 32.
+    pub fn new(data: &'a [u8], bits_per_group: u32) -> Self {
+        check_num_bits(bits_per_group);
+
+        BitReader {
+            data,
+            bits_per_group,
+            bit_order: BitOrder::MsbFirst,
+            next_byte: 0,
+            buffer: 0,
+            num_buffered: 0,
+        }
+    }
+
+    /// Reads the bits in the given order instead of most significant bit first.
+    pub fn with_bit_order(mut self, bit_order: BitOrder) -> Self {
+        self.bit_order = bit_order;
+        self
+    }
+
+    /// Returns the next `num_bits` bits, independent of `bits_per_group`.
+    ///
+    /// Allows groups of varying width, e.g. for subcarriers with different QAM orders.
+    /// Like the groups of the iterator, the last read is padded with zero bits
+    /// and `None` is returned once all bits were read.
+    ///
+    /// # Panics
+    /// If `num_bits` is zero or larger than 32.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::bits::BitReader;
+    ///
+    /// let mut reader = BitReader::new(&[0b1011_0011], 2);
+    /// assert_eq!(reader.read(4), Some(0b1011));
+    /// assert_eq!(reader.next(), Some(0b00));
+    /// assert_eq!(reader.read(4), Some(0b1100));
+    /// assert_eq!(reader.read(4), None);
+    /// ```
+    pub fn read(&mut self, num_bits: u32) -> Option<u32> {
+        check_num_bits(num_bits);
+
+        if self.num_buffered == 0 && self.next_byte >= self.data.len() {
+            return None;
+        }
+
+        while self.num_buffered < num_bits {
+            if let Some(&byte) = self.data.get(self.next_byte) {
+                self.buffer = match self.bit_order {
+                    BitOrder::MsbFirst => (self.buffer << 8) | byte as u64,
+                    BitOrder::LsbFirst => self.buffer | ((byte as u64) << self.num_buffered),
+                };
+                self.num_buffered += 8;
+                self.next_byte += 1;
+            } else {
+                // pad the last group with zeros, which are already in place above the buffered bits if LSB first
+                if self.bit_order == BitOrder::MsbFirst {
+                    self.buffer <<= num_bits - self.num_buffered;
+                }
+                self.num_buffered = num_bits;
+            }
+        }
+
+        self.num_buffered -= num_bits;
+        let group = match self.bit_order {
+            BitOrder::MsbFirst => {
+                let group = (self.buffer >> self.num_buffered) & mask(num_bits);
+                self.buffer &= mask(self.num_buffered);
+                group
+            }
+            BitOrder::LsbFirst => {
+                let group = self.buffer & mask(num_bits);
+                self.buffer >>= num_bits;
+                group
+            }
+        };
+
+        Some(group as u32)
+    }
+}
+
+impl Iterator for BitReader<'_> {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<u32> {
+        self.read(self.bits_per_group)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = (self.num_buffered as usize + (self.data.len() - self.next_byte) * 8)
+            .div_ceil(self.bits_per_group as usize);
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for BitReader<'_> {}
+
+/// Joins groups of bits into bytes, the counterpart of the [BitReader].
+///
+/// # Example
+/// ```
+/// use software_modem::bits::BitWriter;
+///
+/// let mut writer = BitWriter::new();
+/// writer.write(0b101, 3);
+/// writer.write(0b11001, 5);
+/// writer.write(0b1, 1);
+///
+/// assert_eq!(writer.len(), 9);
+/// assert_eq!(writer.clone().into_bytes(), [0b1011_1001, 0b1000_0000]);
+/// assert_eq!(writer.into_whole_bytes(), [0b1011_1001]);
+/// ```
+#[derive(Default, Clone, Debug)]
+pub struct BitWriter {
+    bytes: Vec<u8>,
+    bit_order: BitOrder,
+    /// Bits not forming a whole byte yet, in the lowest `num_pending` bits.
+    pending: u64,
+    num_pending: u32,
+}
+
+impl BitWriter {
+    /// Creates an empty writer.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty writer with room for `num_bits` bits.
+    pub fn with_capacity(num_bits: usize) -> Self {
+        BitWriter {
+            bytes: Vec::with_capacity(num_bits.div_ceil(8)),
+            ..Default::default()
+        }
+    }
+
+    /// Creates a writer appending to the given bytes, keeping their allocation.
+    pub(crate) fn from_bytes(bytes: Vec<u8>) -> Self {
+        BitWriter {
+            bytes,
+            ..Default::default()
+        }
+    }
+
+    /// Joins the bits in the given order instead of most significant bit first.
+    pub fn with_bit_order(mut self, bit_order: BitOrder) -> Self {
+        self.bit_order = bit_order;
+        self
+    }
+
+    /// Appends the lowest `num_bits` bits of `value`, most significant bit first unless configured otherwise.
+    ///
+    /// # Panics
+    /// If `num_bits` is larger than 32.
+    pub fn write(&mut self, value: u32, num_bits: u32) {
+        if num_bits > u32::BITS {
+            panic!(
+                "Bits per group must not exceed {}, but got {}",
+                u32::BITS,
+                num_bits
+            );
+        }
+
+        let value = value as u64 & mask(num_bits);
+        match self.bit_order {
+            BitOrder::MsbFirst => {
+                self.pending = (self.pending << num_bits) | value;
+                self.num_pending += num_bits;
+                while self.num_pending >= 8 {
+                    self.num_pending -= 8;
+                    self.bytes.push((self.pending >> self.num_pending) as u8);
+                }
+                self.pending &= mask(self.num_pending);
+            }
+            BitOrder::LsbFirst => {
+                self.pending |= value << self.num_pending;
+                self.num_pending += num_bits;
+                while self.num_pending >= 8 {
+                    self.num_pending -= 8;
+                    self.bytes.push(self.pending as u8);
+                    self.pending >>= 8;
+                }
+            }
+        }
+    }
+
+    /// Returns the number of bits written.
+    pub fn len(&self) -> usize {
+        self.bytes.len() * 8 + self.num_pending as usize
+    }
+
+    /// Returns whether no bits were written.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the bytes, an incomplete last byte is padded with zero bits.
+    pub fn into_bytes(mut self) -> Vec<u8> {
+        if self.num_pending > 0 {
+            let last = match self.bit_order {
+                BitOrder::MsbFirst => self.pending << (8 - self.num_pending),
+                BitOrder::LsbFirst => self.pending,
+            };
+            self.bytes.push(last as u8);
+        }
+        self.bytes
+    }
+
+    /// Returns the complete bytes, the bits of an incomplete last byte are dropped.
+    pub fn into_whole_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+/// Panics unless `num_bits` is between 1 and 32.
+fn check_num_bits(num_bits: u32) {
+    if num_bits == 0 || num_bits > u32::BITS {
+        panic!(
+            "Bits per group must be between 1 and {}, but got {}",
+            u32::BITS,
+            num_bits
+        );
+    }
+}
+
+/// Returns a mask of the lowest `num_bits` bits.
+fn mask(num_bits: u32) -> u64 {
+    (1 << num_bits) - 1
+}
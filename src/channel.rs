@@ -0,0 +1,344 @@
+//! This module provides channel impairments for testing the modem.
+//!
+//! Pass the output of the [OFDM Modulator](crate::ofdm::modulator) through these functions
+//! before demodulating it, and compare the results with the [error rate metrics](crate::metrics).
+//! All random impairments take the random number generator as a parameter,
+//! so a seeded generator makes a simulation reproducible.
+//! No function of the crate draws from a thread-local or otherwise hidden generator.
+//! The modulator uses no randomness at all: the [scrambler](crate::scrambler), the preamble
+//! and the [selective mapping](crate::ofdm::slm) phases are fixed pseudo random sequences,
+//! so the same input always results in the same samples.
+//!
+//! # Example
+//! ```
+//! use rand::{SeedableRng, rngs::StdRng};
+//! use software_modem::channel::{NoiseProfile, add_awgn, add_colored_noise};
+//! use software_modem::ofdm::CyclicPrefix;
+//! use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+//!
+//! let modulator = OFDMModulator::new(OFDMModulatorConfig {
+//!     num_subcarriers: 64,
+//!     cyclic_prefix: CyclicPrefix::Samples(16),
+//!     num_slm_candidates: 8,
+//!     scrambling: true,
+//!     ..Default::default()
+//! })
+//! .unwrap();
+//!
+//! let simulate = |seed: u64| {
+//!     let (mut samples, _) = modulator.modulate_packet(b"The same seed, the same channel").unwrap();
+//!     let mut rng = StdRng::seed_from_u64(seed);
+//!     add_awgn(&mut samples, 15.0, &mut rng);
+//!     add_colored_noise(&mut samples, 20.0, &NoiseProfile::Pink, &mut rng);
+//!     samples
+//! };
+//!
+//! assert_eq!(simulate(7), simulate(7));
+//! assert_ne!(simulate(7), simulate(8));
+//! ```
+
+use rand::Rng;
+use realfft::RealFftPlanner;
+
+use crate::{
+    dsp::interpolate,
+    float::{Float, cast},
+};
+
+/// Adds white gaussian noise to the samples at the given signal to noise ratio.
+///
+/// The noise power is the mean power of `samples` divided by `10^(snr_db / 10)`.
+/// Silent input stays silent, as there is no signal power to scale the noise to.
+///
+/// # Example
+/// ```
+/// use rand::{SeedableRng, rngs::StdRng};
+/// use software_modem::channel::add_awgn;
+///
+/// let signal: Vec<f32> = (0..10_000).map(|n| (n as f32 * 0.1).sin()).collect();
+/// let mut noisy = signal.clone();
+/// add_awgn(&mut noisy, 10.0, &mut StdRng::seed_from_u64(42));
+///
+/// let signal_power = signal.iter().map(|s| s * s).sum::<f32>();
+/// let noise_power = signal.iter().zip(&noisy).map(|(s, n)| (n - s).powi(2)).sum::<f32>();
+/// let snr_db = 10.0 * (signal_power / noise_power).log10();
+/// assert!((snr_db - 10.0).abs() < 0.5);
+/// ```
+pub fn add_awgn<T: Float>(samples: &mut [T], snr_db: T, rng: &mut impl Rng) {
+    if samples.is_empty() {
+        return;
+    }
+
+    let signal_power = samples.iter().map(|&s| s * s).sum::<T>() / cast(samples.len() as f64);
+    let noise_power = signal_power / cast::<T>(10.0).powf(snr_db / cast(10.0));
+    let deviation = noise_power.sqrt();
+
+    for sample in samples.iter_mut() {
+        *sample = *sample + deviation * gaussian(rng);
+    }
+}
+
+/// Spectral shape of the noise of [add_colored_noise].
+///
+/// The shapes describe the power spectral density over the frequency `f`,
+/// acoustic channels e.g. pick up more noise at low frequencies than white noise has.
+#[derive(Clone, Debug, PartialEq)]
+pub enum NoiseProfile<T: Float = f32> {
+    /// The same power at every frequency, like [add_awgn].
+    White,
+    /// Power proportional to `1 / f`, falling by 3 dB per octave.
+    Pink,
+    /// Power proportional to `1 / f^2`, falling by 6 dB per octave.
+    Brown,
+    /// Relative power at equally spaced frequencies from DC to nyquist, interpolated linearly in between.
+    ///
+    /// A single value is white noise, e.g. `vec![1.0, 0.0]` falls linearly from full power at DC to none at nyquist.
+    Custom(Vec<T>),
+}
+
+impl<T: Float> NoiseProfile<T> {
+    /// Relative power at the frequency `f`, as a fraction of the nyquist frequency.
+    ///
+    /// The shapes which grow without bound towards DC carry no power at DC itself.
+    fn power(&self, f: T) -> T {
+        match self {
+            NoiseProfile::White => T::one(),
+            NoiseProfile::Pink if f > T::zero() => T::one() / f,
+            NoiseProfile::Brown if f > T::zero() => T::one() / (f * f),
+            NoiseProfile::Pink | NoiseProfile::Brown => T::zero(),
+            NoiseProfile::Custom(points) => {
+                let position = f * cast((points.len() - 1) as f64);
+                let index = position.floor().to_usize().unwrap().min(points.len() - 1);
+                let next = (index + 1).min(points.len() - 1);
+                let t = position - cast(index as f64);
+                points[index] + (points[next] - points[index]) * t
+            }
+        }
+    }
+}
+
+/// Adds gaussian noise with the spectral shape of `profile` to the samples at the given signal to noise ratio.
+///
+/// White gaussian noise is filtered in the frequency domain, every bin of its FFT is scaled by the square root
+/// of the power the profile has at its frequency, and the result is scaled to the mean power
+/// of `samples` divided by `10^(snr_db / 10)`, like [add_awgn] does.
+/// Silent input stays silent, as there is no signal power to scale the noise to.
+///
+/// # Panics
+/// If a [custom](NoiseProfile::Custom) profile holds no values, or one of them is negative.
+///
+/// # Example
+/// ```
+/// use rand::{SeedableRng, rngs::StdRng};
+/// use software_modem::channel::{NoiseProfile, add_colored_noise};
+/// use software_modem::dsp::spectrum;
+///
+/// let signal: Vec<f32> = (0..1 << 14).map(|n| (n as f32 * 0.1).sin()).collect();
+/// let mut rng = StdRng::seed_from_u64(7);
+///
+/// // mean power of the noise in the octave starting at the given bin, in dB
+/// let octave_db = |noise: &[f32], start: usize| {
+///     let magnitudes = spectrum(noise);
+///     let power = magnitudes[start..2 * start].iter().map(|m| m * m).sum::<f32>() / start as f32;
+///     10.0 * power.log10()
+/// };
+///
+/// for (profile, slope_db) in [(NoiseProfile::White, 0.0), (NoiseProfile::Pink, -3.0), (NoiseProfile::Brown, -6.0)] {
+///     let mut noisy = signal.clone();
+///     add_colored_noise(&mut noisy, 10.0, &profile, &mut rng);
+///     let noise: Vec<f32> = noisy.iter().zip(&signal).map(|(n, s)| n - s).collect();
+///
+///     // the noise power is set by the SNR, whatever the shape
+///     let signal_power = signal.iter().map(|s| s * s).sum::<f32>();
+///     let noise_power = noise.iter().map(|n| n * n).sum::<f32>();
+///     assert!((10.0 * (signal_power / noise_power).log10() - 10.0).abs() < 1e-3);
+///
+///     // measured over two octaves, as the power of a single noise spectrum scatters
+///     for start in [128, 256, 512, 1024] {
+///         let slope = (octave_db(&noise, 4 * start) - octave_db(&noise, start)) / 2.0;
+///         assert!((slope - slope_db).abs() < 0.75, "{profile:?}: {slope} dB per octave");
+///     }
+/// }
+///
+/// // the noise of a custom profile follows its values
+/// let mut noisy = signal.clone();
+/// add_colored_noise(&mut noisy, 0.0, &NoiseProfile::Custom(vec![0.0, 1.0]), &mut rng);
+/// let noise: Vec<f32> = noisy.iter().zip(&signal).map(|(n, s)| n - s).collect();
+/// assert!((octave_db(&noise, 2048) - octave_db(&noise, 1024) - 10.0 * 2f32.log10()).abs() < 1.0);
+/// ```
+pub fn add_colored_noise<T: Float>(
+    samples: &mut [T],
+    snr_db: T,
+    profile: &NoiseProfile<T>,
+    rng: &mut impl Rng,
+) {
+    if let NoiseProfile::Custom(points) = profile
+        && (points.is_empty() || points.iter().any(|&point| point < T::zero()))
+    {
+        panic!("Custom noise profile must hold at least one value and no negative ones");
+    }
+
+    let signal_power =
+        samples.iter().map(|&s| s * s).sum::<T>() / cast(samples.len().max(1) as f64);
+    if signal_power == T::zero() {
+        return;
+    }
+
+    let mut planner = RealFftPlanner::<T>::new();
+    let forward = planner.plan_fft_forward(samples.len());
+    let inverse = planner.plan_fft_inverse(samples.len());
+
+    let mut noise: Vec<T> = (0..samples.len()).map(|_| gaussian(rng)).collect();
+    let mut spectrum = forward.make_output_vec();
+    forward.process(&mut noise, &mut spectrum).unwrap();
+
+    let nyquist_bin: T = cast((samples.len() as f64 / 2.0).max(1.0));
+    for (bin, value) in spectrum.iter_mut().enumerate() {
+        let gain = profile.power(cast::<T>(bin as f64) / nyquist_bin).sqrt();
+        *value = *value * gain;
+    }
+    // the inverse real FFT ignores the imaginary parts of DC and nyquist
+    inverse.process(&mut spectrum, &mut noise).unwrap();
+
+    let noise_power = noise.iter().map(|&n| n * n).sum::<T>() / cast(samples.len() as f64);
+    if noise_power == T::zero() {
+        return;
+    }
+    let target_power = signal_power / cast::<T>(10.0).powf(snr_db / cast(10.0));
+    let scale = (target_power / noise_power).sqrt();
+
+    for (sample, noise) in samples.iter_mut().zip(noise) {
+        *sample = *sample + noise * scale;
+    }
+}
+
+/// Draws a sample of the standard normal distribution with the Box-Muller transform.
+fn gaussian<T: Float>(rng: &mut impl Rng) -> T {
+    // 1 - [0, 1) keeps the logarithm finite
+    let u1: f64 = 1.0 - rng.random::<f64>();
+    let u2: f64 = rng.random();
+    cast((-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos())
+}
+
+/// Delays the samples by a possibly fractional number of samples.
+///
+/// The output is `ceil(delay)` samples longer than the input, starting with the silence before the signal.
+/// Integer delays just shift the signal, fractional delays interpolate between the samples
+/// with a Hann windowed sinc of 32 taps, so signal content close to the nyquist frequency and samples
+/// close to the edges are attenuated.
+///
+/// # Panics
+/// If `delay` is negative.
+///
+/// # Example
+/// ```
+/// use software_modem::channel::apply_delay;
+///
+/// let signal: Vec<f32> = (0..200).map(|n| (n as f32 * 0.2).sin()).collect();
+///
+/// // an integer delay shifts the signal
+/// let delayed = apply_delay(&signal, 3.0);
+/// assert_eq!(delayed.len(), 203);
+/// assert!(delayed[..3].iter().all(|&s| s == 0.0));
+/// for (delayed, original) in delayed[3..].iter().zip(&signal) {
+///     assert!((delayed - original).abs() < 1e-6);
+/// }
+///
+/// // a fractional delay interpolates between the samples
+/// let delayed = apply_delay(&signal, 2.5);
+/// for n in 20..180 {
+///     let expected = ((n as f32 - 2.5) * 0.2).sin();
+///     assert!((delayed[n] - expected).abs() < 1e-2);
+/// }
+/// ```
+///
+/// Combined with the [preamble](crate::ofdm::preamble), the demodulator finds a delayed packet:
+/// ```
+/// use software_modem::channel::apply_delay;
+/// use software_modem::ofdm::CyclicPrefix;
+/// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+/// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+///
+/// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+///     num_subcarriers: 64,
+///     cyclic_prefix: CyclicPrefix::Samples(16),
+///     ..Default::default()
+/// })
+/// .unwrap();
+/// let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+///     num_subcarriers: 64,
+///     cyclic_prefix: CyclicPrefix::Samples(16),
+///     ..Default::default()
+/// })
+/// .unwrap();
+///
+/// let payload = b"Delayed, but found";
+/// let (packet, padding) = modulator.modulate_packet(payload).unwrap();
+/// let signal = [modulator.generate_preamble(), packet.clone()].concat();
+///
+/// let received = apply_delay(&signal, 100.4);
+/// let start = demodulator.find_frame_start(&received).unwrap();
+/// assert!((start as i32 - 100).abs() <= 2);
+///
+/// let data_start = start + modulator.get_symbol_length();
+/// let data = &received[data_start..data_start + packet.len()];
+/// assert_eq!(demodulator.demodulate_packet(data, padding), payload);
+/// ```
+pub fn apply_delay<T: Float>(samples: &[T], delay: T) -> Vec<T> {
+    assert!(delay >= T::zero(), "Delay must not be negative");
+
+    let whole = delay.floor();
+    let fraction = delay - whole;
+    let shift = whole.to_usize().unwrap();
+    let output_length = samples.len() + delay.ceil().to_usize().unwrap();
+
+    let mut output = vec![T::zero(); output_length];
+    if fraction == T::zero() {
+        output[shift..].copy_from_slice(samples);
+        return output;
+    }
+
+    let fraction = fraction.to_f64().unwrap();
+    for (n, output) in output.iter_mut().enumerate().skip(shift) {
+        // output[n] = x(n - delay) = x((n - shift) - fraction)
+        *output = interpolate(samples, (n - shift) as f64 - fraction);
+    }
+
+    output
+}
+
+/// Resamples the signal as if the sample clock of the receiver ran `sfo` times faster than the one of the transmitter.
+///
+/// A relative sampling frequency offset of `sfo`, e.g. `1e-4` for 100 ppm, stretches the signal by `1 + sfo`,
+/// so the received sample `n` is the transmitted signal at `n / (1 + sfo)`.
+/// Over a long packet the symbols drift away from their nominal positions, which rotates the subcarriers
+/// in proportion to their frequency, see
+/// [estimate_sfo](crate::ofdm::demodulator::OFDMDemodulator::estimate_sfo).
+/// The samples are interpolated like the fractional delays of [apply_delay].
+///
+/// # Panics
+/// If `sfo` is not above `-1.0`.
+///
+/// # Example
+/// ```
+/// use software_modem::channel::apply_sfo;
+///
+/// let signal: Vec<f32> = (0..10000).map(|n| (n as f32 * 0.05).sin()).collect();
+/// let received = apply_sfo(&signal, 1e-3);
+/// assert_eq!(received.len(), 10010);
+///
+/// // the sine is stretched to a slightly lower frequency
+/// for n in [1000, 5000, 9000] {
+///     let expected = (n as f32 / 1.001 * 0.05).sin();
+///     assert!((received[n] - expected).abs() < 1e-2);
+/// }
+/// ```
+pub fn apply_sfo<T: Float>(samples: &[T], sfo: T) -> Vec<T> {
+    let stretch = 1.0 + sfo.to_f64().unwrap();
+    assert!(stretch > 0.0, "Sampling frequency offset must be above -1");
+
+    let output_length = (samples.len() as f64 * stretch).round() as usize;
+    (0..output_length)
+        .map(|n| interpolate(samples, n as f64 / stretch))
+        .collect()
+}
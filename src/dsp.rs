@@ -0,0 +1,685 @@
+//! This module provides signal processing helpers around the OFDM core.
+//!
+//! The [OFDM Modulator](crate::ofdm::modulator) produces a real baseband signal starting at DC,
+//! which can not be passed through AC-coupled hardware or an audio channel with a DC block.
+//! Use [upconvert] or [upconvert_iq] to mix a baseband signal up to an audio carrier frequency
+//! and [downconvert] or [downconvert_iq] to bring a received passband signal back to baseband.
+//! [clip_peaks] limits the peaks of a signal, e.g. for hardware with little headroom,
+//! and [quantize] rounds it to the bit depth of a converter.
+//! [spectrum] shows which frequencies a signal occupies, e.g. to check the subcarriers of a modulated symbol.
+//! [generate_chirp] and [detect_chirp] provide a swept-frequency sync signal,
+//! an alternative to the [preamble](crate::ofdm::preamble) for acoustic links.
+//! [hilbert] forms the analytic signal of a real capture for complex processing.
+//! [resample] converts a signal between sample rates, e.g. when a sound card does not run at the rate of the modem.
+
+use std::f32::consts::PI;
+
+use realfft::{
+    RealFftPlanner,
+    num_complex::{Complex, Complex32},
+};
+
+use crate::float::{Float, cast};
+
+/// Number of taps of the low-pass filter used after downconversion.
+const DOWNCONVERT_FILTER_TAPS: usize = 129;
+
+/// Normalized correlation with the chirp above which [detect_chirp] reports a detection.
+const CHIRP_DETECTION_THRESHOLD: f32 = 0.3;
+/// Energy of the samples overlapped by the chirp relative to the energy of the chirp,
+/// below which [detect_chirp] skips the position.
+const CHIRP_ENERGY_FLOOR: f64 = 1e-6;
+
+/// Zero crossings of the interpolation kernel of [resample] on either side of its center, at the lower sample rate.
+const RESAMPLE_ZERO_CROSSINGS: usize = 64;
+
+/// Largest number of phases between two input samples [resample] computes its kernel for.
+const RESAMPLE_PHASES: usize = 1024;
+
+/// Half the length of the windowed sinc of [interpolate].
+const INTERPOLATION_HALF_LENGTH: usize = 16;
+
+/// Mixes a real baseband signal up to the given carrier frequency.
+///
+/// The baseband signal is treated as the in-phase component of a complex baseband signal,
+/// so this is the same as calling [upconvert_iq] with a zero quadrature component.
+/// The result is a double sideband signal around `carrier_hz`,
+/// so the bandwidth of the baseband signal has to stay below `carrier_hz`
+/// and `carrier_hz` plus the bandwidth has to stay below the nyquist frequency.
+///
+/// # Example
+/// ```
+/// use software_modem::dsp::{downconvert, upconvert};
+///
+/// let sample_rate = 8000.0;
+/// let baseband: Vec<f32> = (0..2000)
+///     .map(|n| (2.0 * std::f32::consts::PI * 200.0 * n as f32 / sample_rate).sin())
+///     .collect();
+///
+/// let passband = upconvert(&baseband, sample_rate, 1500.0);
+/// let recovered = downconvert(&passband, sample_rate, 1500.0);
+///
+/// // skip the settling time of the filter at both ends
+/// for (recovered, original) in recovered[200..1800].iter().zip(baseband[200..1800].iter()) {
+///     assert!((recovered - original).abs() < 1e-2);
+/// }
+/// ```
+pub fn upconvert(samples: &[f32], sample_rate: f32, carrier_hz: f32) -> Vec<f32> {
+    let baseband: Vec<Complex32> = samples.iter().map(|&s| Complex32::new(s, 0.0)).collect();
+    upconvert_iq(&baseband, sample_rate, carrier_hz)
+}
+
+/// Mixes a complex baseband (I/Q) signal up to the given carrier frequency.
+///
+/// The result is the real passband signal `I * cos(wt) - Q * sin(wt)`.
+///
+/// # Example
+/// ```
+/// use software_modem::dsp::{downconvert_iq, upconvert_iq};
+/// use realfft::{RealFftPlanner, num_complex::Complex32};
+///
+/// let sample_rate = 8000.0;
+/// let baseband: Vec<Complex32> = (0..2000)
+///     .map(|n| Complex32::from_polar(0.5, 2.0 * std::f32::consts::PI * 100.0 * n as f32 / sample_rate))
+///     .collect();
+///
+/// let passband = upconvert_iq(&baseband, sample_rate, 2000.0);
+/// let recovered = downconvert_iq(&passband, sample_rate, 2000.0);
+///
+/// for (recovered, original) in recovered[200..1800].iter().zip(baseband[200..1800].iter()) {
+///     assert!((recovered - original).norm() < 1e-2);
+/// }
+/// ```
+pub fn upconvert_iq(samples: &[Complex32], sample_rate: f32, carrier_hz: f32) -> Vec<f32> {
+    samples
+        .iter()
+        .enumerate()
+        .map(|(n, &sample)| (sample * carrier(n, sample_rate, carrier_hz)).re)
+        .collect()
+}
+
+/// Mixes a real passband signal at the given carrier frequency down to a real baseband signal.
+///
+/// This is the in-phase component of [downconvert_iq], see [upconvert] for an example.
+pub fn downconvert(samples: &[f32], sample_rate: f32, carrier_hz: f32) -> Vec<f32> {
+    downconvert_iq(samples, sample_rate, carrier_hz)
+        .into_iter()
+        .map(|sample| sample.re)
+        .collect()
+}
+
+/// Mixes a real passband signal at the given carrier frequency down to a complex baseband (I/Q) signal.
+///
+/// The signal is multiplied with `2 * e^(-jwt)` and low-pass filtered at `carrier_hz`
+/// to remove the image at twice the carrier frequency.
+/// The filter needs a few dozen samples to settle, so the first and last samples are less accurate.
+pub fn downconvert_iq(samples: &[f32], sample_rate: f32, carrier_hz: f32) -> Vec<Complex32> {
+    let mixed: Vec<Complex32> = samples
+        .iter()
+        .enumerate()
+        .map(|(n, &sample)| carrier(n, sample_rate, carrier_hz).conj() * 2.0 * sample)
+        .collect();
+
+    let taps = windowed_sinc(carrier_hz / sample_rate, DOWNCONVERT_FILTER_TAPS);
+    filter(&mixed, &taps)
+}
+
+/// Clips every sample to `±threshold` and returns the error vector magnitude the clipping causes.
+///
+/// Clipping is the simplest way to lower the peak to average power ratio of an OFDM signal,
+/// but unlike [selective mapping](crate::ofdm::slm) it distorts the signal within the band,
+/// which the demodulator sees as additional noise.
+/// The returned EVM is the RMS of the clipped off part divided by the RMS of the original signal,
+/// `0.0` if nothing was clipped.
+///
+/// # Example
+/// ```
+/// use software_modem::dsp::clip_peaks;
+///
+/// let mut samples = [0.5, -2.0, 1.0, 3.0];
+/// let evm = clip_peaks(&mut samples, 1.0);
+///
+/// assert_eq!(samples, [0.5, -1.0, 1.0, 1.0]);
+/// // clipped off 1.0 and 2.0 of a total power of 14.25
+/// assert!((evm - (5.0f32 / 14.25).sqrt()).abs() < 1e-6);
+/// ```
+pub fn clip_peaks<T: Float>(samples: &mut [T], threshold: T) -> T {
+    let mut signal_energy = T::zero();
+    let mut error_energy = T::zero();
+    for sample in samples.iter_mut() {
+        let clipped = sample.max(-threshold).min(threshold);
+        signal_energy = signal_energy + *sample * *sample;
+        error_energy = error_energy + (*sample - clipped).powi(2);
+        *sample = clipped;
+    }
+
+    if signal_energy > T::zero() {
+        (error_energy / signal_energy).sqrt()
+    } else {
+        T::zero()
+    }
+}
+
+/// Rounds every sample to the levels of a `bits` bit converter and returns the error vector magnitude this causes.
+///
+/// Models the DAC of a transmitter or the ADC of a receiver, e.g. to see how the bit error rate degrades with the bit depth.
+/// The levels are the multiples of `full_scale / 2^(bits - 1)` from `-full_scale` up to one step below `full_scale`,
+/// like the codes of a two's complement converter, and samples beyond them saturate at the outermost level.
+/// Every bit halves the step and lowers the EVM of a signal within full scale by about 6 dB.
+/// The returned EVM is the RMS of the quantization error divided by the RMS of the original signal,
+/// `0.0` for a silent signal.
+///
+/// # Panics
+/// If `bits` is zero or larger than 32.
+///
+/// # Example
+/// ```
+/// use software_modem::dsp::quantize;
+///
+/// // 2 bits give the levels -1.0, -0.5, 0.0 and 0.5
+/// let mut samples = [0.3, 2.0, -3.0, -0.2];
+/// quantize(&mut samples, 2, 1.0);
+/// assert_eq!(samples, [0.5, 0.5, -1.0, 0.0]);
+///
+/// let signal: Vec<f32> = (0..10000).map(|n| 0.7 * (n as f32 * 0.01234).sin()).collect();
+/// let evm_at = |bits: u32| quantize(&mut signal.clone(), bits, 1.0);
+///
+/// // 4 more bits are a 16 times finer step
+/// let ratio = evm_at(8) / evm_at(12);
+/// assert!(ratio > 12.0 && ratio < 20.0);
+/// assert!(evm_at(8) < 0.01);
+/// ```
+pub fn quantize<T: Float>(samples: &mut [T], bits: u32, full_scale: T) -> T {
+    if bits == 0 || bits > 32 {
+        panic!("Quantization bit depth must be between 1 and 32, but got {bits}");
+    }
+
+    let num_levels: T = cast((1u64 << (bits - 1)) as f64);
+    let step = full_scale / num_levels;
+    let mut signal_energy = T::zero();
+    let mut error_energy = T::zero();
+    for sample in samples.iter_mut() {
+        let code = (*sample / step)
+            .round()
+            .max(-num_levels)
+            .min(num_levels - T::one());
+        let quantized = code * step;
+        signal_energy = signal_energy + *sample * *sample;
+        error_energy = error_energy + (*sample - quantized).powi(2);
+        *sample = quantized;
+    }
+
+    if signal_energy > T::zero() {
+        (error_energy / signal_energy).sqrt()
+    } else {
+        T::zero()
+    }
+}
+
+/// Returns the magnitude of every frequency bin of a real signal, from DC to nyquist.
+///
+/// The `samples.len() / 2 + 1` magnitudes are divided by the number of samples,
+/// so a cosine of amplitude `A` at a bin frequency shows up as `A / 2` in its bin.
+/// The bins are `sample_rate / samples.len()` Hz apart, use `20 * log10` of a magnitude for dB.
+/// Transforming exactly the `2 * num_subcarriers` samples of an OFDM symbol without its cyclic prefix
+/// puts subcarrier `k` into bin `k`.
+/// An empty input results in an empty output.
+///
+/// # Example
+/// ```
+/// use software_modem::dsp::spectrum;
+/// use software_modem::ofdm::CyclicPrefix;
+/// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+///
+/// // only the subcarriers 5 to 52 are used, DC is never used
+/// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+///     num_subcarriers: 64,
+///     cyclic_prefix: CyclicPrefix::Samples(0),
+///     null_subcarrier_indices: (1..5).chain(53..64).collect(),
+///     ..Default::default()
+/// })
+/// .unwrap();
+/// let payload: Vec<u8> = (0..modulator.bits_per_symbol() / 8).map(|i| (i * 37) as u8).collect();
+/// let (samples, _) = modulator.modulate_packet(&payload).unwrap();
+/// assert_eq!(samples.len(), 128);
+///
+/// let magnitudes: Vec<f32> = spectrum(&samples);
+/// assert_eq!(magnitudes.len(), 65);
+/// let peak = magnitudes.iter().copied().fold(0.0, f32::max);
+/// for (bin, &magnitude) in magnitudes.iter().enumerate() {
+///     if (5..=52).contains(&bin) {
+///         assert!(magnitude > peak / 10.0, "bin {bin} carries no energy");
+///     } else {
+///         assert!(magnitude < peak * 1e-5, "bin {bin} is not empty");
+///     }
+/// }
+///
+/// assert!(spectrum::<f32>(&[]).is_empty());
+/// ```
+pub fn spectrum<T: Float>(samples: &[T]) -> Vec<T> {
+    if samples.is_empty() {
+        return Vec::new();
+    }
+
+    let forward = RealFftPlanner::<T>::new().plan_fft_forward(samples.len());
+    let mut input = samples.to_vec();
+    let mut bins = forward.make_output_vec();
+    forward.process(&mut input, &mut bins).unwrap();
+
+    let scale = T::one() / cast(samples.len() as f64);
+    bins.iter().map(|bin| bin.norm() * scale).collect()
+}
+
+/// Generates a linear chirp of amplitude `1.0`, sweeping from `start_hz` to `end_hz` over `duration_samples` samples.
+///
+/// A chirp sent ahead of the data can be found with [detect_chirp].
+/// Its correlation peak is as narrow as the one of a short pulse of the same bandwidth,
+/// but carries the energy of the whole chirp, so it stands out of the noise far better.
+/// A frequency offset, e.g. from the Doppler shift of a moving speaker, only shifts the peak slightly in time
+/// instead of destroying it.
+/// `end_hz` may be below `start_hz` for a down-chirp, both must stay below the nyquist frequency.
+///
+/// # Example
+/// ```
+/// use software_modem::dsp::{generate_chirp, spectrum};
+///
+/// let chirp = generate_chirp(1000.0, 3000.0, 800, 8000.0);
+/// assert_eq!(chirp.len(), 800);
+/// assert!(chirp.iter().all(|s| s.abs() <= 1.0));
+///
+/// // the bins are 10 Hz apart and only the swept band is occupied
+/// let magnitudes = spectrum(&chirp);
+/// let peak = magnitudes.iter().copied().fold(0.0, f32::max);
+/// assert!(magnitudes[150..250].iter().all(|&m| m > peak / 10.0));
+/// assert!(magnitudes[..50].iter().chain(&magnitudes[350..]).all(|&m| m < peak / 10.0));
+/// ```
+pub fn generate_chirp(
+    start_hz: f32,
+    end_hz: f32,
+    duration_samples: usize,
+    sample_rate: f32,
+) -> Vec<f32> {
+    let duration = duration_samples as f64 / sample_rate as f64;
+    let sweep_rate = (end_hz - start_hz) as f64 / duration;
+
+    (0..duration_samples)
+        .map(|n| {
+            // wrap the phase in double precision like the carrier of the upconversion
+            let t = n as f64 / sample_rate as f64;
+            let cycles = start_hz as f64 * t + 0.5 * sweep_rate * t * t;
+            (2.0 * std::f64::consts::PI * cycles.fract()).sin() as f32
+        })
+        .collect()
+}
+
+/// Finds the start of a [chirp](generate_chirp) in `samples` with a matched filter.
+///
+/// The samples are cross-correlated with the chirp and every correlation is normalized
+/// by the energy of the chirp and of the samples it overlaps,
+/// so the result does not depend on the level of the received signal.
+/// Positions where the samples hold less than a millionth of the energy of the chirp, e.g. exact silence, are skipped.
+/// Returns the index at which the chirp starts if the peak of the normalized correlation exceeds `0.3`,
+/// which a chirp of a few hundred samples still reaches well below 0 dB SNR,
+/// while noise alone stays below it.
+/// `None` is returned otherwise, and if `samples` is shorter than the chirp or the chirp is empty.
+///
+/// # Example
+/// ```
+/// use rand::{SeedableRng, rngs::StdRng};
+/// use software_modem::channel::add_awgn;
+/// use software_modem::dsp::{detect_chirp, generate_chirp};
+///
+/// let chirp = generate_chirp(1000.0, 3000.0, 800, 8000.0);
+/// let mut samples = [vec![0.0; 1234], chirp.clone(), vec![0.0; 1000]].concat();
+///
+/// // over the whole buffer, the noise is about 4 dB stronger than the chirp
+/// let mut rng = StdRng::seed_from_u64(3);
+/// add_awgn(&mut samples, -10.0, &mut rng);
+///
+/// let start = detect_chirp(&samples, &chirp).unwrap();
+/// assert!((start as i32 - 1234).abs() <= 2);
+///
+/// // noise alone is no chirp, add_awgn needs a little signal to scale the noise to
+/// let mut noise = vec![0.0; 3000];
+/// noise[0] = 1.0;
+/// add_awgn(&mut noise, -30.0, &mut rng);
+/// assert_eq!(detect_chirp(&noise, &chirp), None);
+///
+/// assert_eq!(detect_chirp(&chirp[..799], &chirp), None);
+///
+/// // without noise, the exact silence around the chirp is no match either
+/// let samples = [vec![0.0; 1234], chirp.clone(), vec![0.0; 1000]].concat();
+/// assert_eq!(detect_chirp(&samples, &chirp), Some(1234));
+/// let samples = [chirp.clone(), vec![0.0; 3000]].concat();
+/// assert_eq!(detect_chirp(&samples, &chirp), Some(0));
+/// ```
+pub fn detect_chirp(samples: &[f32], chirp: &[f32]) -> Option<usize> {
+    if chirp.is_empty() || samples.len() < chirp.len() {
+        return None;
+    }
+
+    // correlate in the frequency domain, the chirp is zero padded to the length of the samples,
+    // so the lags at which it lies within the samples do not wrap around
+    let mut planner = RealFftPlanner::<f32>::new();
+    let forward = planner.plan_fft_forward(samples.len());
+    let inverse = planner.plan_fft_inverse(samples.len());
+
+    let mut input = samples.to_vec();
+    let mut samples_spectrum = forward.make_output_vec();
+    forward.process(&mut input, &mut samples_spectrum).unwrap();
+
+    let mut padded_chirp = chirp.to_vec();
+    padded_chirp.resize(samples.len(), 0.0);
+    let mut chirp_spectrum = forward.make_output_vec();
+    forward
+        .process(&mut padded_chirp, &mut chirp_spectrum)
+        .unwrap();
+
+    let scale = 1.0 / samples.len() as f32;
+    let mut products: Vec<Complex32> = samples_spectrum
+        .iter()
+        .zip(&chirp_spectrum)
+        .map(|(sample, chirp)| sample * chirp.conj() * scale)
+        .collect();
+    // the imaginary parts of DC and nyquist are only rounding errors, which the inverse FFT rejects
+    products[0].im = 0.0;
+    if samples.len().is_multiple_of(2) {
+        let last = products.len() - 1;
+        products[last].im = 0.0;
+    }
+    let mut correlation = inverse.make_output_vec();
+    inverse.process(&mut products, &mut correlation).unwrap();
+
+    let chirp_energy: f64 = chirp.iter().map(|&s| (s as f64).powi(2)).sum();
+    // prefix sums instead of a running sum, which would leave residue behind a signal,
+    // a window of exact zeros gives exactly zero energy
+    let mut prefix_energy = Vec::with_capacity(samples.len() + 1);
+    prefix_energy.push(0.0);
+    for (i, &sample) in samples.iter().enumerate() {
+        prefix_energy.push(prefix_energy[i] + (sample as f64).powi(2));
+    }
+
+    let mut best = (0, 0.0);
+    for start in 0..=samples.len() - chirp.len() {
+        let window_energy = prefix_energy[start + chirp.len()] - prefix_energy[start];
+        if window_energy > CHIRP_ENERGY_FLOOR * chirp_energy {
+            let normalized =
+                (correlation[start] as f64 / (chirp_energy * window_energy).sqrt()).min(1.0);
+            if normalized > best.1 {
+                best = (start, normalized);
+            }
+        }
+    }
+
+    (best.1 > CHIRP_DETECTION_THRESHOLD as f64).then_some(best.0)
+}
+
+/// Converts a signal from `from_rate` to `to_rate` Hz by windowed-sinc interpolation.
+///
+/// Every output sample is interpolated from the input samples around its position in time,
+/// weighted with a sinc kernel under a Blackman window spanning 64 zero crossings on either side.
+/// The positions of a ratio of small integers, e.g. 48000 to 44100 Hz, fall onto a few phases between the input samples,
+/// whose kernels are computed once like the branches of a polyphase filter.
+/// Ratios with more than 1024 phases interpolate between the kernels of the closest ones.
+/// The kernel cuts off at the nyquist frequency of the lower rate, so downsampling removes the frequencies
+/// the new rate can not represent instead of folding them back, and upsampling adds no images.
+/// Frequencies up to about 95% of that nyquist frequency pass unchanged.
+/// The positions follow the exact ratio of the rates, so the timing does not drift over long signals.
+///
+/// The output holds `floor(samples.len() * to_rate / from_rate)` samples, its first sample lines up with the first input sample.
+/// Samples outside of the input are treated as zero, so the first and last few output samples fade.
+///
+/// # Panics
+/// If either rate is zero.
+///
+/// # Example
+/// ```
+/// use software_modem::dsp::{resample, spectrum};
+///
+/// // a 3.5 kHz tone, close to the nyquist frequency of 8 kHz
+/// let tone = |sample_rate: u32, length: usize| -> Vec<f32> {
+///     (0..length)
+///         .map(|n| (2.0 * std::f32::consts::PI * 3500.0 * n as f32 / sample_rate as f32).sin())
+///         .collect()
+/// };
+/// let original = tone(8000, 4000);
+///
+/// let upsampled = resample(&original, 8000, 44100);
+/// assert_eq!(upsampled.len(), 22050);
+///
+/// // the same tone at the new rate, the bins are 2 Hz apart
+/// let magnitudes = spectrum(&upsampled);
+/// let peak = (0..magnitudes.len()).max_by(|&a, &b| magnitudes[a].total_cmp(&magnitudes[b]));
+/// assert_eq!(peak, Some(1750));
+/// let expected = tone(44100, 22050);
+/// for (upsampled, expected) in upsampled[2000..20000].iter().zip(&expected[2000..20000]) {
+///     assert!((upsampled - expected).abs() < 1e-2);
+/// }
+///
+/// // and back to the original samples, away from the faded ends
+/// let restored = resample(&upsampled, 44100, 8000);
+/// assert_eq!(restored.len(), original.len());
+/// for (restored, original) in restored[400..3600].iter().zip(&original[400..3600]) {
+///     assert!((restored - original).abs() < 1e-2);
+/// }
+/// ```
+pub fn resample(samples: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
+    if from_rate == 0 || to_rate == 0 {
+        panic!(
+            "Sample rates must not be zero, but got {} and {}",
+            from_rate, to_rate
+        );
+    }
+    if from_rate == to_rate || samples.is_empty() {
+        return samples.to_vec();
+    }
+
+    // the output sample n sits at the input position n * down / up
+    let divisor = gcd(from_rate, to_rate);
+    let up = (to_rate / divisor) as usize;
+    let down = (from_rate / divisor) as usize;
+    let length = samples.len() * up / down;
+
+    // the kernel is stretched in time to cut off at the nyquist frequency of the lower rate
+    let scale = (to_rate as f64 / from_rate as f64).min(1.0);
+    let half_width = RESAMPLE_ZERO_CROSSINGS as f64 / scale;
+    let reach = half_width.floor() as usize;
+
+    // weights of the input samples from `index - reach` to `index + reach` for a position `fraction` after `index`
+    let kernel = |fraction: f64| -> Vec<f32> {
+        let weights: Vec<f64> = (0..=2 * reach + 1)
+            .map(|j| {
+                let x = j as f64 - reach as f64 - fraction;
+                if x.abs() >= half_width {
+                    return 0.0;
+                }
+                let angle = std::f64::consts::PI * x / half_width;
+                let window = 0.42 + 0.5 * angle.cos() + 0.08 * (2.0 * angle).cos();
+                sinc(scale * x) * window
+            })
+            .collect();
+        let sum: f64 = weights.iter().sum();
+        weights
+            .iter()
+            .map(|&weight| (weight / sum) as f32)
+            .collect()
+    };
+
+    // the positions repeat every `up` output samples, the kernel of every phase is computed once
+    // and the kernels are interpolated between the phases if there are too many of them
+    let num_phases = up.min(RESAMPLE_PHASES);
+    let table: Vec<Vec<f32>> = (0..=num_phases)
+        .map(|phase| kernel(phase as f64 / num_phases as f64))
+        .collect();
+
+    (0..length)
+        .map(|n| {
+            let index = n * down / up;
+            let position = (n * down % up) as f64 / up as f64 * num_phases as f64;
+            let phase = position as usize;
+            let t = (position - phase as f64) as f32;
+
+            table[phase]
+                .iter()
+                .zip(&table[phase + 1])
+                .enumerate()
+                .filter_map(|(j, (&weight, &next))| {
+                    (index + j)
+                        .checked_sub(reach)
+                        .and_then(|k| samples.get(k))
+                        .map(|&sample| sample * (weight + (next - weight) * t))
+                })
+                .sum()
+        })
+        .collect()
+}
+
+/// Computes the analytic signal `x + j * H(x)` of a real signal, `H` being the Hilbert transform.
+///
+/// The analytic signal holds the positive frequencies of the real signal at twice their amplitude
+/// and no negative frequencies, so a real capture can be processed like a complex I/Q signal,
+/// e.g. to measure its envelope or its instantaneous phase.
+/// The Hilbert transform is computed in the frequency domain by rotating every positive frequency by -90°,
+/// the DC and nyquist bins are removed from it, so the real part stays the original signal.
+/// As the FFT assumes a periodic signal, samples close to the edges are less accurate
+/// unless the signal is periodic within the buffer.
+///
+/// # Example
+/// ```
+/// use std::f32::consts::PI;
+/// use software_modem::dsp::hilbert;
+///
+/// // 25 periods of a cosine, its analytic signal is the rotating phasor 0.8 * e^(jwn)
+/// let samples: Vec<f32> = (0..1000).map(|n| 0.8 * (2.0 * PI * n as f32 / 40.0).cos()).collect();
+/// let analytic = hilbert(&samples);
+///
+/// for (n, value) in analytic.iter().enumerate() {
+///     assert_eq!(value.re, samples[n]);
+///     assert!((value.norm() - 0.8).abs() < 1e-3);
+///     let phase = 2.0 * PI * n as f32 / 40.0;
+///     assert!((value.im - 0.8 * phase.sin()).abs() < 1e-3);
+/// }
+/// ```
+pub fn hilbert<T: Float>(samples: &[T]) -> Vec<Complex<T>> {
+    if samples.is_empty() {
+        return Vec::new();
+    }
+
+    let mut planner = RealFftPlanner::<T>::new();
+    let forward = planner.plan_fft_forward(samples.len());
+    let inverse = planner.plan_fft_inverse(samples.len());
+
+    let mut input = samples.to_vec();
+    let mut spectrum = forward.make_output_vec();
+    forward.process(&mut input, &mut spectrum).unwrap();
+
+    let scale = T::one() / cast(samples.len() as f64);
+    for bin in spectrum.iter_mut() {
+        *bin = Complex::new(bin.im, -bin.re) * scale;
+    }
+    spectrum[0] = Complex::default();
+    if samples.len().is_multiple_of(2) {
+        let last = spectrum.len() - 1;
+        spectrum[last] = Complex::default();
+    }
+
+    let mut hilbert = inverse.make_output_vec();
+    inverse.process(&mut spectrum, &mut hilbert).unwrap();
+
+    samples
+        .iter()
+        .zip(hilbert)
+        .map(|(&re, im)| Complex::new(re, im))
+        .collect()
+}
+
+/// Interpolates the samples at a fractional position with a Hann windowed sinc of `2 * INTERPOLATION_HALF_LENGTH` taps.
+///
+/// The position is given in double precision, so it stays accurate far into long signals.
+/// Samples beyond the ends count as zero.
+pub(crate) fn interpolate<T: Float>(samples: &[T], position: f64) -> T {
+    let half_length = INTERPOLATION_HALF_LENGTH as f64;
+    let first = (position - half_length).floor().max(0.0) as usize;
+    let last = ((position + half_length).ceil().max(0.0) as usize).min(samples.len());
+
+    (first..last)
+        .map(|k| {
+            let t = position - k as f64;
+            if t.abs() >= half_length {
+                return T::zero();
+            }
+            let window = 0.5 * (1.0 + (std::f64::consts::PI * t / half_length).cos());
+            samples[k] * cast(sinc(t) * window)
+        })
+        .sum()
+}
+
+/// Returns `e^(jwn)` for the given sample index.
+fn carrier(n: usize, sample_rate: f32, carrier_hz: f32) -> Complex32 {
+    // wrap the phase in double precision to stay accurate for long signals
+    let cycles = carrier_hz as f64 * n as f64 / sample_rate as f64;
+    Complex32::from_polar(1.0, (2.0 * std::f64::consts::PI * cycles.fract()) as f32)
+}
+
+/// Returns `sin(πx) / (πx)`, which is `1` at zero.
+fn sinc(x: f64) -> f64 {
+    if x == 0.0 {
+        1.0
+    } else {
+        let x = std::f64::consts::PI * x;
+        x.sin() / x
+    }
+}
+
+/// Returns the greatest common divisor of two numbers.
+fn gcd(mut a: u32, mut b: u32) -> u32 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// Designs a linear phase low-pass FIR filter with a Blackman windowed sinc.
+///
+/// `cutoff` is the cutoff frequency relative to the sample rate, `num_taps` should be odd.
+/// The taps are normalized to unity gain at DC.
+fn windowed_sinc(cutoff: f32, num_taps: usize) -> Vec<f32> {
+    let center = (num_taps - 1) as f32 / 2.0;
+    let mut taps: Vec<f32> = (0..num_taps)
+        .map(|i| {
+            let x = i as f32 - center;
+            let sinc = if x == 0.0 {
+                2.0 * cutoff
+            } else {
+                (2.0 * PI * cutoff * x).sin() / (PI * x)
+            };
+            let window = 0.42 - 0.5 * (2.0 * PI * i as f32 / (num_taps - 1) as f32).cos()
+                + 0.08 * (4.0 * PI * i as f32 / (num_taps - 1) as f32).cos();
+            sinc * window
+        })
+        .collect();
+
+    let sum: f32 = taps.iter().sum();
+    taps.iter_mut().for_each(|tap| *tap /= sum);
+    taps
+}
+
+/// Applies a FIR filter, compensating its group delay so the output lines up with the input.
+///
+/// Samples outside of the input are treated as zero.
+fn filter(samples: &[Complex32], taps: &[f32]) -> Vec<Complex32> {
+    let delay = taps.len() / 2;
+    (0..samples.len())
+        .map(|n| {
+            taps.iter()
+                .enumerate()
+                .filter_map(|(k, &tap)| {
+                    (n + delay)
+                        .checked_sub(k)
+                        .and_then(|i| samples.get(i))
+                        .map(|&sample| sample * tap)
+                })
+                .sum()
+        })
+        .collect()
+}
@@ -0,0 +1,438 @@
+//! This module provides the error types used throughout the crate.
+
+use std::fmt::Display;
+
+use crate::{ofdm::slm::MAX_SLM_CANDIDATES, qam::QAMOrder};
+
+/// Errors that can occur while modulating data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModulationError {
+    /// The data buffer does not hold the number of bytes one symbol carries.
+    DataLengthMismatch {
+        /// The expected number of bytes.
+        expected: usize,
+        /// The number of bytes that were passed in.
+        actual: usize,
+    },
+    /// The output buffer does not hold the number of samples of one symbol.
+    OutputBufferLengthMismatch {
+        /// The expected number of samples.
+        expected: usize,
+        /// The number of samples of the buffer that was passed in.
+        actual: usize,
+    },
+    /// The FFT rejected its buffers.
+    Fft {
+        /// The message of the FFT error.
+        message: String,
+    },
+}
+
+impl Display for ModulationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ModulationError::DataLengthMismatch { expected, actual } => write!(
+                f,
+                "Data length must be {expected} bytes, but got {actual} bytes"
+            ),
+            ModulationError::OutputBufferLengthMismatch { expected, actual } => write!(
+                f,
+                "Output buffer length must be {expected} samples, but got {actual} samples"
+            ),
+            ModulationError::Fft { message } => write!(f, "FFT failed: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for ModulationError {}
+
+/// Errors that can occur while demodulating symbols.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DemodulationError {
+    /// The symbols end with more bits after the last whole byte than the padding of the modulation can explain,
+    /// e.g. an odd number of QAM-16 symbols of a truncated capture.
+    IncompleteByte {
+        /// The number of bits after the last whole byte.
+        dropped_bits: usize,
+    },
+}
+
+impl Display for DemodulationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DemodulationError::IncompleteByte { dropped_bits } => write!(
+                f,
+                "Symbols must make up whole bytes, but end with {dropped_bits} bits of an incomplete byte"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DemodulationError {}
+
+/// Errors that can occur while constructing a modulator or demodulator from its configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigError {
+    /// The pilot sequence holds fewer values than there are pilot subcarriers.
+    PilotSequenceTooShort {
+        /// The number of pilot subcarriers.
+        expected: usize,
+        /// The number of values in the pilot sequence.
+        actual: usize,
+    },
+    /// The number of subcarriers is zero.
+    NoSubcarriers,
+    /// The cyclic prefix is not shorter than an OFDM symbol without it.
+    CyclicPrefixTooLong {
+        /// The configured cyclic prefix length.
+        cyclic_prefix_length: u32,
+        /// The length of an OFDM symbol without the cyclic prefix, `2 * num_subcarriers`.
+        symbol_length: u32,
+    },
+    /// The cyclic prefix is given as a fraction of the symbol length which is not at least 0 and below 1.
+    CyclicPrefixFractionOutOfRange {
+        /// The configured fraction.
+        fraction: f32,
+    },
+    /// Pilots are inserted so often that no subcarrier is left for data.
+    PilotSpacingTooSmall {
+        /// The configured pilot interval.
+        pilot_subcarrier_every: u32,
+    },
+    /// Pilots are inserted so rarely that no pilot subcarrier lies between DC and nyquist.
+    PilotSpacingTooLarge {
+        /// The configured pilot interval.
+        pilot_subcarrier_every: u32,
+        /// The configured number of subcarriers.
+        num_subcarriers: u32,
+    },
+    /// An explicitly listed pilot subcarrier is DC, nyquist or does not exist.
+    PilotSubcarrierOutOfRange {
+        /// The configured subcarrier index.
+        index: u32,
+        /// The configured number of subcarriers, which is the nyquist index.
+        num_subcarriers: u32,
+    },
+    /// A subcarrier to null does not exist.
+    NullSubcarrierOutOfRange {
+        /// The configured subcarrier index.
+        index: u32,
+        /// The configured number of subcarriers, which is the highest valid index.
+        num_subcarriers: u32,
+    },
+    /// A range of the spectral mask is reversed or exceeds the subcarriers.
+    SpectralMaskOutOfRange {
+        /// The first masked subcarrier of the range.
+        first: u32,
+        /// The last masked subcarrier of the range.
+        last: u32,
+        /// The configured number of subcarriers, which is the highest valid index.
+        num_subcarriers: u32,
+    },
+    /// The window is longer than the cyclic prefix it overlaps with.
+    WindowTooLong {
+        /// The configured window length.
+        window_length: u32,
+        /// The configured cyclic prefix length.
+        cyclic_prefix_length: u32,
+    },
+    /// The number of selective mapping candidates is zero or exceeds
+    /// [MAX_SLM_CANDIDATES](crate::ofdm::slm::MAX_SLM_CANDIDATES).
+    SlmCandidatesOutOfRange {
+        /// The configured number of candidates.
+        num_slm_candidates: u32,
+    },
+    /// Every symbol of a packet is to be transmitted zero times.
+    NoRepetitions,
+    /// The bit depth of the output quantization is zero or exceeds 32 bits.
+    QuantizationBitsOutOfRange {
+        /// The configured number of bits.
+        bits: u32,
+    },
+    /// The bit loading table does not hold one QAM order per data subcarrier.
+    BitLoadingLengthMismatch {
+        /// The number of data subcarriers.
+        expected: usize,
+        /// The number of QAM orders in the bit loading table.
+        actual: usize,
+    },
+    /// A custom subcarrier mapping does not list every data subcarrier exactly once.
+    InvalidSubcarrierMapping {
+        /// The number of data subcarriers.
+        num_data_subcarriers: usize,
+    },
+    /// The data subcarriers of an OFDM symbol carry less than one byte, so no payload fits into a symbol.
+    NoPayloadCapacity {
+        /// The number of bits the data subcarriers carry.
+        bits_per_symbol: u32,
+    },
+    /// A configured FFT does not transform the `2 * num_subcarriers` samples of a symbol.
+    FftLengthMismatch {
+        /// The length of a symbol without the cyclic prefix.
+        expected: usize,
+        /// The length of the configured FFT.
+        actual: usize,
+    },
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::PilotSequenceTooShort { expected, actual } => write!(
+                f,
+                "Pilot sequence must have at least {expected} values, but got {actual} values"
+            ),
+            ConfigError::NoSubcarriers => write!(f, "Number of subcarriers must not be zero"),
+            ConfigError::CyclicPrefixTooLong {
+                cyclic_prefix_length,
+                symbol_length,
+            } => write!(
+                f,
+                "Cyclic prefix length must be below the symbol length of {symbol_length} samples, but got {cyclic_prefix_length} samples"
+            ),
+            ConfigError::CyclicPrefixFractionOutOfRange { fraction } => write!(
+                f,
+                "Cyclic prefix fraction must be at least 0 and below 1, but got {fraction}"
+            ),
+            ConfigError::PilotSpacingTooSmall {
+                pilot_subcarrier_every,
+            } => write!(
+                f,
+                "Pilot subcarrier interval must be at least 2, but got {pilot_subcarrier_every}"
+            ),
+            ConfigError::PilotSpacingTooLarge {
+                pilot_subcarrier_every,
+                num_subcarriers,
+            } => write!(
+                f,
+                "Pilot subcarrier interval must be below the number of subcarriers {num_subcarriers}, but got {pilot_subcarrier_every}"
+            ),
+            ConfigError::PilotSubcarrierOutOfRange {
+                index,
+                num_subcarriers,
+            } => write!(
+                f,
+                "Pilot subcarrier index must be between 1 and {}, but got {index}",
+                num_subcarriers - 1
+            ),
+            ConfigError::NullSubcarrierOutOfRange {
+                index,
+                num_subcarriers,
+            } => write!(
+                f,
+                "Null subcarrier index must be at most {num_subcarriers}, but got {index}"
+            ),
+            ConfigError::SpectralMaskOutOfRange {
+                first,
+                last,
+                num_subcarriers,
+            } => write!(
+                f,
+                "Spectral mask range must lie within 0 to {num_subcarriers} in ascending order, but got {first} to {last}"
+            ),
+            ConfigError::WindowTooLong {
+                window_length,
+                cyclic_prefix_length,
+            } => write!(
+                f,
+                "Window length must not exceed the cyclic prefix length of {cyclic_prefix_length} samples, but got {window_length} samples"
+            ),
+            ConfigError::SlmCandidatesOutOfRange { num_slm_candidates } => write!(
+                f,
+                "Number of SLM candidates must be between 1 and {MAX_SLM_CANDIDATES}, but got {num_slm_candidates}"
+            ),
+            ConfigError::NoRepetitions => write!(f, "Number of repetitions must not be zero"),
+            ConfigError::QuantizationBitsOutOfRange { bits } => write!(
+                f,
+                "Quantization bit depth must be between 1 and 32, but got {bits}"
+            ),
+            ConfigError::BitLoadingLengthMismatch { expected, actual } => write!(
+                f,
+                "Bit loading must hold one QAM order for each of the {expected} data subcarriers, but got {actual} orders"
+            ),
+            ConfigError::InvalidSubcarrierMapping {
+                num_data_subcarriers,
+            } => write!(
+                f,
+                "Custom subcarrier mapping must list each of the {num_data_subcarriers} data subcarriers exactly once"
+            ),
+            ConfigError::NoPayloadCapacity { bits_per_symbol } => write!(
+                f,
+                "Data subcarriers must carry at least one byte per symbol, but carry {bits_per_symbol} bits"
+            ),
+            ConfigError::FftLengthMismatch { expected, actual } => write!(
+                f,
+                "FFT length must be {expected} samples, but got {actual} samples"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Errors that can occur while unpacking a [frame](crate::frame).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FrameError {
+    /// The data is shorter than the frame header and checksum, or than the length given in the header.
+    TooShort {
+        /// The number of bytes the frame needs.
+        expected: usize,
+        /// The number of bytes available.
+        actual: usize,
+    },
+    /// The checksum of the received frame does not match its content.
+    ChecksumMismatch {
+        /// The checksum transmitted with the frame.
+        expected: u32,
+        /// The checksum calculated over the received frame.
+        actual: u32,
+    },
+}
+
+impl Display for FrameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FrameError::TooShort { expected, actual } => write!(
+                f,
+                "Frame needs at least {expected} bytes, but got {actual} bytes"
+            ),
+            FrameError::ChecksumMismatch { expected, actual } => write!(
+                f,
+                "Frame checksum is {expected:#010x}, but the content results in {actual:#010x}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FrameError {}
+
+/// Errors that can occur while demodulating a
+/// [packet with a header](crate::ofdm::modulator::OFDMModulator::modulate_packet_with_header).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HeaderError {
+    /// The input ends before the header or the payload the header announces.
+    TooShort {
+        /// The number of samples the packet needs.
+        expected: usize,
+        /// The number of samples available.
+        actual: usize,
+    },
+    /// The checksum of the received header does not match its content.
+    ChecksumMismatch {
+        /// The checksum transmitted with the header.
+        expected: u32,
+        /// The checksum calculated over the received header.
+        actual: u32,
+    },
+    /// The header announces a QAM order which does not exist.
+    UnknownQamOrder {
+        /// The number of bits per symbol given in the header.
+        value: u8,
+    },
+    /// The header announces a different QAM order than the demodulator is configured for.
+    QamOrderMismatch {
+        /// The QAM order of the demodulator.
+        expected: QAMOrder,
+        /// The QAM order given in the header.
+        actual: QAMOrder,
+    },
+}
+
+impl Display for HeaderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HeaderError::TooShort { expected, actual } => write!(
+                f,
+                "Packet needs at least {expected} samples, but got {actual} samples"
+            ),
+            HeaderError::ChecksumMismatch { expected, actual } => write!(
+                f,
+                "Header checksum is {expected:#010x}, but the content results in {actual:#010x}"
+            ),
+            HeaderError::UnknownQamOrder { value } => {
+                write!(
+                    f,
+                    "Header announces an unknown QAM order of {value} bits per symbol"
+                )
+            }
+            HeaderError::QamOrderMismatch { expected, actual } => write!(
+                f,
+                "Header announces {actual}, but the demodulator is configured for {expected}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for HeaderError {}
+
+/// Errors that can occur while receiving a [frame](crate::ofdm::modulator::OFDMModulator::build_frame)
+/// with [receive_frame](crate::ofdm::demodulator::OFDMDemodulator::receive_frame).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RxError {
+    /// No preamble was found in the samples.
+    NoPreamble,
+    /// The samples end before the long training symbol after the preamble does.
+    TooShort {
+        /// The number of samples the frame needs up to the end of the long training symbol.
+        expected: usize,
+        /// The number of samples available.
+        actual: usize,
+    },
+    /// The packet with the header after the long training symbol could not be demodulated.
+    Header(HeaderError),
+}
+
+impl From<HeaderError> for RxError {
+    fn from(error: HeaderError) -> Self {
+        RxError::Header(error)
+    }
+}
+
+impl Display for RxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RxError::NoPreamble => write!(f, "No preamble found in the samples"),
+            RxError::TooShort { expected, actual } => write!(
+                f,
+                "Frame needs at least {expected} samples up to the long training symbol, but got {actual} samples"
+            ),
+            RxError::Header(error) => write!(f, "Packet of the frame is invalid: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for RxError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RxError::Header(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Errors that can occur while decoding the [Reed-Solomon code](crate::fec::reed_solomon).
+#[cfg(feature = "rs")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FecError {
+    /// A block holds more wrong bytes than the code can correct.
+    TooManyErrors {
+        /// The index of the block.
+        block: usize,
+    },
+}
+
+#[cfg(feature = "rs")]
+impl Display for FecError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FecError::TooManyErrors { block } => write!(
+                f,
+                "Block {block} holds more than {} wrong bytes and cannot be corrected",
+                crate::fec::reed_solomon::CORRECTABLE_ERRORS
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "rs")]
+impl std::error::Error for FecError {}
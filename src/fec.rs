@@ -0,0 +1,165 @@
+//! This module provides forward error correction (FEC).
+//!
+//! It implements the standard rate 1/2 convolutional code with constraint length 7
+//! and the generator polynomials 171 and 133 (octal), together with a Viterbi decoder.
+//! Every input bit produces two coded bits, so the coded data is about twice as long as the payload.
+//!
+//! Use [encode] on the payload before modulation and [decode] on the demodulated bytes,
+//! or enable `convolutional_coding` in the OFDM configurations to let
+//! [modulate_packet](crate::ofdm::modulator::OFDMModulator::modulate_packet) and
+//! [demodulate_packet](crate::ofdm::demodulator::OFDMDemodulator::demodulate_packet) do it.
+//!
+//! With the `rs` feature, the [reed_solomon] module adds a Reed-Solomon outer code,
+//! which corrects the bursts of byte errors the Viterbi decoder leaves behind.
+
+#[cfg(feature = "rs")]
+pub mod reed_solomon;
+
+/// First generator polynomial, 171 octal.
+const GENERATOR_0: u8 = 0o171;
+/// Second generator polynomial, 133 octal.
+const GENERATOR_1: u8 = 0o133;
+/// Number of previous input bits the encoder remembers (constraint length - 1).
+const MEMORY: usize = 6;
+/// Number of states of the encoder.
+const NUM_STATES: usize = 1 << MEMORY;
+
+/// Encodes the data with the rate 1/2 convolutional code.
+///
+/// The bits are encoded most significant bit first, and 6 zero bits are appended to flush the encoder
+/// back into its initial state, which allows the decoder to terminate in a known state.
+/// The coded bits are packed into bytes, the last byte is padded with zeros.
+/// Encoding `n` bytes results in `2 * n + 2` bytes, an empty input results in an empty output.
+///
+/// # Example
+/// ```
+/// use software_modem::fec::encode;
+///
+/// assert_eq!(encode(b"Hello, FEC!").len(), 24);
+/// assert!(encode(&[]).is_empty());
+/// ```
+pub fn encode(data: &[u8]) -> Vec<u8> {
+    if data.is_empty() {
+        return Vec::new();
+    }
+
+    let num_bits = 2 * (8 * data.len() + MEMORY);
+    let mut output = vec![0u8; num_bits.div_ceil(8)];
+
+    let input_bits = data
+        .iter()
+        .flat_map(|&byte| (0..8).rev().map(move |i| (byte >> i) & 1))
+        .chain(std::iter::repeat_n(0, MEMORY));
+
+    let mut state = 0u8;
+    for (i, bit) in input_bits.enumerate() {
+        let register = (bit << MEMORY) | state;
+        for (j, generator) in [GENERATOR_0, GENERATOR_1].into_iter().enumerate() {
+            let coded_bit = ((register & generator).count_ones() & 1) as u8;
+            let position = 2 * i + j;
+            output[position / 8] |= coded_bit << (7 - position % 8);
+        }
+        state = register >> 1;
+    }
+
+    output
+}
+
+/// Decodes data encoded with [encode] using a hard decision Viterbi decoder.
+///
+/// Every coded bit is treated as a fully reliable decision, see [decode_soft] to pass reliability information.
+/// The decoder assumes the encoder was flushed with zero bits,
+/// so `2 * n + 2` coded bytes decode into `n` bytes.
+///
+/// # Example
+/// ```
+/// use software_modem::fec::{decode, encode};
+///
+/// let data = b"Hello, FEC!";
+/// let mut coded = encode(data);
+///
+/// // flip a few bits, spread over the coded data
+/// coded[1] ^= 0b0000_0100;
+/// coded[6] ^= 0b1000_0000;
+/// coded[12] ^= 0b0001_0000;
+/// coded[19] ^= 0b0000_0001;
+///
+/// assert_eq!(decode(&coded), data);
+/// ```
+pub fn decode(data: &[u8]) -> Vec<u8> {
+    let soft_bits: Vec<f32> = data
+        .iter()
+        .flat_map(|&byte| (0..8).rev().map(move |i| (byte >> i) & 1))
+        .map(|bit| if bit == 0 { 1.0 } else { -1.0 })
+        .collect();
+
+    decode_soft(&soft_bits)
+}
+
+/// Decodes soft coded bits using a soft decision Viterbi decoder.
+///
+/// Every value is a log-likelihood ratio (LLR) of one coded bit, `ln(P(bit = 0) / P(bit = 1))`:
+/// positive values stand for a `0`, negative values for a `1` and the magnitude for the reliability.
+/// Values that are not part of a complete pair of coded bits are ignored.
+///
+/// Returns the decoded bytes, see [decode] for the expected length.
+pub fn decode_soft(llrs: &[f32]) -> Vec<u8> {
+    let num_steps = llrs.len() / 2;
+    if num_steps <= MEMORY {
+        return Vec::new();
+    }
+
+    // path metrics, the encoder starts in state 0
+    let mut metrics = [f32::NEG_INFINITY; NUM_STATES];
+    metrics[0] = 0.0;
+    // for every step and state the lowest bit of the surviving predecessor state
+    let mut decisions = vec![0u64; num_steps];
+
+    for (step, pair) in llrs.chunks_exact(2).enumerate() {
+        let mut next_metrics = [f32::NEG_INFINITY; NUM_STATES];
+        for (next_state, next_metric) in next_metrics.iter_mut().enumerate() {
+            let bit = (next_state >> (MEMORY - 1)) as u8;
+            for oldest in 0..2 {
+                let state = ((next_state << 1) & (NUM_STATES - 1)) | oldest;
+                let register = (bit << MEMORY) | state as u8;
+
+                let branch_metric: f32 = [GENERATOR_0, GENERATOR_1]
+                    .into_iter()
+                    .zip(pair)
+                    .map(|(generator, &llr)| {
+                        if (register & generator).count_ones() & 1 == 0 {
+                            llr
+                        } else {
+                            -llr
+                        }
+                    })
+                    .sum();
+
+                let metric = metrics[state] + branch_metric;
+                if metric > *next_metric {
+                    *next_metric = metric;
+                    if oldest == 1 {
+                        decisions[step] |= 1 << next_state;
+                    } else {
+                        decisions[step] &= !(1 << next_state);
+                    }
+                }
+            }
+        }
+        metrics = next_metrics;
+    }
+
+    // trace back from state 0, which the flushed encoder ends in
+    let mut bits = vec![0u8; num_steps];
+    let mut state = 0usize;
+    for step in (0..num_steps).rev() {
+        bits[step] = (state >> (MEMORY - 1)) as u8;
+        let oldest = ((decisions[step] >> state) & 1) as usize;
+        state = ((state << 1) & (NUM_STATES - 1)) | oldest;
+    }
+
+    bits[..num_steps - MEMORY]
+        .chunks_exact(8)
+        .map(|chunk| chunk.iter().fold(0, |byte, &bit| (byte << 1) | bit))
+        .collect()
+}
@@ -0,0 +1,337 @@
+//! This module provides the RS(255, 223) Reed-Solomon code, an outer code for the convolutional code.
+//!
+//! The Viterbi decoder of the [convolutional code](crate::fec) corrects scattered bit errors well,
+//! but when it fails, it produces bursts of wrong bytes. The Reed-Solomon code works on whole bytes,
+//! so a burst costs only as many corrections as it spans bytes:
+//! every block of [BLOCK_LENGTH] bytes carries [DATA_LENGTH] data bytes and corrects up to
+//! [CORRECTABLE_ERRORS] wrong bytes anywhere in the block.
+//!
+//! The code is systematic, the data bytes are transmitted unchanged in front of the parity bytes.
+//! It uses the field GF(256) with the primitive polynomial `x^8 + x^4 + x^3 + x^2 + 1` (0x11d)
+//! and a generator polynomial with the roots `α^0` to `α^31`.
+//!
+//! Enable `reed_solomon` in the OFDM configurations to let
+//! [modulate_packet](crate::ofdm::modulator::OFDMModulator::modulate_packet) encode the payload
+//! before the convolutional code and
+//! [demodulate_packet](crate::ofdm::demodulator::OFDMDemodulator::demodulate_packet) decode it after the Viterbi decoder.
+//!
+//! # Example
+//! ```
+//! use software_modem::fec::reed_solomon::{CORRECTABLE_ERRORS, decode, encode};
+//!
+//! let data: Vec<u8> = (0..223).map(|i| (i * 7) as u8).collect();
+//! let mut coded = encode(&data);
+//! assert_eq!(coded.len(), 255);
+//!
+//! // a burst of 10 wrong bytes and 6 more spread over the block
+//! for i in (40..50).chain([0, 99, 150, 222, 230, 254]) {
+//!     coded[i] ^= 0x5a;
+//! }
+//! assert_eq!(CORRECTABLE_ERRORS, 16);
+//! assert_eq!(decode(&coded), Ok(data));
+//! ```
+
+use crate::error::FecError;
+
+/// Number of bytes of a coded block.
+pub const BLOCK_LENGTH: usize = 255;
+/// Number of data bytes of a coded block.
+pub const DATA_LENGTH: usize = 223;
+/// Number of parity bytes of a coded block.
+pub const PARITY_LENGTH: usize = BLOCK_LENGTH - DATA_LENGTH;
+/// Number of wrong bytes a block can hold and still be corrected.
+pub const CORRECTABLE_ERRORS: usize = PARITY_LENGTH / 2;
+
+/// Primitive polynomial of the field, `x^8 + x^4 + x^3 + x^2 + 1`.
+const PRIMITIVE_POLYNOMIAL: u16 = 0x11d;
+
+/// Powers of the primitive element `α`, repeated once so products of two logarithms need no reduction.
+const EXP: [u8; 512] = {
+    let mut table = [0u8; 512];
+    let mut value: u16 = 1;
+    let mut i = 0;
+    while i < 255 {
+        table[i] = value as u8;
+        table[i + 255] = value as u8;
+        value <<= 1;
+        if value & 0x100 != 0 {
+            value ^= PRIMITIVE_POLYNOMIAL;
+        }
+        i += 1;
+    }
+    table
+};
+
+/// Logarithms to the base `α`, the entry of zero is unused.
+const LOG: [u8; 256] = {
+    let mut table = [0u8; 256];
+    let mut i = 0;
+    while i < 255 {
+        table[EXP[i] as usize] = i as u8;
+        i += 1;
+    }
+    table
+};
+
+/// Coefficients of the generator polynomial `(x - α^0) ... (x - α^31)`, highest degree first.
+const GENERATOR: [u8; PARITY_LENGTH + 1] = {
+    let mut generator = [0u8; PARITY_LENGTH + 1];
+    generator[0] = 1;
+    let mut root = 0;
+    while root < PARITY_LENGTH {
+        // multiply by (x + α^root), the new lowest coefficient starts at zero
+        let mut i = root + 1;
+        while i > 0 {
+            generator[i] ^= const_mul(generator[i - 1], EXP[root]);
+            i -= 1;
+        }
+        root += 1;
+    }
+    generator
+};
+
+/// Multiplies two field elements, usable in constants.
+const fn const_mul(a: u8, b: u8) -> u8 {
+    if a == 0 || b == 0 {
+        0
+    } else {
+        EXP[LOG[a as usize] as usize + LOG[b as usize] as usize]
+    }
+}
+
+/// Multiplies two field elements.
+fn mul(a: u8, b: u8) -> u8 {
+    const_mul(a, b)
+}
+
+/// Divides two field elements, `b` must not be zero.
+fn div(a: u8, b: u8) -> u8 {
+    if a == 0 {
+        0
+    } else {
+        EXP[LOG[a as usize] as usize + 255 - LOG[b as usize] as usize]
+    }
+}
+
+/// Returns `α^power`.
+fn pow(power: usize) -> u8 {
+    EXP[power % 255]
+}
+
+/// Evaluates a polynomial with the lowest degree first at `x`.
+fn evaluate(polynomial: &[u8], x: u8) -> u8 {
+    polynomial
+        .iter()
+        .rev()
+        .fold(0, |value, &coefficient| mul(value, x) ^ coefficient)
+}
+
+/// Encodes the data with the RS(255, 223) code.
+///
+/// The data is split into blocks of [DATA_LENGTH] bytes and every block is followed by its parity bytes.
+/// The last block is shortened, it is encoded as if leading zeros filled it up,
+/// but the zeros are not transmitted.
+/// Encoding `n` bytes results in `n + 32 * ceil(n / 223)` bytes, an empty input results in an empty output.
+///
+/// # Example
+/// ```
+/// use software_modem::fec::reed_solomon::encode;
+///
+/// let coded = encode(b"Hello, Reed-Solomon!");
+/// assert_eq!(coded.len(), 20 + 32);
+/// assert_eq!(&coded[..20], b"Hello, Reed-Solomon!");
+/// assert_eq!(encode(&[0; 224]).len(), 255 + 1 + 32);
+/// assert!(encode(&[]).is_empty());
+/// ```
+pub fn encode(data: &[u8]) -> Vec<u8> {
+    let mut output =
+        Vec::with_capacity(data.len() + data.len().div_ceil(DATA_LENGTH) * PARITY_LENGTH);
+    for chunk in data.chunks(DATA_LENGTH) {
+        // the remainder of the data times x^32 divided by the generator,
+        // leading zeros of a shortened block do not change it
+        let mut remainder = [0u8; PARITY_LENGTH];
+        for &byte in chunk {
+            let factor = byte ^ remainder[0];
+            remainder.copy_within(1.., 0);
+            remainder[PARITY_LENGTH - 1] = 0;
+            for (value, &coefficient) in remainder.iter_mut().zip(&GENERATOR[1..]) {
+                *value ^= mul(factor, coefficient);
+            }
+        }
+
+        output.extend_from_slice(chunk);
+        output.extend_from_slice(&remainder);
+    }
+
+    output
+}
+
+/// Decodes data encoded with [encode], correcting up to [CORRECTABLE_ERRORS] wrong bytes per block.
+///
+/// Returns the data bytes of all blocks.
+/// A trailing block of at most 32 bytes carries no data and is ignored.
+///
+/// # Errors
+/// Returns [FecError::TooManyErrors] for the first block with more errors than the code can correct.
+///
+/// # Example
+/// ```
+/// use software_modem::error::FecError;
+/// use software_modem::fec::reed_solomon::{decode, encode};
+///
+/// let data = [0x42; 300];
+/// let mut coded = encode(&data);
+///
+/// // the first block is correctable, the second one is not
+/// coded[..16].fill(0);
+/// assert_eq!(decode(&coded).unwrap(), data);
+/// coded[255..255 + 17].fill(0);
+/// assert_eq!(decode(&coded), Err(FecError::TooManyErrors { block: 1 }));
+/// ```
+pub fn decode(data: &[u8]) -> Result<Vec<u8>, FecError> {
+    let mut output = Vec::with_capacity(data.len());
+    for (i, chunk) in coded_blocks(data).enumerate() {
+        let block = decode_chunk(chunk).ok_or(FecError::TooManyErrors { block: i })?;
+        output.extend_from_slice(&block);
+    }
+
+    Ok(output)
+}
+
+/// Decodes like [decode], but keeps the received data bytes of blocks which cannot be corrected.
+pub(crate) fn decode_lenient(data: &[u8]) -> Vec<u8> {
+    coded_blocks(data)
+        .flat_map(|chunk| {
+            decode_chunk(chunk).unwrap_or_else(|| chunk[..chunk.len() - PARITY_LENGTH].to_vec())
+        })
+        .collect()
+}
+
+/// Splits coded data into its blocks, dropping a trailing block without data bytes.
+fn coded_blocks(data: &[u8]) -> impl Iterator<Item = &[u8]> {
+    data.chunks(BLOCK_LENGTH)
+        .filter(|chunk| chunk.len() > PARITY_LENGTH)
+}
+
+/// Corrects a possibly shortened block and returns its data bytes.
+fn decode_chunk(chunk: &[u8]) -> Option<Vec<u8>> {
+    let shortening = BLOCK_LENGTH - chunk.len();
+    let mut block = [0u8; BLOCK_LENGTH];
+    block[shortening..].copy_from_slice(chunk);
+    decode_block(&mut block)?;
+
+    // a correction of the leading zeros of a shortened block is a wrong guess
+    if block[..shortening].iter().any(|&byte| byte != 0) {
+        return None;
+    }
+    Some(block[shortening..DATA_LENGTH].to_vec())
+}
+
+/// Corrects the errors of a single coded block in place.
+///
+/// Returns the number of corrected bytes, or `None` if the block holds more errors than the code can correct,
+/// the block is left unchanged then.
+/// As the code cannot always detect this, a block with many more errors may also be "corrected" into a wrong block.
+pub fn decode_block(block: &mut [u8; BLOCK_LENGTH]) -> Option<usize> {
+    // the received block as a polynomial has the first byte as the highest coefficient
+    let syndromes: Vec<u8> = (0..PARITY_LENGTH)
+        .map(|i| {
+            let x = pow(i);
+            block.iter().fold(0, |value, &byte| mul(value, x) ^ byte)
+        })
+        .collect();
+    if syndromes.iter().all(|&syndrome| syndrome == 0) {
+        return Some(0);
+    }
+
+    let locator = error_locator(&syndromes);
+    let num_errors = locator.len() - 1;
+    if num_errors > CORRECTABLE_ERRORS {
+        return None;
+    }
+
+    // the error evaluator, syndromes times locator modulo x^32
+    let mut evaluator = vec![0u8; PARITY_LENGTH];
+    for (i, &syndrome) in syndromes.iter().enumerate() {
+        for (j, &coefficient) in locator.iter().enumerate().take(PARITY_LENGTH - i) {
+            evaluator[i + j] ^= mul(syndrome, coefficient);
+        }
+    }
+    // the formal derivative of the locator keeps the odd powers
+    let derivative: Vec<u8> = locator
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(i, &coefficient)| if i % 2 == 1 { coefficient } else { 0 })
+        .collect();
+
+    // Chien search for the roots of the locator, Forney for the error values
+    let mut corrections = Vec::with_capacity(num_errors);
+    for position in 0..BLOCK_LENGTH {
+        let degree = BLOCK_LENGTH - 1 - position;
+        let x_inverse = pow(255 - degree);
+        if evaluate(&locator, x_inverse) != 0 {
+            continue;
+        }
+
+        let error = mul(
+            pow(degree),
+            div(
+                evaluate(&evaluator, x_inverse),
+                evaluate(&derivative, x_inverse),
+            ),
+        );
+        corrections.push((position, error));
+    }
+    if corrections.len() != num_errors {
+        return None;
+    }
+
+    for (position, error) in corrections {
+        block[position] ^= error;
+    }
+    Some(num_errors)
+}
+
+/// Finds the error locator polynomial of the syndromes with the Berlekamp-Massey algorithm.
+///
+/// The polynomial has the lowest degree first and its degree is the number of errors.
+fn error_locator(syndromes: &[u8]) -> Vec<u8> {
+    let mut locator = vec![1u8];
+    let mut previous = vec![1u8];
+    let mut num_errors = 0;
+    let mut shift = 1;
+    let mut previous_discrepancy = 1u8;
+
+    for n in 0..syndromes.len() {
+        let discrepancy = (1..=num_errors)
+            .filter_map(|i| Some(mul(*locator.get(i)?, syndromes[n - i])))
+            .fold(syndromes[n], |sum, term| sum ^ term);
+        if discrepancy == 0 {
+            shift += 1;
+            continue;
+        }
+
+        // locator - discrepancy / previous_discrepancy * x^shift * previous
+        let factor = div(discrepancy, previous_discrepancy);
+        let mut updated = locator.clone();
+        updated.resize(updated.len().max(previous.len() + shift), 0);
+        for (i, &coefficient) in previous.iter().enumerate() {
+            updated[i + shift] ^= mul(factor, coefficient);
+        }
+
+        if 2 * num_errors <= n {
+            previous = std::mem::replace(&mut locator, updated);
+            num_errors = n + 1 - num_errors;
+            previous_discrepancy = discrepancy;
+            shift = 1;
+        } else {
+            locator = updated;
+            shift += 1;
+        }
+    }
+
+    // a locator of a lower degree than the number of errors has too few roots, which marks the block uncorrectable
+    locator.resize(num_errors + 1, 0);
+    locator
+}
@@ -0,0 +1,19 @@
+//! This module provides the [Float] trait, which selects the precision of the modem.
+//!
+//! The [QAM Modem](crate::qam::QAMModem), [OFDM Modulator](crate::ofdm::modulator::OFDMModulator)
+//! and [OFDM Demodulator](crate::ofdm::demodulator::OFDMDemodulator) are generic over it
+//! and default to `f32`. Use `f64` for high precision simulations.
+
+use std::iter::Sum;
+
+use realfft::{FftNum, num_traits};
+
+/// A floating point type the modem can work with, implemented for `f32` and `f64`.
+pub trait Float: FftNum + num_traits::Float + Default + Sum {}
+
+impl<T> Float for T where T: FftNum + num_traits::Float + Default + Sum {}
+
+/// Converts a constant into the float type.
+pub(crate) fn cast<T: Float>(value: f64) -> T {
+    T::from_f64(value).unwrap()
+}
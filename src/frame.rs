@@ -0,0 +1,152 @@
+//! This module provides framing of payloads for integrity checking.
+//!
+//! A frame consists of a little endian `u32` length field, a little endian `u16` sequence number, the payload,
+//! and a little endian CRC-32 (IEEE 802.3) checksum over the length field, the sequence number and the payload:
+//!
+//! ```text
+//! | length (4 bytes) | sequence number (2 bytes) | payload (length bytes) | crc-32 (4 bytes) |
+//! ```
+//!
+//! The sequence number is not interpreted here, it lets a retransmission scheme (ARQ)
+//! acknowledge frames and recognize duplicates.
+//!
+//! As the length is part of the frame, bytes following a frame (e.g. the padding of the last OFDM symbol)
+//! are ignored by [deframe].
+
+use crate::error::FrameError;
+
+/// Size of the length field in bytes.
+const LENGTH_SIZE: usize = 4;
+/// Size of the sequence number in bytes.
+const SEQUENCE_SIZE: usize = 2;
+/// Offset of the payload within the frame.
+const PAYLOAD_START: usize = LENGTH_SIZE + SEQUENCE_SIZE;
+/// Size of the checksum in bytes.
+const CHECKSUM_SIZE: usize = 4;
+/// Number of bytes a frame adds to its payload.
+pub(crate) const OVERHEAD: usize = PAYLOAD_START + CHECKSUM_SIZE;
+
+/// Lookup table for the reflected CRC-32 polynomial 0xEDB88320.
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// Calculates the CRC-32 (IEEE 802.3) checksum of the data.
+///
+/// # Example
+/// ```
+/// use software_modem::frame::crc32;
+///
+/// assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+/// ```
+pub fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |crc, &byte| {
+        CRC32_TABLE[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+/// Wraps the payload into a frame with a length field, the sequence number `seq` and a CRC-32 checksum.
+///
+/// # Panics
+/// If the payload is larger than `u32::MAX` bytes.
+///
+/// # Example
+/// ```
+/// use software_modem::frame::{deframe, frame};
+///
+/// let framed = frame(b"Hello, frame!", 7);
+/// assert_eq!(framed.len(), 13 + 10);
+///
+/// // trailing bytes after the frame are ignored
+/// let mut received = framed.clone();
+/// received.extend([0, 0, 0]);
+/// assert_eq!(deframe(&received).unwrap(), (7, b"Hello, frame!".to_vec()));
+/// ```
+pub fn frame(payload: &[u8], seq: u16) -> Vec<u8> {
+    let length = u32::try_from(payload.len()).expect("Payload must not exceed u32::MAX bytes");
+
+    let mut framed = Vec::with_capacity(payload.len() + OVERHEAD);
+    framed.extend(length.to_le_bytes());
+    framed.extend(seq.to_le_bytes());
+    framed.extend(payload);
+    framed.extend(crc32(&framed).to_le_bytes());
+
+    framed
+}
+
+/// Verifies the checksum of a frame and returns its sequence number and payload.
+///
+/// Any bytes after the end of the frame are ignored.
+///
+/// # Errors
+/// - [FrameError::TooShort] if the data ends before the frame does.
+/// - [FrameError::ChecksumMismatch] if the checksum does not match the received length, sequence number and payload.
+///
+/// # Example
+/// ```
+/// use software_modem::error::FrameError;
+/// use software_modem::frame::{deframe, frame};
+///
+/// let framed = frame(b"Hello, frame!", 0x1234);
+///
+/// let mut corrupted = framed.clone();
+/// corrupted[8] ^= 0x01;
+/// assert!(matches!(deframe(&corrupted), Err(FrameError::ChecksumMismatch { .. })));
+///
+/// // the sequence number is covered by the checksum as well
+/// let mut corrupted = framed.clone();
+/// corrupted[4] ^= 0x01;
+/// assert!(matches!(deframe(&corrupted), Err(FrameError::ChecksumMismatch { .. })));
+///
+/// assert!(matches!(deframe(&framed[..10]), Err(FrameError::TooShort { .. })));
+/// ```
+pub fn deframe(data: &[u8]) -> Result<(u16, Vec<u8>), FrameError> {
+    if data.len() < OVERHEAD {
+        return Err(FrameError::TooShort {
+            expected: OVERHEAD,
+            actual: data.len(),
+        });
+    }
+
+    let length = u32::from_le_bytes(data[..LENGTH_SIZE].try_into().unwrap()) as usize;
+    let frame_length = length.saturating_add(OVERHEAD);
+    if data.len() < frame_length {
+        return Err(FrameError::TooShort {
+            expected: frame_length,
+            actual: data.len(),
+        });
+    }
+
+    let checksum_start = PAYLOAD_START + length;
+    let expected = u32::from_le_bytes(data[checksum_start..frame_length].try_into().unwrap());
+    let actual = crc32(&data[..checksum_start]);
+    if expected != actual {
+        return Err(FrameError::ChecksumMismatch { expected, actual });
+    }
+
+    let seq = u16::from_le_bytes(data[LENGTH_SIZE..PAYLOAD_START].try_into().unwrap());
+    Ok((seq, data[PAYLOAD_START..checksum_start].to_vec()))
+}
+
+/// Returns the length of the whole frame announced by the length field at the start of `data`,
+/// or `None` if `data` ends within the length field.
+pub(crate) fn frame_length(data: &[u8]) -> Option<usize> {
+    let length = u32::from_le_bytes(data.get(..LENGTH_SIZE)?.try_into().unwrap()) as usize;
+    Some(length.saturating_add(OVERHEAD))
+}
@@ -0,0 +1,124 @@
+//! This module provides a block interleaver for the bits of one OFDM symbol.
+//!
+//! Consecutive data bits end up on neighbouring subcarriers,
+//! so a fade of a few adjacent subcarriers corrupts a burst of consecutive bits,
+//! which the [convolutional code](crate::fec) can not correct.
+//! The [Interleaver] spreads consecutive bits over the whole symbol, turning such a burst into scattered bit errors.
+
+/// Number of columns of the interleaver matrix.
+const COLUMNS: usize = 16;
+
+/// A block interleaver permuting the bits of a block of bytes.
+///
+/// The bits are written into a matrix of 16 columns row by row and read out column by column,
+/// so bits which were adjacent are about `num_bits / 16` bits apart afterwards.
+/// The permutation only depends on the block size, which is
+/// [bytes_per_symbol](crate::ofdm::modulator::OFDMModulator::bytes_per_symbol) for an OFDM symbol.
+///
+/// # Example
+/// ```
+/// use software_modem::interleaver::Interleaver;
+///
+/// // one OFDM symbol with 48 data subcarriers carrying 4 bits each
+/// let interleaver = Interleaver::new(24);
+/// let data: Vec<u8> = (0..24).map(|i| i * 11).collect();
+///
+/// let interleaved = interleaver.interleave(&data);
+/// assert_ne!(interleaved, data);
+/// assert_eq!(interleaver.deinterleave(&interleaved), data);
+///
+/// // a fade wipes out three adjacent subcarriers, which is a burst of 12 bits
+/// let mut corrupted = interleaved.clone();
+/// corrupted[10] ^= 0xff;
+/// corrupted[11] ^= 0xf0;
+///
+/// let received = interleaver.deinterleave(&corrupted);
+/// let errors: Vec<usize> = (0..24 * 8)
+///     .filter(|&bit| (received[bit / 8] ^ data[bit / 8]) >> (7 - bit % 8) & 1 == 1)
+///     .collect();
+/// assert_eq!(errors.len(), 12);
+/// assert!(errors.windows(2).all(|pair| pair[1] - pair[0] >= 8));
+/// ```
+pub struct Interleaver {
+    /// Position of every input bit in the interleaved block.
+    permutation: Vec<usize>,
+}
+
+impl Interleaver {
+    /// Creates an interleaver for blocks of `num_bytes` bytes.
+    pub fn new(num_bytes: usize) -> Self {
+        let num_bits = num_bytes * 8;
+        let full_rows = num_bits / COLUMNS;
+        let remainder = num_bits % COLUMNS;
+
+        // the first `remainder` columns hold one bit more
+        let permutation = (0..num_bits)
+            .map(|bit| {
+                let (row, column) = (bit / COLUMNS, bit % COLUMNS);
+                column * full_rows + column.min(remainder) + row
+            })
+            .collect();
+
+        Interleaver { permutation }
+    }
+
+    /// Permutes the bits of a block.
+    ///
+    /// # Panics
+    /// If the block does not have the size the interleaver was created for.
+    pub fn interleave(&self, data: &[u8]) -> Vec<u8> {
+        self.check_length(data);
+
+        let mut output = vec![0; data.len()];
+        for (bit, &position) in self.permutation.iter().enumerate() {
+            set_bit(&mut output, position, get_bit(data, bit));
+        }
+        output
+    }
+
+    /// Reverts the permutation of [interleave](Interleaver::interleave).
+    ///
+    /// # Panics
+    /// If the block does not have the size the interleaver was created for.
+    pub fn deinterleave(&self, data: &[u8]) -> Vec<u8> {
+        let mut output = vec![0; data.len()];
+        self.deinterleave_into(data, &mut output);
+        output
+    }
+
+    /// Reverts the permutation like [deinterleave](Interleaver::deinterleave), overwriting `output` instead of allocating it.
+    ///
+    /// # Panics
+    /// If the block or the output does not have the size the interleaver was created for.
+    pub(crate) fn deinterleave_into(&self, data: &[u8], output: &mut [u8]) {
+        self.check_length(data);
+        self.check_length(output);
+
+        output.fill(0);
+        for (bit, &position) in self.permutation.iter().enumerate() {
+            set_bit(output, bit, get_bit(data, position));
+        }
+    }
+
+    fn check_length(&self, data: &[u8]) {
+        if data.len() * 8 != self.permutation.len() {
+            panic!(
+                "Block length must be {} bytes, but got {} bytes",
+                self.permutation.len() / 8,
+                data.len()
+            );
+        }
+    }
+}
+
+/// Returns a bit of the data, counting from the most significant bit of the first byte.
+fn get_bit(data: &[u8], bit: usize) -> bool {
+    (data[bit / 8] >> (7 - bit % 8)) & 1 == 1
+}
+
+/// Sets a bit of the data, counting from the most significant bit of the first byte.
+fn set_bit(data: &mut [u8], bit: usize, value: bool) {
+    if value {
+        data[bit / 8] |= 1 << (7 - bit % 8);
+    }
+}
@@ -0,0 +1,243 @@
+//! This module provides reading and writing of samples and constellations.
+//!
+//! With the `wav` feature enabled,
+//! use `write_wav` or `write_wav_pcm16` to store the output of the [OFDM Modulator](crate::ofdm::modulator)
+//! and `read_wav` to load a recording for the [OFDM Demodulator](crate::ofdm::demodulator).
+//! `write_iq_wav` and `read_iq_wav` store complex baseband samples as stereo WAV files,
+//! the I/Q format of SDR tools like GNU Radio or SDR#.
+//! [write_constellation_csv] stores received constellation points for plotting.
+
+use std::io::Write;
+#[cfg(feature = "wav")]
+use std::path::Path;
+
+#[cfg(feature = "wav")]
+use hound::{SampleFormat, WavReader, WavSpec, WavWriter};
+use realfft::num_complex::Complex;
+#[cfg(feature = "wav")]
+use realfft::num_complex::Complex32;
+
+use crate::float::Float;
+
+/// Writes constellation points as CSV, one `re,im` line per point without a header.
+///
+/// Use it with [received_constellation](crate::ofdm::demodulator::OFDMDemodulator::received_constellation)
+/// to plot the received points in any tool that reads CSV.
+///
+/// # Example
+/// ```
+/// use realfft::num_complex::Complex32;
+/// use software_modem::io::write_constellation_csv;
+///
+/// let mut csv = Vec::new();
+/// write_constellation_csv(&mut csv, &[Complex32::new(1.0, -3.0), Complex32::new(0.5, 2.25)]).unwrap();
+///
+/// assert_eq!(String::from_utf8(csv).unwrap(), "1,-3\n0.5,2.25\n");
+/// ```
+pub fn write_constellation_csv<T: Float>(
+    mut writer: impl Write,
+    points: &[Complex<T>],
+) -> std::io::Result<()> {
+    for point in points {
+        writeln!(
+            writer,
+            "{},{}",
+            point.re.to_f64().unwrap(),
+            point.im.to_f64().unwrap()
+        )?;
+    }
+    Ok(())
+}
+
+/// Writes the samples as a mono WAV file with 32-bit float samples.
+///
+/// Requires the `wav` feature.
+///
+/// The samples are written as they are, without any normalization.
+/// Note that the modulator output can exceed ±1.0, which most audio software will interpret as clipping.
+/// Use [write_wav_pcm16] if the file has to stay within full scale.
+///
+/// # Example
+/// ```
+/// use software_modem::io::{read_wav, write_wav};
+///
+/// let path = std::env::temp_dir().join("software_modem_write_wav.wav");
+/// let samples = vec![0.0, 0.5, -1.5, 2.0];
+///
+/// write_wav(&path, &samples, 48000).unwrap();
+/// let (read_samples, sample_rate) = read_wav(&path).unwrap();
+///
+/// assert_eq!(read_samples, samples);
+/// assert_eq!(sample_rate, 48000);
+/// ```
+#[cfg(feature = "wav")]
+pub fn write_wav(path: &Path, samples: &[f32], sample_rate: u32) -> hound::Result<()> {
+    let spec = WavSpec {
+        channels: 1,
+        sample_rate,
+        bits_per_sample: 32,
+        sample_format: SampleFormat::Float,
+    };
+
+    let mut writer = WavWriter::create(path, spec)?;
+    for &sample in samples {
+        writer.write_sample(sample)?;
+    }
+    writer.finalize()
+}
+
+/// Writes the samples as a mono WAV file with 16-bit PCM samples.
+///
+/// Requires the `wav` feature.
+///
+/// As the modulator output can exceed ±1.0, the samples are normalized before the conversion,
+/// so the largest absolute sample maps to full scale (`i16::MAX`) and nothing clips.
+/// Samples that already fit within ±1.0 are not amplified.
+///
+/// Returns the factor the samples were scaled by before the conversion.
+///
+/// # Example
+/// ```
+/// use software_modem::io::{read_wav, write_wav_pcm16};
+///
+/// let path = std::env::temp_dir().join("software_modem_write_wav_pcm16.wav");
+/// let samples = vec![0.0, 1.0, -4.0, 2.0];
+///
+/// let scale = write_wav_pcm16(&path, &samples, 8000).unwrap();
+/// assert_eq!(scale, 0.25);
+///
+/// let (read_samples, _) = read_wav(&path).unwrap();
+/// for (read, original) in read_samples.iter().zip(samples.iter()) {
+///     assert!((read - original * scale).abs() < 1e-4);
+/// }
+/// ```
+#[cfg(feature = "wav")]
+pub fn write_wav_pcm16(path: &Path, samples: &[f32], sample_rate: u32) -> hound::Result<f32> {
+    let spec = WavSpec {
+        channels: 1,
+        sample_rate,
+        bits_per_sample: 16,
+        sample_format: SampleFormat::Int,
+    };
+
+    let peak = samples.iter().map(|s| s.abs()).fold(0.0, f32::max);
+    let scale = if peak > 1.0 { 1.0 / peak } else { 1.0 };
+
+    let mut writer = WavWriter::create(path, spec)?;
+    for &sample in samples {
+        writer.write_sample((sample * scale * i16::MAX as f32).round() as i16)?;
+    }
+    writer.finalize()?;
+
+    Ok(scale)
+}
+
+/// Reads a mono WAV file and returns its samples together with the sample rate.
+///
+/// Requires the `wav` feature.
+///
+/// Float files are returned as they are, integer PCM files are scaled to ±1.0 full scale.
+///
+/// # Errors
+/// Returns [hound::Error::Unsupported] if the file has more than one channel.
+#[cfg(feature = "wav")]
+pub fn read_wav(path: &Path) -> hound::Result<(Vec<f32>, u32)> {
+    let reader = WavReader::open(path)?;
+    let spec = reader.spec();
+    if spec.channels != 1 {
+        return Err(hound::Error::Unsupported);
+    }
+
+    let samples = read_samples(reader)?;
+
+    Ok((samples, spec.sample_rate))
+}
+
+/// Writes complex baseband samples as a stereo WAV file with 32-bit float samples.
+///
+/// Requires the `wav` feature.
+///
+/// The in-phase part goes to the left and the quadrature part to the right channel,
+/// interleaved sample by sample as `I, Q, I, Q, ...`, which GNU Radio and SDR# read as I/Q recordings.
+/// Like [write_wav], the samples are written as they are, without any normalization.
+///
+/// # Example
+/// ```
+/// use hound::WavReader;
+/// use realfft::num_complex::Complex32;
+/// use software_modem::io::{read_iq_wav, write_iq_wav};
+///
+/// let path = std::env::temp_dir().join("software_modem_write_iq_wav.wav");
+/// let samples: Vec<Complex32> = (0..100)
+///     .map(|n| Complex32::from_polar(0.9, n as f32 * 0.3))
+///     .collect();
+///
+/// write_iq_wav(&path, &samples, 250_000).unwrap();
+/// let (read_samples, sample_rate) = read_iq_wav(&path).unwrap();
+/// assert_eq!(read_samples, samples);
+/// assert_eq!(sample_rate, 250_000);
+///
+/// // the channels alternate between the real and the imaginary part
+/// let mut reader = WavReader::open(&path).unwrap();
+/// assert_eq!(reader.spec().channels, 2);
+/// let interleaved: Vec<f32> = reader.samples::<f32>().take(4).map(Result::unwrap).collect();
+/// assert_eq!(interleaved, [samples[0].re, samples[0].im, samples[1].re, samples[1].im]);
+/// ```
+#[cfg(feature = "wav")]
+pub fn write_iq_wav(path: &Path, samples: &[Complex32], sample_rate: u32) -> hound::Result<()> {
+    let spec = WavSpec {
+        channels: 2,
+        sample_rate,
+        bits_per_sample: 32,
+        sample_format: SampleFormat::Float,
+    };
+
+    let mut writer = WavWriter::create(path, spec)?;
+    for sample in samples {
+        writer.write_sample(sample.re)?;
+        writer.write_sample(sample.im)?;
+    }
+    writer.finalize()
+}
+
+/// Reads a stereo I/Q WAV file and returns its complex samples together with the sample rate.
+///
+/// Requires the `wav` feature.
+///
+/// The left channel is the in-phase and the right channel the quadrature part, see [write_iq_wav].
+/// Float files are returned as they are, integer PCM files, e.g. the 16-bit recordings of SDR#,
+/// are scaled so that full scale maps to ±1.0 on both parts.
+///
+/// # Errors
+/// Returns [hound::Error::Unsupported] if the file does not have exactly two channels.
+#[cfg(feature = "wav")]
+pub fn read_iq_wav(path: &Path) -> hound::Result<(Vec<Complex32>, u32)> {
+    let reader = WavReader::open(path)?;
+    let spec = reader.spec();
+    if spec.channels != 2 {
+        return Err(hound::Error::Unsupported);
+    }
+
+    let samples = read_samples(reader)?
+        .chunks_exact(2)
+        .map(|pair| Complex32::new(pair[0], pair[1]))
+        .collect();
+
+    Ok((samples, spec.sample_rate))
+}
+
+/// Reads all interleaved samples of a WAV file, scaling integer PCM samples to ±1.0 full scale.
+#[cfg(feature = "wav")]
+fn read_samples<R: std::io::Read>(mut reader: WavReader<R>) -> hound::Result<Vec<f32>> {
+    let spec = reader.spec();
+    match spec.sample_format {
+        SampleFormat::Float => reader.samples::<f32>().collect(),
+        SampleFormat::Int => {
+            let full_scale = (1_i64 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .samples::<i32>()
+                .map(|sample| sample.map(|s| s as f32 / full_scale))
+                .collect()
+        }
+    }
+}
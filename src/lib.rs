@@ -1,4 +1,25 @@
 #![doc = include_str!("../README.md")]
 
+/// Emits a debug event with the `log` feature, the arguments are not even evaluated without it.
+macro_rules! log_debug {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "log")]
+        log::debug!($($arg)*);
+    };
+}
+
+#[cfg(feature = "audio")]
+pub mod audio;
+pub mod bits;
+pub mod channel;
+pub mod dsp;
+pub mod error;
+pub mod fec;
+pub mod float;
+pub mod frame;
+pub mod interleaver;
+pub mod io;
+pub mod metrics;
 pub mod ofdm;
 pub mod qam;
+pub mod scrambler;
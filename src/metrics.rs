@@ -0,0 +1,249 @@
+//! This module provides error rate measurements for evaluating a link.
+//!
+//! Compare the transmitted and received data with [bit_error_rate]
+//! or the transmitted and decided constellation points with [symbol_error_rate],
+//! e.g. to plot error rates against the signal to noise ratio.
+//! [papr_db] measures the peak to average power ratio of a transmitted signal
+//! and [evm] the error vector magnitude of received constellation points.
+//! For planning a link, [required_snr_db] predicts the SNR a QAM order needs for a target bit error rate.
+
+use realfft::num_complex::Complex;
+
+use crate::float::{Float, cast};
+use crate::qam::QAMOrder;
+
+/// Returns the share of differing bits between the transmitted and the received bytes.
+///
+/// Only the first `min(tx.len(), rx.len())` bytes are compared, so a truncated reception does not panic.
+/// Returns `0.0` if there is nothing to compare.
+///
+/// # Example
+/// ```
+/// use software_modem::metrics::bit_error_rate;
+///
+/// let tx = [0u8; 8];
+/// let mut rx = tx;
+/// rx[3] ^= 0b0001_0000;
+///
+/// assert_eq!(bit_error_rate(&tx, &rx), 1.0 / 64.0);
+/// assert_eq!(bit_error_rate(&tx, &rx[..4]), 1.0 / 32.0);
+/// assert_eq!(bit_error_rate(&tx, &[]), 0.0);
+/// ```
+pub fn bit_error_rate(tx: &[u8], rx: &[u8]) -> f64 {
+    let length = tx.len().min(rx.len());
+    if length == 0 {
+        return 0.0;
+    }
+
+    let errors: u32 = tx.iter().zip(rx).map(|(a, b)| (a ^ b).count_ones()).sum();
+
+    errors as f64 / (length * 8) as f64
+}
+
+/// Returns the share of differing symbols between the transmitted and the received constellation points.
+///
+/// The received symbols are expected to be hard decisions, i.e. constellation points,
+/// so symbols count as equal only if they match exactly.
+/// Only the first `min(tx.len(), rx.len())` symbols are compared,
+/// and `0.0` is returned if there is nothing to compare.
+///
+/// # Example
+/// ```
+/// use software_modem::metrics::symbol_error_rate;
+/// use software_modem::qam::{QAMModem, QAMOrder};
+///
+/// let modem = QAMModem::new(QAMOrder::QAM16);
+/// let tx = modem.modulate(&[0x12, 0x34]);
+/// let rx = modem.modulate(&[0x12, 0x35]);
+///
+/// assert_eq!(symbol_error_rate(&tx, &rx), 0.25);
+/// ```
+pub fn symbol_error_rate<T: Float>(tx: &[Complex<T>], rx: &[Complex<T>]) -> f64 {
+    let length = tx.len().min(rx.len());
+    if length == 0 {
+        return 0.0;
+    }
+
+    let errors = tx.iter().zip(rx).filter(|(a, b)| a != b).count();
+
+    errors as f64 / length as f64
+}
+
+/// Returns the RMS error vector magnitude of the received constellation points in percent and in dB.
+///
+/// The error power is normalized by the mean power of the ideal points,
+/// so the result does not depend on the scale of the constellation.
+/// Both slices hold the same symbols, e.g. the equalized points and their decisions,
+/// only the first `min(received.len(), ideal.len())` points are compared.
+/// Without errors or without any points to compare, the EVM is `0.0` percent and negative infinity in dB.
+///
+/// See [OFDMDemodulator::evm](crate::ofdm::demodulator::OFDMDemodulator::evm)
+/// to measure a received OFDM symbol against its own decisions.
+///
+/// # Example
+/// ```
+/// use realfft::num_complex::Complex32;
+/// use software_modem::metrics::evm;
+/// use software_modem::qam::{QAMModem, QAMOrder};
+///
+/// // QAM-4 points have a power of 2, an offset of 0.1 is an error power of 0.01
+/// let ideal = QAMModem::new(QAMOrder::QAM4).modulate(&[0x1b, 0xe4]);
+/// let received: Vec<Complex32> = ideal.iter().map(|point| point + Complex32::new(0.1, 0.0)).collect();
+///
+/// let (percent, db) = evm(&received, &ideal);
+/// assert!((percent - 100.0 * 0.005f32.sqrt()).abs() < 1e-4);
+/// assert!((db - 10.0 * 0.005f32.log10()).abs() < 1e-4);
+/// assert_eq!(evm(&ideal, &ideal), (0.0, f32::NEG_INFINITY));
+/// ```
+pub fn evm<T: Float>(received: &[Complex<T>], ideal: &[Complex<T>]) -> (T, T) {
+    let error_power: T = received
+        .iter()
+        .zip(ideal)
+        .map(|(point, ideal)| (point - ideal).norm_sqr())
+        .sum();
+    let reference_power: T = ideal
+        .iter()
+        .take(received.len())
+        .map(|ideal| ideal.norm_sqr())
+        .sum();
+    if error_power == T::zero() {
+        return (T::zero(), T::neg_infinity());
+    }
+
+    let ratio = (error_power / reference_power).sqrt();
+    (cast::<T>(100.0) * ratio, cast::<T>(20.0) * ratio.log10())
+}
+
+/// Returns the peak to average power ratio of a signal in dB.
+///
+/// OFDM signals occasionally peak far above their average power,
+/// which either clips in the hardware or forces a lower average level.
+/// Returns `0.0` for an empty or silent signal.
+///
+/// # Example
+/// ```
+/// use software_modem::metrics::papr_db;
+///
+/// let sine: Vec<f32> = (0..1000).map(|n| (n as f32 * 0.1).sin()).collect();
+/// assert!((papr_db(&sine) - 3.01).abs() < 0.05);
+///
+/// let mut pulse = vec![0.0f32; 100];
+/// pulse[0] = 1.0;
+/// assert!((papr_db(&pulse) - 20.0).abs() < 1e-4);
+/// ```
+pub fn papr_db<T: Float>(samples: &[T]) -> T {
+    let peak = samples
+        .iter()
+        .fold(T::zero(), |peak, &sample| peak.max(sample * sample));
+    let average = samples.iter().map(|&sample| sample * sample).sum::<T>()
+        / cast(samples.len().max(1) as f64);
+    if average == T::zero() {
+        return T::zero();
+    }
+
+    cast::<T>(10.0) * (peak / average).log10()
+}
+
+/// Returns the approximate SNR in dB a QAM order needs on an AWGN channel to reach the target bit error rate.
+///
+/// Uses the textbook approximation for Gray coded square constellations,
+/// `BER = 4 / k * (1 - 1 / sqrt(M)) * Q(sqrt(3 * SNR / (M - 1)))` for `M` points of `k` bits,
+/// and the exact `BER = Q(sqrt(2 * SNR))` for BPSK.
+/// The SNR is the ratio of the mean power of the constellation points to the noise power on them, i.e. Es/N0,
+/// which is the SNR of a subcarrier after equalization.
+/// Since the noise of [add_awgn](crate::channel::add_awgn) spreads over all subcarriers and the signal only over the used ones,
+/// the SNR of a subcarrier is higher than the one of the samples if some subcarriers are unused.
+///
+/// Returns negative infinity if the constellation reaches the target without any signal.
+///
+/// # Panics
+/// If `target_ber` is not between `0` and `1`, both excluded.
+///
+/// # Example
+/// ```
+/// use software_modem::metrics::required_snr_db;
+/// use software_modem::qam::QAMOrder;
+///
+/// // denser constellations need a better channel
+/// let snr_db: Vec<f64> = QAMOrder::all().map(|order| required_snr_db(order, 1e-3)).collect();
+/// assert!(snr_db.windows(2).all(|pair| pair[0] < pair[1]));
+///
+/// // the textbook values of 6.8 dB for BPSK and 9.8 dB for QAM-4, and 16.5 dB for QAM-16,
+/// // which is an Eb/N0 of 10.5 dB for its 4 bits per symbol
+/// assert!((required_snr_db(QAMOrder::BPSK, 1e-3) - 6.8).abs() < 0.05);
+/// assert!((required_snr_db(QAMOrder::QAM4, 1e-3) - 9.8).abs() < 0.05);
+/// assert!((required_snr_db(QAMOrder::QAM16, 1e-3) - 16.5).abs() < 0.1);
+///
+/// // a lower error rate costs SNR
+/// assert!(required_snr_db(QAMOrder::QAM16, 1e-6) > required_snr_db(QAMOrder::QAM16, 1e-3) + 3.0);
+/// ```
+pub fn required_snr_db(order: QAMOrder, target_ber: f64) -> f64 {
+    if !(target_ber > 0.0 && target_ber < 1.0) {
+        panic!("Target bit error rate must be between 0 and 1, but got {target_ber}");
+    }
+
+    // the BER is a factor times Q(sqrt(scale * SNR))
+    let (factor, scale) = match order {
+        QAMOrder::BPSK => (1.0, 2.0),
+        _ => {
+            let num_points = (1u32 << order.bits_per_symbol()) as f64;
+            (
+                4.0 / order.bits_per_symbol() as f64 * (1.0 - 1.0 / num_points.sqrt()),
+                3.0 / (num_points - 1.0),
+            )
+        }
+    };
+
+    let argument = inverse_q(target_ber / factor);
+    if argument <= 0.0 {
+        return f64::NEG_INFINITY;
+    }
+    10.0 * (argument * argument / scale).log10()
+}
+
+/// Returns the tail probability of the standard normal distribution above `x`.
+fn q(x: f64) -> f64 {
+    0.5 * erfc(x / std::f64::consts::SQRT_2)
+}
+
+/// Returns the `x` with `q(x) == p`, or `0.0` if `p` is at least one half.
+fn inverse_q(p: f64) -> f64 {
+    // q falls monotonically, bisect until the interval no longer shrinks
+    let (mut low, mut high) = (0.0, 40.0);
+    if p >= 0.5 {
+        return low;
+    }
+    for _ in 0..100 {
+        let mid = 0.5 * (low + high);
+        if q(mid) > p {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    0.5 * (low + high)
+}
+
+/// Returns the complementary error function with a relative error below `1.2e-7`.
+///
+/// Chebyshev fit of Numerical Recipes, which keeps its relative accuracy far into the tail.
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let polynomial = [
+        0.17087277,
+        -0.82215223,
+        1.48851587,
+        -1.13520398,
+        0.27886807,
+        -0.18628806,
+        0.09678418,
+        0.37409196,
+        1.00002368,
+        -1.26551223,
+    ]
+    .iter()
+    .fold(0.0, |sum, coefficient| sum * t + coefficient);
+    let result = t * (-z * z + polynomial).exp();
+    if x >= 0.0 { result } else { 2.0 - result }
+}
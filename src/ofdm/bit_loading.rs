@@ -0,0 +1,142 @@
+//! Mapping of the bits of an OFDM symbol onto data subcarriers with individual QAM orders.
+//!
+//! See [bit_loading](crate::ofdm::modulator::OFDMModulatorConfig::bit_loading) to configure the orders.
+
+use realfft::num_complex::Complex;
+
+use crate::{
+    bits::{BitReader, BitWriter},
+    float::Float,
+    qam::{QAMModem, QAMOrder},
+};
+
+/// The QAM modems of the data subcarriers.
+pub(super) struct BitLoading<T: Float> {
+    /// One modem per distinct QAM order.
+    modems: Vec<QAMModem<T>>,
+    /// Modem of every data subcarrier, as an index into `modems`.
+    subcarrier_modems: Vec<usize>,
+    /// Bits carried by every data subcarrier.
+    subcarrier_bits: Vec<u32>,
+}
+
+impl<T: Float> BitLoading<T> {
+    /// Creates the modems for the given QAM order of every data subcarrier.
+    pub(super) fn new(orders: &[QAMOrder]) -> Self {
+        let mut distinct_orders: Vec<QAMOrder> = Vec::new();
+        let subcarrier_modems = orders
+            .iter()
+            .map(|order| {
+                distinct_orders
+                    .iter()
+                    .position(|distinct| distinct == order)
+                    .unwrap_or_else(|| {
+                        distinct_orders.push(*order);
+                        distinct_orders.len() - 1
+                    })
+            })
+            .collect();
+
+        BitLoading {
+            modems: distinct_orders
+                .into_iter()
+                .map(QAMModem::from_order)
+                .collect(),
+            subcarrier_modems,
+            subcarrier_bits: orders.iter().map(|order| order.bits_per_symbol()).collect(),
+        }
+    }
+
+    /// Number of bits all data subcarriers carry together.
+    pub(super) fn bits_per_symbol(&self) -> u32 {
+        self.subcarrier_bits.iter().sum()
+    }
+
+    /// Mean energy all data subcarriers carry together.
+    pub(super) fn average_power(&self) -> T {
+        self.subcarrier_modems
+            .iter()
+            .map(|&modem| self.modems[modem].average_power())
+            .sum()
+    }
+
+    /// Number of leading data subcarriers needed to carry `num_bits` bits.
+    pub(super) fn num_subcarriers(&self, num_bits: usize) -> usize {
+        let mut remaining = num_bits;
+        self.subcarrier_bits
+            .iter()
+            .take_while(|&&bits| {
+                let needed = remaining > 0;
+                remaining = remaining.saturating_sub(bits as usize);
+                needed
+            })
+            .count()
+    }
+
+    /// Maps the bits of `data` onto the data subcarriers in order, replacing the content of `symbols`.
+    ///
+    /// Only the subcarriers needed for the data are filled, the last one is padded with zero bits.
+    pub(super) fn modulate_into(&self, data: &[u8], symbols: &mut Vec<Complex<T>>) {
+        self.modulate_from(&mut BitReader::new(data, 1), symbols);
+    }
+
+    /// Maps the next bits of `reader` onto the data subcarriers in order, replacing the content of `symbols`.
+    ///
+    /// Unlike [modulate_into](BitLoading::modulate_into), the symbol need not start at a byte boundary,
+    /// the following bits are left in the reader for the next symbol.
+    pub(super) fn modulate_from(&self, reader: &mut BitReader, symbols: &mut Vec<Complex<T>>) {
+        symbols.clear();
+
+        for (&modem, &bits) in self.subcarrier_modems.iter().zip(&self.subcarrier_bits) {
+            match reader.read(bits) {
+                Some(value) => symbols.push(self.modems[modem].point(value)),
+                None => break,
+            }
+        }
+    }
+
+    /// Returns the ideal constellation points closest to the points of the leading data subcarriers.
+    pub(super) fn nearest_points(&self, points: &[Complex<T>]) -> Vec<Complex<T>> {
+        points
+            .iter()
+            .enumerate()
+            .map(|(subcarrier, point)| self.nearest_point(subcarrier, point))
+            .collect()
+    }
+
+    /// Returns the ideal constellation point of the given data subcarrier closest to `point`.
+    pub(super) fn nearest_point(&self, subcarrier: usize, point: &Complex<T>) -> Complex<T> {
+        let modem = &self.modems[self.subcarrier_modems[subcarrier]];
+        modem.point(modem.decide(point))
+    }
+
+    /// Slices the points of the leading data subcarriers back into whole bytes.
+    pub(super) fn demodulate(&self, points: &[Complex<T>]) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.bits_per_symbol().div_ceil(8) as usize);
+        self.demodulate_into(points, &mut bytes);
+        bytes
+    }
+
+    /// Slices the points like [demodulate](BitLoading::demodulate), appending the bytes to `bytes`.
+    ///
+    /// Does not allocate if `bytes` has room for them.
+    pub(super) fn demodulate_into(&self, points: &[Complex<T>], bytes: &mut Vec<u8>) {
+        let mut writer = BitWriter::from_bytes(std::mem::take(bytes));
+        self.demodulate_bits(points, &mut writer);
+
+        // the bits of an incomplete last byte are the padding of modulate_into
+        *bytes = writer.into_whole_bytes();
+    }
+
+    /// Slices the points of the leading data subcarriers, appending their bits to `writer`,
+    /// the counterpart of [modulate_from](BitLoading::modulate_from).
+    pub(super) fn demodulate_bits(&self, points: &[Complex<T>], writer: &mut BitWriter) {
+        for ((point, &modem), &bits) in points
+            .iter()
+            .zip(&self.subcarrier_modems)
+            .zip(&self.subcarrier_bits)
+        {
+            writer.write(self.modems[modem].decide(point), bits);
+        }
+    }
+}
@@ -1,50 +1,261 @@
-use std::sync::Arc;
+use std::{
+    cmp::Ordering,
+    ops::{Add, Div},
+    sync::Arc,
+};
 
-use realfft::{RealFftPlanner, RealToComplex, num_complex::Complex32};
+use realfft::{RealFftPlanner, RealToComplex, num_complex::Complex};
+use rustfft::{Fft, FftPlanner};
 use smart_default::SmartDefault;
 
 use crate::{
-    ofdm::OFDMConstants,
-    qam::{QAMModem, QAMOrder},
+    bits::BitWriter,
+    dsp::{hilbert, interpolate},
+    error::{ConfigError, FrameError, HeaderError, RxError},
+    fec,
+    float::{Float, cast},
+    frame,
+    interleaver::Interleaver,
+    metrics,
+    ofdm::{
+        CyclicPrefix, OFDMConstants, SubcarrierMapping,
+        bit_loading::BitLoading,
+        check_fft_length, header,
+        preamble::{long_training_spectrum, preamble_spectrum, timing_metric},
+    },
+    qam::QAMOrder,
+    scrambler,
 };
 
-#[allow(dead_code)]
-const PILOT_VALUE_TO_BE_CHANGED: Complex32 = Complex32 { re: 1.0, im: 0.0 };
+/// Number of bytes to decode before the length of a frame is read.
+const MIN_DECODED_BYTES: usize = 8;
+
+/// Largest distance of an equalized point from its decision for the point to refine the channel estimate,
+/// a quarter of the distance between neighbouring constellation points.
+const DECISION_DIRECTED_MAX_ERROR: f64 = 0.5;
 
-pub struct OFDMDemodulator {
-    fft: Arc<dyn RealToComplex<f32>>,
-    qam_modem: QAMModem,
-    constants: OFDMConstants,
+/// Number of subcarriers on either side whose observations are averaged into the refined channel estimate.
+const DECISION_DIRECTED_HALF_WINDOW: usize = 2;
+
+/// OFDM Demodulator
+///
+/// The counterpart to the [OFDM Modulator](crate::ofdm::modulator::OFDMModulator).
+/// It strips the cyclic prefix, transforms the symbol back into the frequency domain,
+/// equalizes the data subcarriers with a channel estimate derived from the pilot subcarriers
+/// and demodulates the QAM symbols on the data subcarriers back into bytes.
+/// It has to be configured with the same parameters as the modulator.
+///
+/// # Example
+/// ```
+/// use software_modem::ofdm::CyclicPrefix;
+/// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+/// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+/// use software_modem::qam::QAMOrder;
+///
+/// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+///     num_subcarriers: 64,
+///     cyclic_prefix: CyclicPrefix::Samples(4),
+///     pilot_subcarrier_every: 4,
+///     qam_order: QAMOrder::QAM16,
+///     ..Default::default()
+/// })
+/// .unwrap();
+/// let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+///     num_subcarriers: 64,
+///     cyclic_prefix: CyclicPrefix::Samples(4),
+///     pilot_subcarrier_every: 4,
+///     qam_order: QAMOrder::QAM16,
+///     ..Default::default()
+/// })
+/// .unwrap();
+///
+/// let data = "Hello, OFDM!            ".as_bytes();
+/// let mut symbol = vec![0.0; modulator.get_symbol_length()];
+/// modulator.modulate_buffer_as_symbol(data, &mut symbol).unwrap();
+///
+/// assert_eq!(symbol.len(), demodulator.get_symbol_length());
+/// assert_eq!(demodulator.demodulate_symbol_from_buffer(&symbol), data);
+/// ```
+///
+/// The samples are `f32` by default, see [from_config](OFDMDemodulator::from_config) for other [float types](Float).
+///
+/// # Logging
+/// With the `log` feature, the demodulator emits debug events through the [log](https://crates.io/crates/log) facade:
+/// the detected frame start, the refined timing, the estimated CFO,
+/// and the mean channel gain and the error vector magnitude of every symbol.
+/// Without the feature, none of them is even computed.
+/// ```
+/// # #[cfg(feature = "log")]
+/// # {
+/// use std::sync::Mutex;
+///
+/// use log::{Level, LevelFilter, Log, Metadata, Record};
+/// use software_modem::ofdm::CyclicPrefix;
+/// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+/// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+///
+/// // collects the messages of all events
+/// struct Capture(Mutex<Vec<String>>);
+///
+/// impl Log for Capture {
+///     fn enabled(&self, metadata: &Metadata) -> bool {
+///         metadata.level() <= Level::Debug
+///     }
+///
+///     fn log(&self, record: &Record) {
+///         self.0.lock().unwrap().push(record.args().to_string());
+///     }
+///
+///     fn flush(&self) {}
+/// }
+///
+/// static CAPTURE: Capture = Capture(Mutex::new(Vec::new()));
+/// log::set_logger(&CAPTURE).unwrap();
+/// log::set_max_level(LevelFilter::Debug);
+///
+/// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+///     num_subcarriers: 64,
+///     cyclic_prefix: CyclicPrefix::Samples(16),
+///     ..Default::default()
+/// })
+/// .unwrap();
+/// let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+///     num_subcarriers: 64,
+///     cyclic_prefix: CyclicPrefix::Samples(16),
+///     ..Default::default()
+/// })
+/// .unwrap();
+///
+/// let (packet, padding) = modulator.modulate_packet(b"Hello, logged OFDM!").unwrap();
+/// let mut samples = vec![0.0; 300];
+/// samples.extend(modulator.generate_preamble());
+/// samples.extend(&packet);
+///
+/// let start = demodulator.find_frame_start(&samples).unwrap();
+/// let data_start = demodulator.refine_timing(&samples, start + demodulator.get_symbol_length());
+/// demodulator.estimate_cfo(&samples[data_start..data_start + demodulator.get_symbol_length()]);
+/// let payload = demodulator.demodulate_packet(&samples[data_start..data_start + packet.len()], padding);
+/// assert_eq!(payload, b"Hello, logged OFDM!");
+///
+/// let events = CAPTURE.0.lock().unwrap();
+/// assert!(events.iter().any(|event| event.starts_with(&format!("frame found at sample {start}"))));
+/// assert!(events.contains(&format!("timing refined from sample {} to {data_start}", start + 144)));
+/// assert!(events.iter().any(|event| event.starts_with("estimated CFO")));
+/// assert!(events.iter().any(|event| event.starts_with("symbol 0: mean channel gain")));
+/// assert!(events.iter().any(|event| event.starts_with("symbol 0: EVM")));
+/// # }
+/// ```
+pub struct OFDMDemodulator<T: Float = f32> {
+    fft: Arc<dyn RealToComplex<T>>,
+    /// Complex forward FFT of the [I/Q mode](OFDMDemodulator::demodulate_iq).
+    iq_fft: Arc<dyn Fft<T>>,
+    constants: OFDMConstants<T>,
+    preamble_threshold: T,
+    trailing_gap: usize,
+    equalizer: Equalizer,
+    decision_directed_iterations: u32,
+    max_evm_percent: Option<T>,
+    convolutional_coding: bool,
+    #[cfg(feature = "rs")]
+    reed_solomon: bool,
+    scrambling: bool,
+    interleaver: Option<Interleaver>,
 }
 
-impl OFDMDemodulator {
-    /// Creates a new OFDM modulator with the given [configuration](OFDMModulatorConfig).
-    pub fn new(config: OFDMDemodulatorConfig) -> Self {
-        let qam_modem = QAMModem::new(config.qam_order);
+impl OFDMDemodulator<f32> {
+    /// Creates a new OFDM demodulator with the given [configuration](OFDMDemodulatorConfig).
+    ///
+    /// # Errors
+    /// - [ConfigError::NoSubcarriers] if `num_subcarriers` is zero.
+    /// - [ConfigError::CyclicPrefixTooLong] if the cyclic prefix is not shorter than `2 * num_subcarriers` samples.
+    /// - [ConfigError::CyclicPrefixFractionOutOfRange] if the cyclic prefix is given as a fraction
+    ///   which is not at least `0.0` and below `1.0`.
+    /// - [ConfigError::PilotSpacingTooSmall] if `pilot_subcarrier_every` is below 2
+    ///   and no `pilot_subcarrier_indices` are configured.
+    /// - [ConfigError::PilotSpacingTooLarge] if `pilot_subcarrier_every` is not below `num_subcarriers`,
+    ///   so no pilot lies between DC and nyquist, and no `pilot_subcarrier_indices` are configured.
+    /// - [ConfigError::PilotSubcarrierOutOfRange] if a pilot subcarrier index is not between DC and nyquist.
+    /// - [ConfigError::NullSubcarrierOutOfRange] if a null subcarrier index exceeds `num_subcarriers`.
+    /// - [ConfigError::SpectralMaskOutOfRange] if a range of the spectral mask is reversed or exceeds `num_subcarriers`.
+    /// - [ConfigError::PilotSequenceTooShort] if a pilot sequence is configured
+    ///   which holds fewer values than there are pilot subcarriers.
+    /// - [ConfigError::WindowTooLong] if `window_length` exceeds the length of the cyclic prefix.
+    /// - [ConfigError::SlmCandidatesOutOfRange] if `num_slm_candidates` is zero or exceeds
+    ///   [MAX_SLM_CANDIDATES](crate::ofdm::slm::MAX_SLM_CANDIDATES).
+    /// - [ConfigError::NoRepetitions] if `repetition` is zero.
+    /// - [ConfigError::BitLoadingLengthMismatch] if a bit loading table is configured
+    ///   which does not hold one QAM order per data subcarrier.
+    /// - [ConfigError::InvalidSubcarrierMapping] if a custom subcarrier mapping does not list every data subcarrier once.
+    /// - [ConfigError::NoPayloadCapacity] if the data subcarriers of a symbol carry less than one byte,
+    ///   e.g. because all of them are nulled.
+    /// - [ConfigError::FftLengthMismatch] if an FFT is configured which does not transform `2 * num_subcarriers` samples.
+    pub fn new(config: OFDMDemodulatorConfig) -> Result<Self, ConfigError> {
+        Self::from_config(config)
+    }
+}
 
+impl<T: Float> OFDMDemodulator<T> {
+    /// Creates a new OFDM demodulator with the given [configuration](OFDMDemodulatorConfig), working with the float type `T`.
+    ///
+    /// The default FFT is planned for `T` as well.
+    /// See [OFDMModulator::from_config](crate::ofdm::modulator::OFDMModulator::from_config) for an example.
+    ///
+    /// # Errors
+    /// See [new](OFDMDemodulator::new).
+    pub fn from_config(config: OFDMDemodulatorConfig<T>) -> Result<Self, ConfigError> {
         let constants = OFDMConstants::new(
             config.num_subcarriers,
             config.pilot_subcarrier_every,
-            config.cyclic_prefix_length,
+            &config.pilot_subcarrier_indices,
+            &config.null_subcarrier_indices,
+            &config.spectral_mask,
+            &config.subcarrier_mapping,
+            config.cyclic_prefix,
+            config.window_length,
             config.qam_order,
-            qam_modem.bits_per_symbol(),
-        );
+            &config.bit_loading,
+            &config.pilot_sequence,
+            config.pilot_polarity,
+            config.num_slm_candidates,
+            config.repetition,
+        )?;
+
+        if let Some(fft) = &config.fft {
+            check_fft_length(fft.len(), config.num_subcarriers)?;
+        }
+        let fft = config
+            .fft
+            .unwrap_or_else(|| plan_default_fft(config.num_subcarriers));
 
-        let fft = config.fft.unwrap_or_else(|| {
-            RealFftPlanner::<f32>::new().plan_fft_forward(2 * config.num_subcarriers as usize)
-        });
+        let iq_fft = FftPlanner::<T>::new().plan_fft_forward(2 * config.num_subcarriers as usize);
 
-        OFDMDemodulator {
+        let interleaver = config
+            .interleaving
+            .then(|| Interleaver::new(constants.bytes_per_symbol()));
+
+        Ok(OFDMDemodulator {
             fft,
-            qam_modem,
+            iq_fft,
             constants,
-        }
+            preamble_threshold: cast(config.preamble_threshold as f64),
+            trailing_gap: config.trailing_gap as usize,
+            equalizer: config.equalizer,
+            decision_directed_iterations: config.decision_directed_iterations,
+            max_evm_percent: config
+                .max_evm_percent
+                .map(|max_evm_percent| cast(max_evm_percent as f64)),
+            convolutional_coding: config.convolutional_coding,
+            #[cfg(feature = "rs")]
+            reed_solomon: config.reed_solomon,
+            scrambling: config.scrambling,
+            interleaver,
+        })
     }
 
     /// Demodulates a single OFDM symbol from the given input buffer.
     ///
     /// The input buffer must have a length equal to the expected symbol length,
-    /// which is `2 * num_subcarriers + cyclic_prefix_length`,
+    /// which is `2 * num_subcarriers` plus the length of the cyclic prefix,
     /// or: `self.get_symbol_length()`.
     ///
     /// # Panics
@@ -52,24 +263,268 @@ impl OFDMDemodulator {
     ///
     /// # Example
     /// ```
+    /// use software_modem::ofdm::CyclicPrefix;
     /// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
     /// use software_modem::qam::QAMOrder;
     ///
     /// let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
     ///     num_subcarriers: 64,
-    ///     cyclic_prefix_length: 4,
+    ///     cyclic_prefix: CyclicPrefix::Samples(4),
     ///     pilot_subcarrier_every: 4,
     ///     qam_order: QAMOrder::QAM16,
-    ///     fft: None,
-    /// });
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
     ///
-    /// let input_buffer = vec![1.5578203, 10.757554, -60.41084, -22.017548, 170.0, -42.44605, 54.674767, 22.390936, 6.2399883, -4.9697013, 22.430595, 17.925348, -2.8670907, -23.034523, -11.360638, 0.024665833, -3.071948, -7.734082, 3.0158787, 21.293457, 0.82842445, -35.719788, -33.072395, -19.85823, -0.14415121, -1.0148859, 1.0802565, 1.3617897, 1.0318756, -7.007739, 2.1753244, 15.374781, 21.054213, 0.07890889, -1.2171764, -3.3891459, -2.0, 41.081707, -4.085703, 0.47892523, -0.24726725, 6.605378, -11.310527, -4.8029222, -3.2976942, 6.129626, -5.986044, 17.46577, 33.94296, 56.904747, 10.276956, 26.332466, -21.798985, -45.932056, 16.227457, -11.979431, -5.4379044, -10.107577, 12.925878, 5.066286, 7.585412, -2.9996142, 5.774047, -8.335448, -6.82592, -9.922427, 26.371922, 19.215015, -6.0, -0.36616898, -44.328407, -32.542404, -11.508089, -6.3610272, -14.268342, -14.096208, 4.5239453, 3.1953726, -9.655043, -32.157936, -18.771591, -23.806992, -12.9909935, -65.67099, -4.8284245, 67.96052, 26.218727, 38.012096, 13.98769, 15.913272, -13.206813, -18.395777, -10.68873, 22.887703, 19.290443, -5.741539, -23.786112, -0.9140358, 27.256096, 6.191677, -42.0, 1.7305107, -14.260653, 9.6725445, -2.4846325, 4.7253504, -4.8517256, 0.97378147, -6.3591604, 13.709526, 19.001724, 14.6675, -20.099422, -25.363672, -8.301841, 18.045067, 17.798985, 13.69133, -17.373789, -6.1744323, -16.405634, -4.7908087, -8.799321, 11.967701, -5.9285583, -12.88035, -35.239815, -1.2977934, 1.5578203, 10.757554, -60.41084, -22.017548];
+    /// let input_buffer = vec![9.05898, -12.156946, -7.408151, 4.2209883, 122.0, -12.306044, -2.617607, 18.477463, 20.92475, 14.469386, 4.077007, -3.2443385, -19.407051, -24.469095, -15.839418, 1.5884099, 2.8588753, 2.9958897, 7.269639, 11.281305, 1.1126976, -38.600235, -38.667408, -3.3315783, 19.08457, 15.077415, 1.5392227, 10.077158, 16.244547, 5.8428364, -14.068358, -8.090888, 7.1389017, 8.489253, -12.48889, -14.977501, 6.0000005, 28.43967, 13.329844, -21.443176, -48.42842, -41.26578, -19.710575, 2.5980263, 5.8512287, 0.23176241, -9.537546, -0.14152575, 24.085457, 53.466354, 52.400803, 20.0388, 51.45584, -38.774704, -19.087183, 6.7157125, -3.8297672, -30.790676, -39.858704, -11.279367, 10.216277, 1.1795657, -27.869131, -32.045296, -18.65665, -6.921402, -11.120058, -5.488862, 2.0000038, -3.0725732, -29.214117, -35.987534, -15.740347, 10.848258, 7.2493606, -3.7884889, -1.5635109, 15.421064, 10.558971, -11.427988, -35.15492, -39.67769, -37.230194, -17.55874, -61.112698, 52.420013, 51.738842, 24.090427, -0.9114609, 4.9124546, 11.433788, 3.6154282, -14.587694, -14.121054, -8.379453, -4.8585887, -5.541232, 10.589095, 23.0171, 16.245375, -9.999996, -13.061054, 10.50188, 38.953247, 35.24402, 15.948136, 0.38420868, 4.434801, 7.1193333, 8.816269, 6.817993, 9.981104, 0.21058369, -16.784554, -30.440247, -13.761364, 0.544157, 24.954922, -1.9842491, -27.47456, -22.343342, 10.800805, 18.885693, -2.413219, -19.87313, 7.098652, 42.31694, 44.994774, 9.05898, -12.156946, -7.408151, 4.2209883];
     ///
     /// let demodulated_data = demodulator.demodulate_symbol_from_buffer(&input_buffer);
     ///
     /// assert_eq!(demodulated_data, "Hello, OFDM!            ".as_bytes());
     /// ```
-    pub fn demodulate_symbol_from_buffer(&self, input_buffer: &[f32]) -> Vec<u8> {
+    pub fn demodulate_symbol_from_buffer(&self, input_buffer: &[T]) -> Vec<u8> {
+        self.constants
+            .bit_loading
+            .demodulate(&self.received_constellation(input_buffer))
+    }
+
+    /// Demodulates a single OFDM symbol like [demodulate_symbol_from_buffer](OFDMDemodulator::demodulate_symbol_from_buffer)
+    /// unless its [error vector magnitude](OFDMDemodulator::evm) exceeds the configured
+    /// [max_evm_percent](OFDMDemodulatorConfig::max_evm_percent).
+    ///
+    /// Returns `None` for a rejected symbol, which holds too many errors to be trusted as a whole,
+    /// unlike the per point [erasures](crate::qam::QAMModem::demodulate_with_erasures) of the QAM modem.
+    /// Without a configured threshold, every symbol is returned.
+    ///
+    /// # Panics
+    /// If the input buffer length does not match `self.get_symbol_length()`.
+    ///
+    /// # Example
+    /// ```
+    /// use rand::{SeedableRng, rngs::StdRng};
+    /// use software_modem::channel::add_awgn;
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    ///
+    /// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(4),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    /// let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(4),
+    ///     max_evm_percent: Some(30.0),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    ///
+    /// let data = vec![0xc3; modulator.bytes_per_symbol()];
+    /// let mut symbol = vec![0.0; modulator.get_symbol_length()];
+    /// modulator.modulate_buffer_as_symbol(&data, &mut symbol).unwrap();
+    /// assert_eq!(demodulator.demodulate_confident_symbol(&symbol), Some(data));
+    ///
+    /// // the noise is as strong as the signal
+    /// add_awgn(&mut symbol, 0.0, &mut StdRng::seed_from_u64(11));
+    /// assert!(demodulator.evm(&symbol).0 > 30.0);
+    /// assert_eq!(demodulator.demodulate_confident_symbol(&symbol), None);
+    /// ```
+    pub fn demodulate_confident_symbol(&self, input_buffer: &[T]) -> Option<Vec<u8>> {
+        let points = self.received_constellation(input_buffer);
+        if let Some(max_evm_percent) = self.max_evm_percent {
+            let (evm_percent, _) = self.evm_of_points(&points);
+            if evm_percent.is_nan() || evm_percent > max_evm_percent {
+                return None;
+            }
+        }
+
+        Some(self.constants.bit_loading.demodulate(&points))
+    }
+
+    /// Demodulates a single OFDM symbol like [demodulate_symbol_from_buffer](OFDMDemodulator::demodulate_symbol_from_buffer)
+    /// and estimates the quality of the link from its [received constellation](OFDMDemodulator::received_constellation).
+    ///
+    /// Every point is sliced to the closest ideal constellation point, the estimates are decision directed:
+    /// the SNR is the power of the ideal points divided by the power of the error vectors,
+    /// and the phase error is the mean rotation of the points against the ideal ones.
+    /// The noise of the channel estimate counts into the SNR as well,
+    /// boosted pilots keep it close to the SNR of the channel.
+    /// With so much noise that points are sliced to wrong neighbours, the SNR is overestimated.
+    ///
+    /// # Panics
+    /// If the input buffer length does not match `self.get_symbol_length()`.
+    ///
+    /// # Example
+    /// ```
+    /// use rand::{SeedableRng, rngs::StdRng};
+    /// use software_modem::channel::add_awgn;
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    /// use realfft::num_complex::Complex32;
+    ///
+    /// // pilots with 16 times the power of the default ones
+    /// let pilot_sequence = vec![Complex32::new(4.0, 0.0); 15];
+    /// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(4),
+    ///     pilot_sequence: pilot_sequence.clone(),
+    ///     scrambling: true,
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    /// let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(4),
+    ///     pilot_sequence,
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    /// let (samples, _) = modulator.modulate_packet(&vec![0x3c; 24 * 20]).unwrap();
+    ///
+    /// let mut rng = StdRng::seed_from_u64(5);
+    /// for snr_db in [15.0, 20.0, 25.0] {
+    ///     let mut received = samples.clone();
+    ///     add_awgn(&mut received, snr_db, &mut rng);
+    ///
+    ///     let estimates: Vec<f32> = received
+    ///         .chunks_exact(demodulator.get_symbol_length())
+    ///         .map(|symbol| demodulator.demodulate_with_quality(symbol).1.snr_db)
+    ///         .collect();
+    ///     let mean = estimates.iter().sum::<f32>() / estimates.len() as f32;
+    ///     assert!((mean - snr_db).abs() < 2.5);
+    /// }
+    ///
+    /// // a clean symbol is the ideal constellation
+    /// let (data, quality) = demodulator.demodulate_with_quality(&samples[..demodulator.get_symbol_length()]);
+    /// assert_eq!(data.len(), demodulator.bytes_per_symbol());
+    /// assert!(quality.snr_db > 60.0);
+    /// assert!(quality.phase_error.abs() < 1e-3);
+    /// ```
+    pub fn demodulate_with_quality(&self, input_buffer: &[T]) -> (Vec<u8>, EstimatedQuality<T>) {
+        let points = self.received_constellation(input_buffer);
+        let ideal_points = self.constants.bit_loading.nearest_points(&points);
+
+        let signal_power: T = ideal_points.iter().map(|ideal| ideal.norm_sqr()).sum();
+        let error_power: T = points
+            .iter()
+            .zip(&ideal_points)
+            .map(|(point, ideal)| (point - ideal).norm_sqr())
+            .sum();
+        let rotation: Complex<T> = points
+            .iter()
+            .zip(&ideal_points)
+            .map(|(point, ideal)| point * ideal.conj())
+            .sum();
+
+        let quality = EstimatedQuality {
+            snr_db: cast::<T>(10.0) * (signal_power / error_power).log10(),
+            phase_error: rotation.arg(),
+        };
+
+        (self.constants.bit_loading.demodulate(&points), quality)
+    }
+
+    /// Returns the RMS [error vector magnitude](crate::metrics::evm) of a single OFDM symbol in percent and in dB.
+    ///
+    /// The equalized points of the data subcarriers are compared with their hard decisions,
+    /// so no knowledge of the transmitted data is needed.
+    /// Like for [demodulate_with_quality](OFDMDemodulator::demodulate_with_quality),
+    /// errors pushing a point past a decision boundary are underestimated.
+    ///
+    /// # Panics
+    /// If the input buffer length does not match `self.get_symbol_length()`.
+    ///
+    /// # Example
+    /// ```
+    /// use rand::{SeedableRng, rngs::StdRng};
+    /// use software_modem::channel::add_awgn;
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    ///
+    /// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(4),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    /// let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(4),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    ///
+    /// let mut symbol = vec![0.0; modulator.get_symbol_length()];
+    /// modulator.modulate_buffer_as_symbol(&[0x5a; 24], &mut symbol).unwrap();
+    /// add_awgn(&mut symbol, 20.0, &mut StdRng::seed_from_u64(3));
+    ///
+    /// // the EVM in dB mirrors the estimated signal to noise ratio
+    /// let (percent, db) = demodulator.evm(&symbol);
+    /// assert!(percent > 1.0 && percent < 30.0);
+    /// assert!((db + demodulator.demodulate_with_quality(&symbol).1.snr_db).abs() < 1e-3);
+    /// ```
+    pub fn evm(&self, input_buffer: &[T]) -> (T, T) {
+        self.evm_of_points(&self.received_constellation(input_buffer))
+    }
+
+    /// Returns the error vector magnitude of the points of the leading data subcarriers against their decisions.
+    fn evm_of_points(&self, points: &[Complex<T>]) -> (T, T) {
+        metrics::evm(points, &self.constants.bit_loading.nearest_points(points))
+    }
+
+    /// Returns the equalized constellation points of the data subcarriers of a single OFDM symbol.
+    ///
+    /// These are the points [demodulate_symbol_from_buffer](OFDMDemodulator::demodulate_symbol_from_buffer)
+    /// slices into bits, in the order of the data subcarriers.
+    /// Only the subcarriers carrying whole bytes are returned, which is `ceil(bytes_per_symbol() * 8 / bits_per_subcarrier)` points
+    /// if all subcarriers use the same QAM order.
+    /// Plot them, e.g. with [write_constellation_csv](crate::io::write_constellation_csv),
+    /// to see noise, phase or scale errors of the channel.
+    ///
+    /// # Panics
+    /// If the input buffer length does not match `self.get_symbol_length()`.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    /// use software_modem::qam::{QAMModem, QAMOrder};
+    ///
+    /// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(4),
+    ///     qam_order: QAMOrder::QAM16,
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    /// let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(4),
+    ///     qam_order: QAMOrder::QAM16,
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    ///
+    /// let data: Vec<u8> = (0..modulator.bytes_per_symbol()).map(|i| (i * 37) as u8).collect();
+    /// let mut symbol = vec![0.0; modulator.get_symbol_length()];
+    /// modulator.modulate_buffer_as_symbol(&data, &mut symbol).unwrap();
+    ///
+    /// let points = demodulator.received_constellation(&symbol);
+    /// assert_eq!(points.len(), 48);
+    ///
+    /// // on a clean channel every point sits on the ideal 16-QAM grid
+    /// let grid = QAMModem::new(QAMOrder::QAM16).modulate(&(0..=255).collect::<Vec<u8>>());
+    /// for point in points {
+    ///     let error = grid.iter().map(|ideal| (point - ideal).norm()).fold(f32::MAX, f32::min);
+    ///     assert!(error < 1e-3);
+    /// }
+    /// ```
+    pub fn received_constellation(&self, input_buffer: &[T]) -> Vec<Complex<T>> {
         if input_buffer.len() != self.get_symbol_length() {
             panic!(
                 "Symbol buffer length must be {}, but got {}",
@@ -78,68 +533,2294 @@ impl OFDMDemodulator {
             );
         }
 
-        let demodulated_symbol = self.demodulate_ofdm_symbol(input_buffer).unwrap();
-
-        self.qam_modem.demodulate(&demodulated_symbol)
+        self.packet_constellation(input_buffer, 0, None)
     }
 
-    fn demodulate_ofdm_symbol(&self, input: &[f32]) -> Result<Vec<Complex32>, String> {
-        // remove cyclic prefix
-        let mut input_no_cp = vec![0.0; 2 * self.constants.num_subcarriers as usize];
-        input_no_cp.clone_from_slice(&input[self.constants.cyclic_prefix_length as usize..]);
+    /// Returns the spectrum of a single OFDM symbol, the lowest level hook for custom receivers.
+    ///
+    /// The cyclic prefix is stripped and the rest transformed, without equalization or any other processing.
+    /// The spectrum holds the `num_subcarriers + 1` bins from DC to nyquist,
+    /// scaled so a clean channel returns the values the modulator placed on the subcarriers.
+    /// Index it with the [data](OFDMDemodulator::data_subcarrier_indices)
+    /// and [pilot](OFDMDemodulator::pilot_subcarrier_indices) subcarriers.
+    ///
+    /// # Panics
+    /// If the input buffer length does not match `self.get_symbol_length()`.
+    ///
+    /// # Example
+    /// ```
+    /// use realfft::num_complex::Complex32;
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    /// use software_modem::qam::{QAMModem, QAMOrder};
+    ///
+    /// let pilot_sequence: Vec<Complex32> = (0..15).map(|i| Complex32::from_polar(2.0, i as f32)).collect();
+    /// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(4),
+    ///     qam_order: QAMOrder::QAM16,
+    ///     pilot_sequence: pilot_sequence.clone(),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    /// let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(4),
+    ///     qam_order: QAMOrder::QAM16,
+    ///     pilot_sequence: pilot_sequence.clone(),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    ///
+    /// let data: Vec<u8> = (0..modulator.bytes_per_symbol()).map(|i| (i * 37) as u8).collect();
+    /// let mut symbol = vec![0.0; modulator.get_symbol_length()];
+    /// modulator.modulate_buffer_as_symbol(&data, &mut symbol).unwrap();
+    ///
+    /// let spectrum = demodulator.extract_subcarriers(&symbol);
+    /// assert_eq!(spectrum.len(), 65);
+    /// for (&index, pilot) in demodulator.pilot_subcarrier_indices().iter().zip(&pilot_sequence) {
+    ///     assert!((spectrum[index as usize] - pilot).norm() < 1e-4);
+    /// }
+    ///
+    /// let points = QAMModem::new(QAMOrder::QAM16).modulate(&data);
+    /// for (&index, point) in demodulator.data_subcarrier_indices().iter().zip(&points) {
+    ///     assert!((spectrum[index as usize] - point).norm() < 1e-4);
+    /// }
+    /// ```
+    pub fn extract_subcarriers(&self, input_buffer: &[T]) -> Vec<Complex<T>> {
+        if input_buffer.len() != self.get_symbol_length() {
+            panic!(
+                "Symbol buffer length must be {}, but got {}",
+                self.get_symbol_length(),
+                input_buffer.len()
+            );
+        }
 
-        // time domain to frequency domain
-        let mut output_buffer = self.fft.make_output_vec();
-        self.fft
-            .process(&mut input_no_cp, &mut output_buffer)
+        self.symbol_spectrum(input_buffer)
+    }
+
+    /// Returns the equalized points of the data subcarriers carrying whole bytes
+    /// of the symbol with the given index within a packet.
+    ///
+    /// The symbol is equalized with the given channel estimate if any, otherwise with the one of its pilots.
+    fn packet_constellation(
+        &self,
+        input_buffer: &[T],
+        symbol_index: usize,
+        channel: Option<&[Complex<T>]>,
+    ) -> Vec<Complex<T>> {
+        let mut points = self
+            .demodulate_ofdm_symbol(
+                input_buffer,
+                symbol_index,
+                &self.constants.bit_loading,
+                channel,
+            )
             .unwrap();
 
-        // equalize
-        // for now, just scale everything to fit the range of QAM symbols
-        let max_value = output_buffer.iter().map(|c| c.norm()).fold(0.0, f32::max);
-        if max_value > 0.0 {
-            for value in output_buffer.iter_mut() {
-                *value /= max_value / 3.0;
+        points.truncate(self.num_payload_subcarriers());
+        log_debug!(
+            "symbol {symbol_index}: EVM {:?} %",
+            self.evm_of_points(&points).0
+        );
+
+        points
+    }
+
+    /// Number of leading data subcarriers of a packet symbol, the ones carrying whole bytes.
+    fn num_payload_subcarriers(&self) -> usize {
+        // only the subcarriers carrying whole bytes hold data
+        self.constants
+            .bit_loading
+            .num_subcarriers(self.constants.bytes_per_symbol() * 8)
+    }
+
+    /// Demodulates a sequence of OFDM symbols produced by
+    /// [modulate_packet](crate::ofdm::modulator::OFDMModulator::modulate_packet) back into the payload.
+    ///
+    /// Every symbol is demodulated (and [deinterleaved](crate::interleaver) if `interleaving` is enabled)
+    /// on its own and the bytes are concatenated
+    /// (and [descrambled](crate::scrambler::descramble) if `scrambling` is enabled),
+    /// after which the given number of `padding` bytes is removed from the end.
+    /// If `convolutional_coding` is enabled, the remaining bytes are [decoded](crate::fec::decode).
+    /// If `reed_solomon` is enabled, the [Reed-Solomon code](crate::fec::reed_solomon) is decoded last,
+    /// blocks with too many errors to correct are returned as received.
+    /// With a `window_length`, the fade out of the last symbol is expected after the last symbol and ignored.
+    /// With a `repetition` above 1, the copies of every symbol are averaged before it is demodulated.
+    /// An empty input results in an empty output.
+    ///
+    /// The channel is estimated from the pilots of every symbol on its own,
+    /// so the phase a small residual frequency offset accumulates over a long packet is tracked from symbol to symbol.
+    /// This requires the pilot sequence and the [pilot_polarity](OFDMDemodulatorConfig::pilot_polarity)
+    /// to match the ones of the modulator.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    ///
+    /// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(4),
+    ///     convolutional_coding: true,
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    /// let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(4),
+    ///     convolutional_coding: true,
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    ///
+    /// let payload = b"Hello, coded OFDM!";
+    /// let (mut samples, padding) = modulator.modulate_packet(payload).unwrap();
+    ///
+    /// // a burst of interference in the first symbol
+    /// samples[40..44].iter_mut().for_each(|s| *s += 8.0);
+    ///
+    /// assert_eq!(demodulator.demodulate_packet(&samples, padding), payload);
+    /// ```
+    ///
+    /// The pilots keep the symbols of a long packet in their decision regions,
+    /// while the phase drifts far enough to turn the outer points of the last symbols onto their neighbors:
+    /// ```
+    /// use software_modem::metrics::bit_error_rate;
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    ///
+    /// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(16),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    /// let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(16),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    ///
+    /// let payload: Vec<u8> = (0..20 * 24).map(|i| (i * 37 % 251) as u8).collect();
+    /// let (mut samples, padding) = modulator.modulate_packet(&payload).unwrap();
+    ///
+    /// // a residual offset which turns the phase by 0.4 radians over the 20 symbols
+    /// let cfo = 0.4 / samples.len() as f32;
+    /// demodulator.correct_cfo(&mut samples, -cfo);
+    ///
+    /// let received = demodulator.demodulate_packet(&samples, padding);
+    /// for (sent, received) in payload.chunks(24).zip(received.chunks(24)) {
+    ///     assert_eq!(bit_error_rate(sent, received), 0.0);
+    /// }
+    /// ```
+    ///
+    /// # Panics
+    /// If the input length without the trailing `window_length` and `trailing_gap` samples is not a multiple of
+    /// `self.get_symbol_length()` times the `repetition`, or if `padding` is larger than the demodulated data.
+    pub fn demodulate_packet(&self, input: &[T], padding: usize) -> Vec<u8> {
+        self.demodulate_packet_symbols(self.strip_gap(input), padding, None)
+    }
+
+    /// Demodulates a packet like [demodulate_packet](OFDMDemodulator::demodulate_packet),
+    /// which ends with the fade out of the last symbol instead of the trailing gap.
+    ///
+    /// The symbols are equalized with the given channel estimate if any, otherwise with the ones of their pilots.
+    pub(super) fn demodulate_packet_symbols(
+        &self,
+        input: &[T],
+        padding: usize,
+        channel: Option<&[Complex<T>]>,
+    ) -> Vec<u8> {
+        let input = self.packet_symbols(input);
+        let symbol_length = self.get_symbol_length();
+        let copies_length = symbol_length * self.constants.repetition as usize;
+
+        let mut data =
+            Vec::with_capacity(input.len() / copies_length * self.constants.bytes_per_symbol());
+        for (i, copies) in input.chunks(copies_length).enumerate() {
+            let symbol = average_copies::<T, _>(copies, symbol_length);
+            let bytes = self
+                .constants
+                .bit_loading
+                .demodulate(&self.packet_constellation(&symbol, i, channel));
+            match &self.interleaver {
+                Some(interleaver) => data.extend(interleaver.deinterleave(&bytes)),
+                None => data.extend(bytes),
             }
         }
 
-        // extract data subcarriers
-        let mut output_symbols =
-            vec![Complex32::default(); self.constants.data_subcarrier_indices.len()];
-        for (i, &idx) in self.constants.data_subcarrier_indices.iter().enumerate() {
-            output_symbols[i] = output_buffer[idx as usize];
+        self.finish_packet(data, padding)
+    }
+
+    /// Strips the trailing gap from a packet, a packet without symbols has none.
+    fn strip_gap<'a>(&self, input: &'a [T]) -> &'a [T] {
+        if input.is_empty() {
+            input
+        } else {
+            &input[..input.len().saturating_sub(self.trailing_gap)]
+        }
+    }
+
+    /// Strips the fade out of the last symbol from a packet and checks that the rest holds whole symbols.
+    ///
+    /// # Panics
+    /// If the packet is not a multiple of `self.get_symbol_length()` times the `repetition`.
+    fn packet_symbols<'a>(&self, input: &'a [T]) -> &'a [T] {
+        // the fade out of the last symbol trails the packet
+        let input = if input.is_empty() {
+            input
+        } else {
+            &input[..input.len().saturating_sub(self.constants.window_length())]
+        };
+
+        let copies_length = self.get_symbol_length() * self.constants.repetition as usize;
+        if !input.len().is_multiple_of(copies_length) {
+            panic!(
+                "Packet length must be a multiple of {}, but got {}",
+                copies_length,
+                input.len()
+            );
         }
 
-        Ok(output_symbols)
+        input
     }
 
-    /// Returns the length of the OFDM symbol, including the cyclic prefix.
+    /// Demodulates complex baseband (I/Q) symbols produced by
+    /// [modulate_iq](crate::ofdm::modulator::OFDMModulator::modulate_iq).
     ///
-    /// The length is calculated as:
-    /// `2 * num_subcarriers + cyclic_prefix_length`.
-    pub fn get_symbol_length(&self) -> usize {
-        (2 * self.constants.num_subcarriers + self.constants.cyclic_prefix_length) as usize
+    /// Every symbol is transformed with a complex FFT, the positive and the negative subcarriers
+    /// are equalized with their own pilots and demodulated into one chunk each.
+    /// Descrambling, trimming the padding and decoding work like for [demodulate_packet](OFDMDemodulator::demodulate_packet).
+    ///
+    /// See [modulate_iq](crate::ofdm::modulator::OFDMModulator::modulate_iq) for an example.
+    ///
+    /// # Panics
+    /// If the input length is not a multiple of `self.get_symbol_length()` times the `repetition`,
+    /// or if `padding` is larger than the demodulated data.
+    pub fn demodulate_iq(&self, input: &[Complex<T>], padding: usize) -> Vec<u8> {
+        let symbol_length = self.get_symbol_length();
+        let copies_length = symbol_length * self.constants.repetition as usize;
+        if !input.len().is_multiple_of(copies_length) {
+            panic!(
+                "Packet length must be a multiple of {}, but got {}",
+                copies_length,
+                input.len()
+            );
+        }
+
+        let fft_length = 2 * self.constants.num_subcarriers as usize;
+        let scale = T::one() / cast(fft_length as f64);
+
+        let mut data =
+            Vec::with_capacity(input.len() / copies_length * 2 * self.constants.bytes_per_symbol());
+        for (i, copies) in input.chunks(copies_length).enumerate() {
+            // remove cyclic prefix, time domain to frequency domain
+            let symbol = average_copies::<T, _>(copies, symbol_length);
+            let mut spectrum = symbol[self.constants.cyclic_prefix_length as usize..].to_vec();
+            self.iq_fft.process(&mut spectrum);
+
+            // split into the positive and the mirrored negative subcarriers
+            let positive: Vec<Complex<T>> = spectrum[..=fft_length / 2]
+                .iter()
+                .map(|&bin| bin * scale)
+                .collect();
+            let negative: Vec<Complex<T>> = (0..=fft_length / 2)
+                .map(|k| spectrum[(fft_length - k) % fft_length] * scale)
+                .collect();
+
+            for half in [positive, negative] {
+                let mut channel = self.channel_from_spectrum(&half, i);
+                let points =
+                    self.equalize_refined(&half, i, &self.constants.bit_loading, &mut channel);
+                let bytes = self.constants.bit_loading.demodulate(&points);
+                match &self.interleaver {
+                    Some(interleaver) => data.extend(interleaver.deinterleave(&bytes)),
+                    None => data.extend(bytes),
+                }
+            }
+        }
+
+        self.finish_packet(data, padding)
     }
-}
 
-/// Configuration for the [OFDM Demodulator](OFDMDemodulator).
-///
-/// Just contruct this struct with the desired parameters and pass it to the `OFDMDemodulator::new()` method.
-#[derive(SmartDefault)]
-pub struct OFDMDemodulatorConfig {
-    pub num_subcarriers: u32,
-    /// Length of the cyclic prefix in samples.
+    /// Descrambles the data of a packet, trims the padding and decodes it, as configured.
+    fn finish_packet(&self, mut data: Vec<u8>, padding: usize) -> Vec<u8> {
+        if self.scrambling {
+            data = scrambler::descramble(&data);
+        }
+        data.truncate(data.len() - padding);
+
+        let data = if self.convolutional_coding {
+            fec::decode(&data)
+        } else {
+            data
+        };
+
+        #[cfg(feature = "rs")]
+        let data = if self.reed_solomon {
+            fec::reed_solomon::decode_lenient(&data)
+        } else {
+            data
+        };
+
+        data
+    }
+
+    /// Demodulates a packet produced by
+    /// [modulate_framed_packet](crate::ofdm::modulator::OFDMModulator::modulate_framed_packet)
+    /// and verifies the checksum of its [frame](crate::frame).
+    /// Returns the sequence number and the payload of the frame.
     ///
-    /// One OFDM symbol double num_subcarriers samples. If you want to have a CP of 1/4 you need to set this to `(2 * num_subcarriers) / 4`
-    pub cyclic_prefix_length: u32,
-    /// Interval for pilot subcarriers.
+    /// The length field of the frame is demodulated first and only the symbols of the frame are decoded,
+    /// so samples after its last symbol, e.g. the [trailing_gap](OFDMDemodulatorConfig::trailing_gap), are ignored.
     ///
-    /// Inserts pilot subcarriers every `pilot_subcarrier_every` subcarrier.
-    #[default(4)]
-    pub pilot_subcarrier_every: u32,
-    pub qam_order: QAMOrder,
-    /// Optional FFT implementation/planner to use.
+    /// # Errors
+    /// Returns a [FrameError] if the frame is incomplete or its checksum does not match.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::error::FrameError;
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    ///
+    /// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(4),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    /// let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(4),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    ///
+    /// let mut samples = modulator.modulate_framed_packet(b"Hello, framed OFDM!", 3).unwrap();
+    /// assert_eq!(
+    ///     demodulator.demodulate_framed_packet(&samples).unwrap(),
+    ///     (3, b"Hello, framed OFDM!".to_vec())
+    /// );
+    ///
+    /// // heavy interference on the second symbol corrupts the checksum, which is detected
+    /// samples[140..160].iter_mut().for_each(|s| *s += 30.0);
+    /// assert!(matches!(
+    ///     demodulator.demodulate_framed_packet(&samples),
+    ///     Err(FrameError::ChecksumMismatch { .. })
+    /// ));
+    /// ```
+    pub fn demodulate_framed_packet(&self, input: &[T]) -> Result<(u16, Vec<u8>), FrameError> {
+        let symbol_length = self.get_symbol_length();
+        let repetition = self.constants.repetition as usize;
+        let available =
+            input.len().saturating_sub(self.window_length()) / symbol_length / repetition
+                * repetition;
+        // a corrupted length field still leaves the checksum to reject the frame
+        let num_symbols = self
+            .read_frame_symbols(input)
+            .map_or(available, |num_symbols| num_symbols.min(available));
+        let end = if num_symbols > 0 {
+            num_symbols * symbol_length + self.window_length()
+        } else {
+            0
+        };
+
+        // the frame knows its own length, so the padding can stay in place
+        frame::deframe(&self.demodulate_packet_symbols(&input[..end], 0, None))
+    }
+
+    /// Demodulates the first symbols of a framed packet to read the length of its frame,
+    /// returns the number of symbols of the packet or `None` if the input ends before the length field.
+    pub(super) fn read_frame_symbols(&self, input: &[T]) -> Option<usize> {
+        // the end of a truncated convolutional code is unreliable, so decode past the length field
+        let num_symbols = self.num_packet_symbols(MIN_DECODED_BYTES);
+        let end = num_symbols * self.get_symbol_length() + self.window_length();
+        let data = self.demodulate_packet_symbols(input.get(..end)?, 0, None);
+
+        let frame_length = frame::frame_length(&data)?;
+        Some(self.num_packet_symbols(frame_length))
+    }
+
+    /// Demodulates a packet produced by
+    /// [modulate_packet_with_header](crate::ofdm::modulator::OFDMModulator::modulate_packet_with_header).
+    ///
+    /// The header symbols are demodulated first, the payload length they announce
+    /// gives the number of data symbols and the padding to trim,
+    /// which are then demodulated like by [demodulate_packet](OFDMDemodulator::demodulate_packet).
+    /// The input has to start with the first header symbol, samples after the end of the packet are ignored.
+    ///
+    /// See [modulate_packet_with_header](crate::ofdm::modulator::OFDMModulator::modulate_packet_with_header) for an example.
+    ///
+    /// # Errors
+    /// - [HeaderError::TooShort] if the input ends before the header or the announced payload does.
+    /// - [HeaderError::ChecksumMismatch] if the checksum of the header does not match its content.
+    /// - [HeaderError::UnknownQamOrder] or [HeaderError::QamOrderMismatch] if the header announces
+    ///   another QAM order than the configured `qam_order`.
+    pub fn demodulate_packet_with_header(&self, input: &[T]) -> Result<Vec<u8>, HeaderError> {
+        self.demodulate_header_packet(input, None)
+    }
+
+    /// Receives a complete frame of [build_frame](crate::ofdm::modulator::OFDMModulator::build_frame)
+    /// from a capture of samples, the turnkey API for most receivers.
+    ///
+    /// Runs the whole pipeline on the first frame in the samples:
+    /// 1. [Finds](OFDMDemodulator::find_frame_start) the preamble
+    ///    and [refines](OFDMDemodulator::refine_timing) the start of the long training symbol after it.
+    /// 2. [Estimates the channel](OFDMDemodulator::estimate_channel_from_ltf) from the long training symbol.
+    /// 3. Demodulates the [packet with the header](OFDMDemodulator::demodulate_packet_with_header),
+    ///    equalizing every symbol with the channel estimate while the pilots track its common phase.
+    ///
+    /// The pilots only follow a slow drift of the phase. A larger carrier frequency offset has to be
+    /// [estimated](OFDMDemodulator::estimate_cfo) and [removed](OFDMDemodulator::correct_cfo) beforehand.
+    /// Samples before the preamble and after the end of the frame are ignored.
+    /// See [build_frame](crate::ofdm::modulator::OFDMModulator::build_frame) for an example.
+    ///
+    /// # Errors
+    /// - [RxError::NoPreamble] if no preamble is found.
+    /// - [RxError::TooShort] if the samples end before the long training symbol does.
+    /// - [RxError::Header] if the packet after the long training symbol can not be demodulated,
+    ///   e.g. because its header is corrupted.
+    pub fn receive_frame(&self, samples: &[T]) -> Result<Vec<u8>, RxError> {
+        let symbol_length = self.get_symbol_length();
+        let start = self.find_frame_start(samples).ok_or(RxError::NoPreamble)?;
+
+        let too_short = |training_start: usize| RxError::TooShort {
+            expected: training_start + symbol_length,
+            actual: samples.len(),
+        };
+        let training_start = start + symbol_length;
+        if samples.len() < training_start + symbol_length {
+            return Err(too_short(training_start));
+        }
+        let training_start = self.refine_timing(samples, training_start);
+        let training = samples
+            .get(training_start..training_start + symbol_length)
+            .ok_or_else(|| too_short(training_start))?;
+
+        let channel = self.estimate_channel_from_ltf(training);
+        Ok(self
+            .demodulate_header_packet(&samples[training_start + symbol_length..], Some(&channel))?)
+    }
+
+    /// Demodulates a packet with a header like [demodulate_packet_with_header](OFDMDemodulator::demodulate_packet_with_header),
+    /// equalizing the symbols with the given channel estimate if any, otherwise with the ones of their pilots.
+    fn demodulate_header_packet(
+        &self,
+        input: &[T],
+        channel: Option<&[Complex<T>]>,
+    ) -> Result<Vec<u8>, HeaderError> {
+        let symbol_length = self.get_symbol_length();
+        let header_length = self.constants.num_header_symbols() * symbol_length;
+        if input.len() < header_length {
+            return Err(HeaderError::TooShort {
+                expected: header_length,
+                actual: input.len(),
+            });
+        }
+
+        let mut header_bits = BitWriter::with_capacity(8 * header::HEADER_LENGTH);
+        for (i, symbol) in input[..header_length].chunks(symbol_length).enumerate() {
+            let points = self
+                .demodulate_ofdm_symbol(symbol, i, &self.constants.header_loading, channel)
+                .unwrap();
+            self.constants
+                .header_loading
+                .demodulate_bits(&points, &mut header_bits);
+        }
+        let (length, qam_order) = header::decode(&header_bits.into_whole_bytes())?;
+        if qam_order != self.constants.qam_order {
+            return Err(HeaderError::QamOrderMismatch {
+                expected: self.constants.qam_order,
+                actual: qam_order,
+            });
+        }
+
+        let num_symbols = self.num_packet_symbols(length);
+        let window_length = if num_symbols > 0 {
+            self.window_length()
+        } else {
+            0
+        };
+        let packet_end = header_length + num_symbols * symbol_length + window_length;
+        if input.len() < packet_end {
+            return Err(HeaderError::TooShort {
+                expected: packet_end,
+                actual: input.len(),
+            });
+        }
+
+        let padding = num_symbols / self.constants.repetition as usize
+            * self.constants.bytes_per_symbol()
+            - self.encoded_length(length);
+        Ok(self.demodulate_packet_symbols(&input[header_length..packet_end], padding, channel))
+    }
+
+    /// Estimates the channel response for every subcarrier from the pilot subcarriers of the given symbol.
+    ///
+    /// The received pilot values are compared against the known transmitted pilot sequence,
+    /// and the estimate is interpolated linearly between neighbouring pilots.
+    /// Subcarriers below the first or above the last pilot use the estimate of the closest pilot.
+    /// The estimate is normalized, so a perfect channel results in `1 + 0i` on every subcarrier.
+    ///
+    /// The returned vector holds one value per frequency bin, which is `num_subcarriers + 1` values.
+    ///
+    /// # Panics
+    /// If the input buffer length does not match `self.get_symbol_length()`.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    /// use realfft::{RealFftPlanner, num_complex::Complex32};
+    ///
+    /// let config = || OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(16),
+    ///     ..Default::default()
+    /// };
+    /// let modulator = OFDMModulator::new(config()).unwrap();
+    /// let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(16),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    ///
+    /// let data = "Hello, OFDM!            ".as_bytes();
+    /// let mut symbol = vec![0.0; modulator.get_symbol_length()];
+    /// modulator.modulate_buffer_as_symbol(data, &mut symbol).unwrap();
+    ///
+    /// // apply a complex gain to every subcarrier, DC and nyquist carry no data
+    /// let gain = Complex32::from_polar(0.3, 2.0);
+    /// let mut planner = RealFftPlanner::<f32>::new();
+    /// let mut spectrum = planner.plan_fft_forward(128).make_output_vec();
+    /// planner.plan_fft_forward(128).process(&mut symbol[16..].to_vec(), &mut spectrum).unwrap();
+    /// spectrum.iter_mut().for_each(|bin| *bin *= gain / 128.0);
+    /// spectrum[0] = Complex32::default();
+    /// spectrum[64] = Complex32::default();
+    /// let mut received = vec![0.0; 128];
+    /// planner.plan_fft_inverse(128).process(&mut spectrum, &mut received).unwrap();
+    /// let received = [&received[112..], &received[..]].concat();
+    ///
+    /// let channel = demodulator.estimate_channel(&received);
+    /// assert_eq!(channel.len(), 65);
+    /// assert!(channel[1..64].iter().all(|h| (h - gain).norm() < 1e-4));
+    ///
+    /// assert_eq!(demodulator.demodulate_symbol_from_buffer(&received), data);
+    /// ```
+    pub fn estimate_channel(&self, input: &[T]) -> Vec<Complex<T>> {
+        if input.len() != self.get_symbol_length() {
+            panic!(
+                "Symbol buffer length must be {}, but got {}",
+                self.get_symbol_length(),
+                input.len()
+            );
+        }
+
+        let spectrum = self.symbol_spectrum(input);
+        self.channel_from_spectrum(&spectrum, 0)
+    }
+
+    /// Estimates the channel of every subcarrier from a received
+    /// [long training symbol](crate::ofdm::modulator::OFDMModulator::generate_long_training_symbol).
+    ///
+    /// Returns the least-squares estimate, the received value of every subcarrier divided by the known training value,
+    /// for all bins from DC to nyquist like [estimate_channel](OFDMDemodulator::estimate_channel).
+    /// DC, nyquist and the null subcarriers carry no training value, their estimate is zero.
+    /// Pass the estimate to [demodulate_symbol_with_channel](OFDMDemodulator::demodulate_symbol_with_channel)
+    /// to equalize the following data symbols with it instead of the pilots.
+    ///
+    /// # Panics
+    /// If the input buffer length does not match `self.get_symbol_length()`.
+    ///
+    /// # Example
+    /// ```
+    /// use rand::{Rng, SeedableRng, rngs::StdRng};
+    /// use realfft::{RealFftPlanner, num_complex::Complex32};
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    ///
+    /// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(16),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    /// let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(16),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    ///
+    /// // a random gain and phase on every subcarrier, which the pilots can not follow
+    /// let mut rng = StdRng::seed_from_u64(3);
+    /// let gains: Vec<Complex32> = (0..65)
+    ///     .map(|_| Complex32::from_polar(rng.random_range(0.5..1.5), rng.random_range(-3.0..3.0)))
+    ///     .collect();
+    /// let apply_channel = |symbol: &[f32]| {
+    ///     let mut planner = RealFftPlanner::<f32>::new();
+    ///     let mut spectrum = planner.plan_fft_forward(128).make_output_vec();
+    ///     planner.plan_fft_forward(128).process(&mut symbol[16..].to_vec(), &mut spectrum).unwrap();
+    ///     for (bin, gain) in spectrum.iter_mut().zip(&gains) {
+    ///         *bin *= gain / 128.0;
+    ///     }
+    ///     spectrum[0] = Complex32::default();
+    ///     spectrum[64] = Complex32::default();
+    ///     let mut received = vec![0.0; 128];
+    ///     planner.plan_fft_inverse(128).process(&mut spectrum, &mut received).unwrap();
+    ///     [&received[112..], &received[..]].concat()
+    /// };
+    ///
+    /// let data: Vec<u8> = (0..modulator.bytes_per_symbol()).map(|i| (i * 37) as u8).collect();
+    /// let mut symbol = vec![0.0; modulator.get_symbol_length()];
+    /// modulator.modulate_buffer_as_symbol(&data, &mut symbol).unwrap();
+    ///
+    /// let channel = demodulator.estimate_channel_from_ltf(&apply_channel(&modulator.generate_long_training_symbol()));
+    /// assert!((1..64).all(|bin| (channel[bin] - gains[bin]).norm() < 1e-3));
+    ///
+    /// let received = apply_channel(&symbol);
+    /// assert_ne!(demodulator.demodulate_symbol_from_buffer(&received), data);
+    /// assert_eq!(demodulator.demodulate_symbol_with_channel(&received, &channel), data);
+    /// ```
+    pub fn estimate_channel_from_ltf(&self, input: &[T]) -> Vec<Complex<T>> {
+        if input.len() != self.get_symbol_length() {
+            panic!(
+                "Symbol buffer length must be {}, but got {}",
+                self.get_symbol_length(),
+                input.len()
+            );
+        }
+
+        let spectrum = self.symbol_spectrum(input);
+        spectrum
+            .iter()
+            .zip(long_training_spectrum(&self.constants))
+            .map(|(&received, training)| {
+                if training == Complex::default() {
+                    Complex::default()
+                } else {
+                    received / training
+                }
+            })
+            .collect()
+    }
+
+    /// Demodulates a single OFDM symbol like [demodulate_symbol_from_buffer](OFDMDemodulator::demodulate_symbol_from_buffer),
+    /// but equalizes it with the given channel estimate instead of the one of the pilots.
+    ///
+    /// The channel holds one value for every bin from DC to nyquist,
+    /// as returned by [estimate_channel_from_ltf](OFDMDemodulator::estimate_channel_from_ltf).
+    /// The pilots of the symbol still track the common phase error, e.g. of a residual frequency offset,
+    /// the channel is rotated by the average phase between the received pilots and the ones the channel predicts.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    ///
+    /// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(16),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    /// let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(16),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    ///
+    /// let payload: Vec<u8> = (0..20 * 24).map(|i| (i * 37 % 251) as u8).collect();
+    /// let (symbols, _) = modulator.modulate_packet(&payload).unwrap();
+    /// let mut samples = modulator.generate_long_training_symbol();
+    /// samples.extend(symbols);
+    ///
+    /// // a residual offset turns the later symbols 0.5 radians away from the phase of the training symbol,
+    /// // without tracking the second half of the packet would be lost
+    /// let cfo = 0.5 / samples.len() as f32;
+    /// demodulator.correct_cfo(&mut samples, -cfo);
+    ///
+    /// let symbol_length = demodulator.get_symbol_length();
+    /// let channel = demodulator.estimate_channel_from_ltf(&samples[..symbol_length]);
+    /// for (sent, symbol) in payload.chunks(24).zip(samples[symbol_length..].chunks(symbol_length)) {
+    ///     assert_eq!(demodulator.demodulate_symbol_with_channel(symbol, &channel), sent);
+    /// }
+    /// ```
+    ///
+    /// # Panics
+    /// If the input buffer length does not match `self.get_symbol_length()`,
+    /// or the channel does not hold `num_subcarriers + 1` values.
+    pub fn demodulate_symbol_with_channel(&self, input: &[T], channel: &[Complex<T>]) -> Vec<u8> {
+        if input.len() != self.get_symbol_length() {
+            panic!(
+                "Symbol buffer length must be {}, but got {}",
+                self.get_symbol_length(),
+                input.len()
+            );
+        }
+        if channel.len() != self.constants.num_subcarriers as usize + 1 {
+            panic!(
+                "Channel estimate length must be {}, but got {}",
+                self.constants.num_subcarriers + 1,
+                channel.len()
+            );
+        }
+
+        let points = self
+            .demodulate_ofdm_symbol(input, 0, &self.constants.bit_loading, Some(channel))
+            .unwrap();
+        self.constants.bit_loading.demodulate(&points)
+    }
+
+    /// Rotates a channel estimate by the common phase error between the pilots of a spectrum and the estimate,
+    /// the spectrum being the symbol with the given index within a packet.
+    fn track_common_phase(
+        &self,
+        spectrum: &[Complex<T>],
+        symbol_index: usize,
+        channel: &[Complex<T>],
+    ) -> Vec<Complex<T>> {
+        let sign = self.constants.pilot_sign(symbol_index);
+        let correlation = self
+            .constants
+            .pilot_subcarrier_indices
+            .iter()
+            .zip(self.constants.pilot_values.iter())
+            .map(|(&idx, &pilot)| {
+                spectrum[idx as usize] * (channel[idx as usize] * pilot * sign).conj()
+            })
+            .fold(Complex::<T>::default(), |sum, value| sum + value);
+
+        let magnitude = correlation.norm();
+        if magnitude > T::zero() {
+            let rotation = correlation / magnitude;
+            channel.iter().map(|&value| value * rotation).collect()
+        } else {
+            // without pilots, or a silent channel, there is no phase to track
+            channel.to_vec()
+        }
+    }
+
+    /// Equalizes the data subcarriers of the symbol with the given index within a packet.
+    ///
+    /// A given channel estimate, e.g. of the long training symbol, only has its common phase tracked with the pilots,
+    /// otherwise the channel is estimated from the pilots of the symbol.
+    fn demodulate_ofdm_symbol(
+        &self,
+        input: &[T],
+        symbol_index: usize,
+        loading: &BitLoading<T>,
+        channel: Option<&[Complex<T>]>,
+    ) -> Result<Vec<Complex<T>>, String> {
+        let spectrum = self.symbol_spectrum(input);
+        let mut channel = match channel {
+            Some(channel) => self.track_common_phase(&spectrum, symbol_index, channel),
+            None => self.channel_from_spectrum(&spectrum, symbol_index),
+        };
+
+        Ok(self.equalize_refined(&spectrum, symbol_index, loading, &mut channel))
+    }
+
+    /// Equalizes the data subcarriers of a spectrum with the channel estimate
+    /// after [refining](OFDMDemodulatorConfig::decision_directed_iterations) it, if enabled.
+    fn equalize_refined(
+        &self,
+        spectrum: &[Complex<T>],
+        symbol_index: usize,
+        loading: &BitLoading<T>,
+        channel: &mut [Complex<T>],
+    ) -> Vec<Complex<T>> {
+        let mut points = self.equalize_spectrum(spectrum, channel);
+        self.refine_channel(
+            spectrum,
+            symbol_index,
+            loading,
+            channel,
+            &mut points,
+            &mut Vec::new(),
+            &mut Vec::new(),
+            &mut Vec::new(),
+        );
+
+        points
+    }
+
+    /// Equalizes the data subcarriers of a spectrum with the channel estimate
+    /// and undoes the rotation of selective mapping.
+    fn equalize_spectrum(
+        &self,
+        spectrum: &[Complex<T>],
+        channel: &[Complex<T>],
+    ) -> Vec<Complex<T>> {
+        let mut output_symbols = Vec::with_capacity(self.constants.data_subcarrier_indices.len());
+        self.equalize_spectrum_into(spectrum, channel, &mut output_symbols);
+
+        output_symbols
+    }
+
+    /// Equalizes the data subcarriers like [equalize_spectrum](OFDMDemodulator::equalize_spectrum),
+    /// replacing the content of `output_symbols`.
+    fn equalize_spectrum_into(
+        &self,
+        spectrum: &[Complex<T>],
+        channel: &[Complex<T>],
+        output_symbols: &mut Vec<Complex<T>>,
+    ) {
+        // extract data subcarriers
+        output_symbols.clear();
+        output_symbols.extend(
+            self.constants
+                .data_subcarrier_indices
+                .iter()
+                .map(|&idx| self.equalize(spectrum[idx as usize], channel[idx as usize])),
+        );
+
+        if let Some(phases) = self.slm_phases(spectrum, channel) {
+            for (symbol, phase) in output_symbols.iter_mut().zip(phases) {
+                *symbol = *symbol * phase.conj();
+            }
+        }
+    }
+
+    /// Returns the rotation of the data subcarriers by the selective mapping candidate signalled in BPSK,
+    /// or `None` for candidate `0`, which leaves them untouched.
+    fn slm_phases(&self, spectrum: &[Complex<T>], channel: &[Complex<T>]) -> Option<&[Complex<T>]> {
+        let candidate =
+            self.constants
+                .slm_index_subcarrier_indices
+                .iter()
+                .fold(0, |candidate, &idx| {
+                    let bit = self
+                        .equalize(spectrum[idx as usize], channel[idx as usize])
+                        .re
+                        < T::zero();
+                    (candidate << 1) | bit as usize
+                });
+
+        self.constants
+            .slm_phases
+            .get(candidate)
+            .filter(|_| candidate > 0)
+            .map(Vec::as_slice)
+    }
+
+    /// Refines a channel estimate with the decisions on the data subcarriers of a spectrum,
+    /// see [decision_directed_iterations](OFDMDemodulatorConfig::decision_directed_iterations).
+    ///
+    /// `points` holds the data subcarriers equalized with `channel`, both are updated with every iteration which is kept.
+    /// `estimates`, `refined` and `refined_points` are buffers, which are resized as needed.
+    #[allow(clippy::too_many_arguments)]
+    fn refine_channel(
+        &self,
+        spectrum: &[Complex<T>],
+        symbol_index: usize,
+        loading: &BitLoading<T>,
+        channel: &mut [Complex<T>],
+        points: &mut Vec<Complex<T>>,
+        estimates: &mut Vec<Option<Complex<T>>>,
+        refined: &mut Vec<Complex<T>>,
+        refined_points: &mut Vec<Complex<T>>,
+    ) {
+        let max_error: T = cast(DECISION_DIRECTED_MAX_ERROR);
+        let sign = self.constants.pilot_sign(symbol_index);
+        let mut error = decision_error(loading, points);
+
+        for _ in 0..self.decision_directed_iterations {
+            // the pilots and the data subcarriers close to their decision observe the channel directly
+            estimates.clear();
+            estimates.resize(spectrum.len(), None);
+            for (&idx, &pilot) in self
+                .constants
+                .pilot_subcarrier_indices
+                .iter()
+                .zip(self.constants.pilot_values.iter())
+            {
+                estimates[idx as usize] = Some(spectrum[idx as usize] / (pilot * sign));
+            }
+            let phases = self.slm_phases(spectrum, channel);
+            for (i, (&idx, point)) in self
+                .constants
+                .data_subcarrier_indices
+                .iter()
+                .zip(points.iter())
+                .enumerate()
+            {
+                let decision = loading.nearest_point(i, point);
+                if (point - decision).norm() < max_error {
+                    let transmitted = match phases {
+                        Some(phases) => decision * phases[i],
+                        None => decision,
+                    };
+                    estimates[idx as usize] = Some(spectrum[idx as usize] / transmitted);
+                }
+            }
+
+            // average the observations of the neighbouring subcarriers to reduce their noise
+            refined.clear();
+            refined.extend(channel.iter().enumerate().map(|(bin, &previous)| {
+                let window = bin.saturating_sub(DECISION_DIRECTED_HALF_WINDOW)
+                    ..(bin + DECISION_DIRECTED_HALF_WINDOW + 1).min(estimates.len());
+                let (sum, count) = estimates[window]
+                    .iter()
+                    .flatten()
+                    .fold((Complex::default(), 0), |(sum, count), &estimate| {
+                        (sum + estimate, count + 1)
+                    });
+                if count > 0 {
+                    sum / cast::<T>(count as f64)
+                } else {
+                    previous
+                }
+            }));
+
+            // keep the refinement only if the points moved closer to the decisions
+            self.equalize_spectrum_into(spectrum, refined, refined_points);
+            let refined_error = decision_error(loading, refined_points);
+            if refined_error.is_nan() || refined_error >= error {
+                break;
+            }
+            error = refined_error;
+            channel.copy_from_slice(refined);
+            std::mem::swap(points, refined_points);
+        }
+    }
+
+    /// Removes the channel from a received subcarrier with the configured [Equalizer].
+    fn equalize(&self, received: Complex<T>, channel: Complex<T>) -> Complex<T> {
+        match self.equalizer {
+            Equalizer::ZeroForcing => received / channel,
+            Equalizer::Mmse { noise_var } => {
+                received * channel.conj() / (channel.norm_sqr() + cast(noise_var as f64))
+            }
+        }
+    }
+
+    /// Strips the cyclic prefix and transforms the symbol into the frequency domain.
+    ///
+    /// The spectrum is scaled by the FFT length,
+    /// so the bins hold the values the modulator placed on the subcarriers.
+    fn symbol_spectrum(&self, input: &[T]) -> Vec<Complex<T>> {
+        let mut time = vec![T::zero(); 2 * self.constants.num_subcarriers as usize];
+        let mut spectrum = self.fft.make_output_vec();
+        let mut fft = self.fft.make_scratch_vec();
+        self.symbol_spectrum_into(input, &mut time, &mut spectrum, &mut fft);
+
+        spectrum
+    }
+
+    /// Transforms a symbol like [symbol_spectrum](OFDMDemodulator::symbol_spectrum) using the given buffers,
+    /// `time` for the `2 * num_subcarriers` samples without the cyclic prefix and `fft` as the scratch of the FFT.
+    fn symbol_spectrum_into(
+        &self,
+        input: &[T],
+        time: &mut [T],
+        spectrum: &mut [Complex<T>],
+        fft: &mut [Complex<T>],
+    ) {
+        // remove cyclic prefix
+        time.copy_from_slice(&input[self.constants.cyclic_prefix_length as usize..]);
+
+        // time domain to frequency domain
+        self.fft.process_with_scratch(time, spectrum, fft).unwrap();
+
+        let scale = T::one() / cast(time.len() as f64);
+        for value in spectrum.iter_mut() {
+            *value = *value * scale;
+        }
+    }
+
+    /// Derives the per-bin channel estimate from the pilot subcarriers of a spectrum,
+    /// which carry the pilots of the symbol with the given index within a packet.
+    fn channel_from_spectrum(
+        &self,
+        spectrum: &[Complex<T>],
+        symbol_index: usize,
+    ) -> Vec<Complex<T>> {
+        let mut pilots = Vec::with_capacity(self.constants.pilot_subcarrier_indices.len());
+        let mut channel = vec![Complex::default(); spectrum.len()];
+        self.channel_into(spectrum, symbol_index, &mut pilots, &mut channel);
+
+        channel
+    }
+
+    /// Derives the channel estimate like [channel_from_spectrum](OFDMDemodulator::channel_from_spectrum) into `channel`,
+    /// using `pilots` for the received pilots.
+    fn channel_into(
+        &self,
+        spectrum: &[Complex<T>],
+        symbol_index: usize,
+        pilots: &mut Vec<(u32, Complex<T>)>,
+        channel: &mut [Complex<T>],
+    ) {
+        let sign = self.constants.pilot_sign(symbol_index);
+        pilots.clear();
+        pilots.extend(
+            self.constants
+                .pilot_subcarrier_indices
+                .iter()
+                .zip(self.constants.pilot_values.iter())
+                .map(|(&idx, &pilot)| (idx, spectrum[idx as usize] / (pilot * sign))),
+        );
+
+        // without pilots there is nothing to estimate, assume a perfect channel
+        let (Some(&(first_idx, first)), Some(&(last_idx, last))) = (pilots.first(), pilots.last())
+        else {
+            channel.fill(Complex::new(T::one(), T::zero()));
+            return;
+        };
+
+        for (bin, estimate) in channel.iter_mut().enumerate() {
+            let bin = bin as u32;
+            *estimate = if bin <= first_idx {
+                first
+            } else if bin >= last_idx {
+                last
+            } else {
+                // linear interpolation between the surrounding pilots
+                let right = pilots.iter().position(|&(idx, _)| idx >= bin).unwrap();
+                let (left_idx, left_value) = pilots[right - 1];
+                let (right_idx, right_value) = pilots[right];
+                let t: T = cast((bin - left_idx) as f64 / (right_idx - left_idx) as f64);
+                left_value + (right_value - left_value) * t
+            };
+        }
+        log_debug!(
+            "symbol {symbol_index}: mean channel gain {:?}",
+            mean_gain(&self.constants.data_subcarrier_indices, channel)
+        );
+    }
+
+    /// Estimates the carrier frequency offset (CFO) of a received symbol from its cyclic prefix.
+    ///
+    /// The cyclic prefix is a copy of the symbol tail, so a frequency offset shows up as a phase rotation
+    /// between the two, which grows by the offset for every sample between them.
+    /// The estimate is the angle of the correlation between the cyclic prefix and the symbol tail
+    /// of the analytic signal, divided by the distance of `2 * num_subcarriers` samples.
+    /// The first `window_length` samples of the cyclic prefix are skipped, as they overlap with the previous symbol.
+    ///
+    /// Returns the offset in normalized radians per sample, where a positive value means the received
+    /// subcarriers are shifted up in frequency.
+    /// Offsets up to half a subcarrier spacing (`π / (2 * num_subcarriers)`) can be estimated unambiguously.
+    /// Without a cyclic prefix the estimate is always `0.0`.
+    ///
+    /// # Panics
+    /// If the input buffer length does not match `self.get_symbol_length()`.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    ///
+    /// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(32),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    /// let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(32),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    ///
+    /// let data = "Hello, OFDM!            ".as_bytes();
+    /// let mut symbol = vec![0.0; modulator.get_symbol_length()];
+    /// modulator.modulate_buffer_as_symbol(data, &mut symbol).unwrap();
+    ///
+    /// // inject an offset of a third of the subcarrier spacing by correcting the opposite offset
+    /// let cfo = std::f32::consts::PI / 64.0 / 3.0;
+    /// demodulator.correct_cfo(&mut symbol, -cfo);
+    ///
+    /// let estimate = demodulator.estimate_cfo(&symbol);
+    /// assert!((estimate - cfo).abs() < cfo * 0.1);
+    ///
+    /// demodulator.correct_cfo(&mut symbol, estimate);
+    /// assert_eq!(demodulator.demodulate_symbol_from_buffer(&symbol), data);
+    /// ```
+    pub fn estimate_cfo(&self, input: &[T]) -> T {
+        if input.len() != self.get_symbol_length() {
+            panic!(
+                "Symbol buffer length must be {}, but got {}",
+                self.get_symbol_length(),
+                input.len()
+            );
+        }
+
+        let distance = 2 * self.constants.num_subcarriers as usize;
+        let cyclic_prefix_length = self.constants.cyclic_prefix_length as usize;
+
+        // the start of the cyclic prefix overlaps with the window of the previous symbol
+        let analytic = hilbert(input);
+        let correlation: Complex<T> = (self.constants.window_length()..cyclic_prefix_length)
+            .map(|n| analytic[n].conj() * analytic[n + distance])
+            .sum();
+
+        let cfo = correlation.arg() / cast::<T>(distance as f64);
+        log_debug!("estimated CFO {cfo:?} radians per sample");
+        cfo
+    }
+
+    /// Removes a carrier frequency offset from the time domain signal in place.
+    ///
+    /// The analytic signal is de-rotated by `cfo` radians per sample, starting with zero at the first sample,
+    /// and its real part is written back.
+    /// Pass the result of [estimate_cfo](OFDMDemodulator::estimate_cfo) to correct a received symbol.
+    pub fn correct_cfo(&self, samples: &mut [T], cfo: T) {
+        let analytic = hilbert(samples);
+        for (n, (sample, value)) in samples.iter_mut().zip(analytic).enumerate() {
+            let rotation = -cfo * cast(n as f64);
+            *sample = (value * Complex::from_polar(T::one(), rotation)).re;
+        }
+    }
+
+    /// Estimates the relative sampling frequency offset between the transmitter and the receiver.
+    ///
+    /// Independent clocks drift apart, so the symbols of a long packet move away from their nominal positions.
+    /// A symbol which is `d` samples late rotates its subcarrier `k` by `-π * k * d / num_subcarriers`,
+    /// so the pilots of consecutive symbols differ by a phase slope across frequency.
+    /// The slope is fitted to the pilots of every pair of consecutive `symbols` and averaged over the pairs,
+    /// after removing the common phase of the pair, which a carrier frequency offset adds.
+    ///
+    /// The `symbols` are the buffers of consecutive symbols of a packet, cut at their nominal positions,
+    /// the first one being the first symbol of the packet, so the [pilot polarity](OFDMDemodulatorConfig::pilot_polarity)
+    /// matches.
+    /// Cutting them a few samples early, within the cyclic prefix, keeps a drift in either direction
+    /// from reaching into the next symbol, which would distort the estimate.
+    /// Returns the offset in the convention of [apply_sfo](crate::channel::apply_sfo), e.g. `1e-4` if the
+    /// receiver samples 100 ppm faster, and `0.0` for fewer than 2 symbols or fewer than 2 pilots.
+    /// Remove it with [correct_sfo](OFDMDemodulator::correct_sfo).
+    ///
+    /// # Panics
+    /// If a symbol buffer length does not match `self.get_symbol_length()`.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::channel::apply_sfo;
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    ///
+    /// // a guard band below nyquist, which the interpolation of the resampling attenuates
+    /// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(16),
+    ///     null_subcarrier_indices: (53..64).collect(),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    /// let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(16),
+    ///     null_subcarrier_indices: (53..64).collect(),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    ///
+    /// let payload: Vec<u8> = (0..200 * modulator.bytes_per_symbol()).map(|i| (i * 37 % 251) as u8).collect();
+    /// let (samples, padding) = modulator.modulate_packet(&payload).unwrap();
+    /// // the receiver cuts the symbols 4 samples early
+    /// let signal = [vec![0.0; 4], samples.clone()].concat();
+    ///
+    /// let sfo_of = |received: &[f32]| {
+    ///     let symbols: Vec<&[f32]> = received.chunks_exact(demodulator.get_symbol_length()).collect();
+    ///     demodulator.estimate_sfo(&symbols)
+    /// };
+    /// let packet_of = |received: &[f32]| {
+    ///     let mut packet = received[4..].to_vec();
+    ///     packet.resize(samples.len(), 0.0);
+    ///     demodulator.demodulate_packet(&packet, padding)
+    /// };
+    /// assert!(sfo_of(&signal).abs() < 1e-6);
+    ///
+    /// for sfo in [2e-4, -1e-4] {
+    ///     let received = apply_sfo(&signal, sfo);
+    ///     let estimate = sfo_of(&received);
+    ///     assert!((estimate - sfo).abs() < 0.05 * sfo.abs());
+    ///
+    ///     // the last symbols drift by several samples, which costs bit errors unless corrected
+    ///     assert_ne!(packet_of(&received), payload);
+    ///     assert_eq!(packet_of(&demodulator.correct_sfo(&received, estimate)), payload);
+    /// }
+    /// ```
+    pub fn estimate_sfo(&self, symbols: &[&[T]]) -> T {
+        let pilots = |symbol_index: usize, symbol: &[T]| -> Vec<Complex<T>> {
+            let spectrum = self.extract_subcarriers(symbol);
+            let sign = self.constants.pilot_sign(symbol_index);
+            self.constants
+                .pilot_subcarrier_indices
+                .iter()
+                .zip(&self.constants.pilot_values)
+                .map(|(&idx, &pilot)| spectrum[idx as usize] * (pilot * sign).conj())
+                .collect()
+        };
+        let indices: Vec<T> = self
+            .constants
+            .pilot_subcarrier_indices
+            .iter()
+            .map(|&idx| cast(idx as f64))
+            .collect();
+        if indices.len() < 2 {
+            return T::zero();
+        }
+        let mean_index = indices.iter().copied().sum::<T>() / cast(indices.len() as f64);
+
+        // least squares slope of the phase differences over the subcarrier index, summed over all pairs
+        let mut covariance = T::zero();
+        let mut variance = T::zero();
+        let mut previous: Option<Vec<Complex<T>>> = None;
+        for (symbol_index, symbol) in symbols.iter().enumerate() {
+            let current = pilots(symbol_index, symbol);
+            if let Some(previous) = previous {
+                let differences: Vec<Complex<T>> = current
+                    .iter()
+                    .zip(&previous)
+                    .map(|(current, previous)| current * previous.conj())
+                    .collect();
+                let common = differences.iter().copied().sum::<Complex<T>>();
+                let common = common / Complex::from(common.norm().max(T::min_positive_value()));
+                let phases: Vec<T> = differences
+                    .iter()
+                    .map(|difference| (difference * common.conj()).arg())
+                    .collect();
+                let mean_phase = phases.iter().copied().sum::<T>() / cast(phases.len() as f64);
+                for (&index, &phase) in indices.iter().zip(&phases) {
+                    covariance = covariance + (index - mean_index) * (phase - mean_phase);
+                    variance = variance + (index - mean_index).powi(2);
+                }
+            }
+            previous = Some(current);
+        }
+        if variance == T::zero() {
+            return T::zero();
+        }
+
+        // a delay of d samples per symbol turns subcarrier k by -pi * k * d / num_subcarriers
+        let slope = covariance / variance;
+        let num_subcarriers: T = cast(self.constants.num_subcarriers as f64);
+        let symbol_length: T = cast(self.get_symbol_length() as f64);
+        -slope * num_subcarriers / (cast::<T>(std::f64::consts::PI) * symbol_length)
+    }
+
+    /// Removes a sampling frequency offset from the samples, the inverse of [apply_sfo](crate::channel::apply_sfo).
+    ///
+    /// The samples are interpolated at the positions `n * (1 + sfo)`, so a stretched signal is compressed again.
+    /// Pass the result of [estimate_sfo](OFDMDemodulator::estimate_sfo) to keep the symbols of a long packet aligned.
+    /// Like the fractional delays of [apply_delay](crate::channel::apply_delay), the interpolation attenuates
+    /// subcarriers close to nyquist, which a guard band below it avoids.
+    ///
+    /// # Panics
+    /// If `sfo` is not above `-1.0`.
+    pub fn correct_sfo(&self, samples: &[T], sfo: T) -> Vec<T> {
+        let stretch = 1.0 + sfo.to_f64().unwrap();
+        assert!(stretch > 0.0, "Sampling frequency offset must be above -1");
+
+        let output_length = (samples.len() as f64 / stretch).floor() as usize;
+        (0..output_length)
+            .map(|n| interpolate(samples, n as f64 * stretch))
+            .collect()
+    }
+
+    /// Searches the samples for a [Schmidl-Cox preamble](crate::ofdm::preamble) and returns where it starts.
+    ///
+    /// The [timing metric](crate::ofdm::preamble::timing_metric) of a preamble forms a plateau
+    /// as long as the cyclic prefix, so the center of the region around the peak
+    /// where the metric stays above 90% of the peak is used to locate the preamble.
+    ///
+    /// Returns the index of the first sample of the preamble, including its cyclic prefix,
+    /// so the first data symbol starts `self.get_symbol_length()` samples later.
+    /// Returns `None` if the metric never exceeds the configured `preamble_threshold`.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    ///
+    /// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(16),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    /// let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(16),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    ///
+    /// // simple deterministic noise source
+    /// let mut state: u32 = 1;
+    /// let mut noise = move || {
+    ///     state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+    ///     (state >> 8) as f32 / (1 << 24) as f32 - 0.5
+    /// };
+    ///
+    /// let data = "Hello, OFDM!            ".as_bytes();
+    /// let mut symbol = vec![0.0; modulator.get_symbol_length()];
+    /// modulator.modulate_buffer_as_symbol(data, &mut symbol).unwrap();
+    ///
+    /// let mut samples = vec![0.0; 300];
+    /// samples.extend(modulator.generate_preamble());
+    /// samples.extend(&symbol);
+    /// samples.extend(vec![0.0; 200]);
+    /// samples.iter_mut().for_each(|s| *s += 10.0 * noise());
+    ///
+    /// let start = demodulator.find_frame_start(&samples).unwrap();
+    /// assert!(start.abs_diff(300) <= 3);
+    ///
+    /// // noise alone does not trigger the detector
+    /// let noise_only: Vec<f32> = (0..1000).map(|_| noise()).collect();
+    /// assert_eq!(demodulator.find_frame_start(&noise_only), None);
+    /// ```
+    pub fn find_frame_start(&self, samples: &[T]) -> Option<usize> {
+        let half_length = self.constants.num_subcarriers as usize;
+        let metric = timing_metric(samples, half_length);
+
+        let (peak, &peak_value) = metric
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(Ordering::Equal))?;
+        if peak_value < self.preamble_threshold {
+            log_debug!("no preamble found, peak timing metric {peak_value:?} at sample {peak}");
+            return None;
+        }
+
+        // extend the peak to the whole plateau
+        let level = cast::<T>(0.9) * peak_value;
+        let first = (0..=peak)
+            .rev()
+            .take_while(|&d| metric[d] >= level)
+            .last()
+            .unwrap_or(peak);
+        let last = (peak..metric.len())
+            .take_while(|&d| metric[d] >= level)
+            .last()
+            .unwrap_or(peak);
+
+        // the plateau spans the cyclic prefix, its center is half a cyclic prefix after the start
+        // with windowing, the metric drops within the fade in of the preamble,
+        // so the plateau starts about halfway into the window
+        let center = (first + last) / 2;
+        let offset =
+            (2 * self.constants.cyclic_prefix_length as usize + self.constants.window_length()) / 4;
+        let start = center.saturating_sub(offset);
+        log_debug!("frame found at sample {start}, peak timing metric {peak_value:?}");
+        Some(start)
+    }
+
+    /// Refines a coarse symbol start, e.g. of [find_frame_start](OFDMDemodulator::find_frame_start),
+    /// to the exact sample with the cyclic prefix.
+    ///
+    /// The cyclic prefix repeats the last samples of its symbol, so at the correct start
+    /// the prefix and the end of the symbol match.
+    /// Every start within one cyclic prefix length around `coarse_start` is tried and the one
+    /// with the smallest squared difference between the prefixes and the ends of all following symbols is returned.
+    /// The samples tapered by a `window_length` are left out of the comparison.
+    /// Without a cyclic prefix there is nothing to compare and `coarse_start` is returned unchanged.
+    ///
+    /// A start a few samples too early only rotates the subcarriers, which the pilots correct,
+    /// but a start too late takes samples of the next symbol and causes interference between the symbols.
+    ///
+    /// # Panics
+    /// If the input holds no whole symbol starting at `coarse_start`.
+    ///
+    /// # Example
+    /// ```
+    /// use rand::{SeedableRng, rngs::StdRng};
+    /// use software_modem::channel::add_awgn;
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    ///
+    /// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(16),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    /// let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(16),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    ///
+    /// let payload: Vec<u8> = (0..96).map(|i| (i * 37 % 251) as u8).collect();
+    /// let (symbols, padding) = modulator.modulate_packet(&payload).unwrap();
+    /// let mut samples = vec![0.0; 100];
+    /// samples.extend(modulator.generate_preamble());
+    /// samples.extend(&symbols);
+    /// samples.extend(vec![0.0; 100]);
+    /// add_awgn(&mut samples, 30.0, &mut StdRng::seed_from_u64(3));
+    ///
+    /// let start = 100 + demodulator.get_symbol_length();
+    /// for error in -3..=3 {
+    ///     let coarse_start = start.checked_add_signed(error).unwrap();
+    ///     let refined = demodulator.refine_timing(&samples, coarse_start);
+    ///     assert_eq!(refined, start);
+    ///     assert_eq!(
+    ///         demodulator.demodulate_packet(&samples[refined..refined + symbols.len()], padding),
+    ///         payload
+    ///     );
+    /// }
+    /// ```
+    pub fn refine_timing(&self, input: &[T], coarse_start: usize) -> usize {
+        let symbol_length = self.get_symbol_length();
+        if input.len() < coarse_start + symbol_length {
+            panic!(
+                "Input must hold a symbol of {} samples after {}, but holds {} samples",
+                symbol_length,
+                coarse_start,
+                input.len()
+            );
+        }
+
+        let cyclic_prefix_length = self.constants.cyclic_prefix_length as usize;
+        let compared = self.constants.window_length()..cyclic_prefix_length;
+        if compared.is_empty() {
+            return coarse_start;
+        }
+
+        // every candidate compares the same number of symbols
+        let first = coarse_start.saturating_sub(cyclic_prefix_length);
+        let last = (coarse_start + cyclic_prefix_length).min(input.len() - symbol_length);
+        let num_symbols = (input.len() - last) / symbol_length;
+        let fft_length = 2 * self.constants.num_subcarriers as usize;
+
+        let mismatch = |start: usize| {
+            (0..num_symbols)
+                .flat_map(|symbol| {
+                    compared
+                        .clone()
+                        .map(move |i| start + symbol * symbol_length + i)
+                })
+                .map(|i| (input[i] - input[i + fft_length]).powi(2))
+                .sum::<T>()
+        };
+        let start = (first..=last)
+            .map(|start| (start, mismatch(start)))
+            .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+            .map_or(coarse_start, |(start, _)| start);
+        log_debug!("timing refined from sample {coarse_start} to {start}");
+        start
+    }
+
+    /// Estimates the gain of the channel from a received [preamble](crate::ofdm::preamble).
+    ///
+    /// The preamble carries a known energy, so the ratio of the received to the transmitted energy
+    /// on its subcarriers gives the gain of the channel.
+    /// Divide the received samples by the estimate to bring the signal back to its nominal level.
+    ///
+    /// The pilots already compensate a constant gain for the [zero forcing](Equalizer::ZeroForcing) equalizer,
+    /// but the `noise_var` of the [MMSE](Equalizer::Mmse) equalizer refers to the nominal level.
+    ///
+    /// # Panics
+    /// If the input length does not match `self.get_symbol_length()`.
+    ///
+    /// # Example
+    /// ```
+    /// use rand::{SeedableRng, rngs::StdRng};
+    /// use software_modem::channel::add_awgn;
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    ///
+    /// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(16),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    /// let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(16),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    ///
+    /// let payload = b"Quiet, but clear";
+    /// let (symbols, padding) = modulator.modulate_packet(payload).unwrap();
+    /// let mut samples = modulator.generate_preamble();
+    /// samples.extend(symbols);
+    ///
+    /// // a microphone 20 dB below the nominal level
+    /// samples.iter_mut().for_each(|s| *s *= 0.1);
+    /// add_awgn(&mut samples, 25.0, &mut StdRng::seed_from_u64(7));
+    ///
+    /// let symbol_length = demodulator.get_symbol_length();
+    /// let gain = demodulator.estimate_gain(&samples[..symbol_length]);
+    /// assert!((gain - 0.1).abs() < 0.005);
+    ///
+    /// samples.iter_mut().for_each(|s| *s /= gain);
+    /// assert!((demodulator.estimate_gain(&samples[..symbol_length]) - 1.0).abs() < 1e-3);
+    /// assert_eq!(
+    ///     demodulator.demodulate_packet(&samples[symbol_length..], padding),
+    ///     payload
+    /// );
+    /// ```
+    pub fn estimate_gain(&self, preamble_samples: &[T]) -> T {
+        if preamble_samples.len() != self.get_symbol_length() {
+            panic!(
+                "Preamble length must be {}, but got {}",
+                self.get_symbol_length(),
+                preamble_samples.len()
+            );
+        }
+
+        let received = self.symbol_spectrum(preamble_samples);
+        let (received_energy, transmitted_energy) = preamble_spectrum(&self.constants)
+            .iter()
+            .zip(&received)
+            .filter(|(transmitted, _)| transmitted.norm_sqr() > T::zero())
+            .fold(
+                (T::zero(), T::zero()),
+                |(received_energy, transmitted_energy), (transmitted, received)| {
+                    (
+                        received_energy + received.norm_sqr(),
+                        transmitted_energy + transmitted.norm_sqr(),
+                    )
+                },
+            );
+
+        (received_energy / transmitted_energy).sqrt()
+    }
+
+    /// Returns the length of the OFDM symbol, including the cyclic prefix.
+    ///
+    /// The length is calculated as:
+    /// `2 * num_subcarriers` plus the length of the cyclic prefix.
+    pub fn get_symbol_length(&self) -> usize {
+        self.constants.symbol_length()
+    }
+
+    /// Splits a capture into consecutive windows of `self.get_symbol_length()` samples,
+    /// dropping a trailing remainder shorter than a symbol.
+    ///
+    /// A lightweight alternative to [find_frame_start](OFDMDemodulator::find_frame_start)
+    /// for captures which are known to start with a symbol, e.g. a loopback test.
+    /// Every window can be demodulated with [demodulate_symbol_from_buffer](OFDMDemodulator::demodulate_symbol_from_buffer).
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    ///
+    /// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(16),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    /// let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(16),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    ///
+    /// let messages: Vec<Vec<u8>> = (0..5).map(|i| vec![i; modulator.bytes_per_symbol()]).collect();
+    /// let mut samples = Vec::new();
+    /// for message in &messages {
+    ///     let mut symbol = vec![0.0; modulator.get_symbol_length()];
+    ///     modulator.modulate_buffer_as_symbol(message, &mut symbol).unwrap();
+    ///     samples.extend(symbol);
+    /// }
+    /// // the capture stops in the middle of a sixth symbol
+    /// samples.extend(vec![0.1; 50]);
+    ///
+    /// let symbols: Vec<&[f32]> = demodulator.chunk_symbols(&samples).collect();
+    /// assert_eq!(symbols.len(), 5);
+    /// for (symbol, message) in symbols.into_iter().zip(&messages) {
+    ///     assert_eq!(symbol.len(), demodulator.get_symbol_length());
+    ///     assert_eq!(&demodulator.demodulate_symbol_from_buffer(symbol), message);
+    /// }
+    /// ```
+    pub fn chunk_symbols<'a>(
+        &self,
+        samples: &'a [T],
+    ) -> impl Iterator<Item = &'a [T]> + use<'a, T> {
+        samples.chunks_exact(self.get_symbol_length())
+    }
+
+    /// Returns the number of payload bytes carried by one OFDM symbol.
+    ///
+    /// This is `bits_per_symbol() / 8`, data subcarriers not covered by a whole byte stay empty.
+    ///
+    /// See [OFDMModulator::bytes_per_symbol](crate::ofdm::modulator::OFDMModulator::bytes_per_symbol) for an example.
+    pub fn bytes_per_symbol(&self) -> usize {
+        self.constants.bytes_per_symbol()
+    }
+
+    /// Returns the number of bits the data subcarriers of one OFDM symbol can carry.
+    pub fn bits_per_symbol(&self) -> u32 {
+        self.constants.bits_per_symbol
+    }
+
+    /// Returns the number of subcarriers carrying data.
+    pub fn num_data_subcarriers(&self) -> u32 {
+        self.constants.num_data_subcarriers
+    }
+
+    /// Returns the number of subcarriers carrying pilots.
+    pub fn num_pilot_subcarriers(&self) -> u32 {
+        self.constants.num_pilot_subcarriers
+    }
+
+    /// Returns the indices of the subcarriers carrying data in the order their bits are mapped in,
+    /// which is ascending unless another [subcarrier_mapping](OFDMDemodulatorConfig::subcarrier_mapping) is configured.
+    ///
+    /// The subcarriers carrying the selective mapping index are not included.
+    pub fn data_subcarrier_indices(&self) -> &[u32] {
+        &self.constants.data_subcarrier_indices
+    }
+
+    /// Returns the indices of the subcarriers carrying pilots in ascending order,
+    /// the order the values of the pilot sequence are assigned in.
+    pub fn pilot_subcarrier_indices(&self) -> &[u32] {
+        &self.constants.pilot_subcarrier_indices
+    }
+
+    /// Number of samples of the fade out trailing a packet.
+    pub(super) fn window_length(&self) -> usize {
+        self.constants.window_length()
+    }
+
+    /// Number of symbols [modulate_packet](crate::ofdm::modulator::OFDMModulator::modulate_packet)
+    /// produces for `num_bytes` bytes.
+    pub(super) fn num_packet_symbols(&self, num_bytes: usize) -> usize {
+        self.encoded_length(num_bytes)
+            .div_ceil(self.constants.bytes_per_symbol())
+            * self.constants.repetition as usize
+    }
+
+    /// Number of bytes `num_bytes` payload bytes take after the configured coding, without padding.
+    fn encoded_length(&self, num_bytes: usize) -> usize {
+        #[cfg(feature = "rs")]
+        let num_bytes = if self.reed_solomon {
+            num_bytes
+                + num_bytes.div_ceil(fec::reed_solomon::DATA_LENGTH)
+                    * fec::reed_solomon::PARITY_LENGTH
+        } else {
+            num_bytes
+        };
+        if self.convolutional_coding && num_bytes > 0 {
+            2 * num_bytes + 2
+        } else {
+            num_bytes
+        }
+    }
+}
+
+/// Demodulates packets with buffers allocated up front, for real-time threads like audio callbacks.
+///
+/// [demodulate_into](DemodContext::demodulate_into) demodulates the symbols of a packet
+/// like [demodulate_packet](OFDMDemodulator::demodulate_packet),
+/// but keeps the buffers for the FFT, the channel estimate and the constellation between calls.
+///
+/// # Real-time guarantees
+/// All buffers are allocated by [new](DemodContext::new). Once the output vector has room for the bytes of a packet,
+/// e.g. after the first call with the longest expected packet, demodulating does not allocate or free memory.
+/// No locks are taken and there is no I/O, so the time a call takes only depends on the number of symbols.
+/// The [Reed-Solomon code](crate::fec::reed_solomon) and the [convolutional code](crate::fec) are not decoded,
+/// since decoding allocates. With forward error correction enabled, the output holds the coded bytes,
+/// which can be [decoded](crate::fec::decode) outside of the real-time thread.
+///
+/// # Example
+/// ```
+/// use software_modem::ofdm::CyclicPrefix;
+/// use software_modem::ofdm::demodulator::{DemodContext, OFDMDemodulator, OFDMDemodulatorConfig};
+/// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+///
+/// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+///     num_subcarriers: 64,
+///     cyclic_prefix: CyclicPrefix::Samples(16),
+///     scrambling: true,
+///     interleaving: true,
+///     ..Default::default()
+/// })
+/// .unwrap();
+/// let demodulator = || {
+///     OFDMDemodulator::new(OFDMDemodulatorConfig {
+///         num_subcarriers: 64,
+///         cyclic_prefix: CyclicPrefix::Samples(16),
+///         scrambling: true,
+///         interleaving: true,
+///         ..Default::default()
+///     })
+///     .unwrap()
+/// };
+///
+/// let payload: Vec<u8> = (0..100).collect();
+/// let (samples, padding) = modulator.modulate_packet(&payload).unwrap();
+///
+/// let mut context = DemodContext::new(demodulator());
+/// let mut received = Vec::new();
+/// context.demodulate_into(&samples, &mut received);
+///
+/// // the padding is left to the caller
+/// received.truncate(received.len() - padding);
+/// assert_eq!(received, payload);
+/// assert_eq!(received, demodulator().demodulate_packet(&samples, padding));
+/// ```
+///
+/// Counting the allocations of the whole program shows that none are left after the first packet:
+/// ```
+/// use std::alloc::{GlobalAlloc, Layout, System};
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+///
+/// use software_modem::ofdm::CyclicPrefix;
+/// use software_modem::ofdm::demodulator::{DemodContext, OFDMDemodulator, OFDMDemodulatorConfig};
+/// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+///
+/// struct CountingAllocator;
+///
+/// static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+///
+/// unsafe impl GlobalAlloc for CountingAllocator {
+///     unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+///         ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+///         unsafe { System.alloc(layout) }
+///     }
+///
+///     unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+///         unsafe { System.dealloc(ptr, layout) }
+///     }
+/// }
+///
+/// #[global_allocator]
+/// static ALLOCATOR: CountingAllocator = CountingAllocator;
+///
+/// fn main() {
+///     let modulator = OFDMModulator::new(OFDMModulatorConfig {
+///         num_subcarriers: 64,
+///         cyclic_prefix: CyclicPrefix::Samples(16),
+///         window_length: 4,
+///         pilot_polarity: true,
+///         num_slm_candidates: 4,
+///         repetition: 2,
+///         scrambling: true,
+///         interleaving: true,
+///         ..Default::default()
+///     })
+///     .unwrap();
+///     let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+///         num_subcarriers: 64,
+///         cyclic_prefix: CyclicPrefix::Samples(16),
+///         window_length: 4,
+///         pilot_polarity: true,
+///         num_slm_candidates: 4,
+///         repetition: 2,
+///         scrambling: true,
+///         interleaving: true,
+///         decision_directed_iterations: 2,
+///         ..Default::default()
+///     })
+///     .unwrap();
+///
+///     let packets: Vec<(Vec<u8>, (Vec<f32>, usize))> = (0..4)
+///         .map(|i| {
+///             let payload: Vec<u8> = (0..200 + i * 20).map(|j| (j * 37 % 251) as u8).collect();
+///             let packet = modulator.modulate_packet(&payload).unwrap();
+///             (payload, packet)
+///         })
+///         .collect();
+///
+///     let mut context = DemodContext::new(demodulator);
+///     let mut received = Vec::new();
+///     for (payload, (samples, padding)) in packets.iter().rev() {
+///         let before = ALLOCATIONS.load(Ordering::Relaxed);
+///         context.demodulate_into(samples, &mut received);
+///         let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+///
+///         // the first and longest packet grows the output
+///         if payload.len() < 260 {
+///             assert_eq!(allocations, 0);
+///         }
+///         assert_eq!(&received[..received.len() - padding], payload);
+///     }
+/// }
+/// ```
+pub struct DemodContext<T: Float = f32> {
+    demodulator: OFDMDemodulator<T>,
+    scratch: Scratch<T>,
+}
+
+/// Buffers of a [DemodContext], reused between symbols.
+struct Scratch<T: Float> {
+    /// The average of the copies of a repeated symbol.
+    symbol: Vec<T>,
+    time: Vec<T>,
+    spectrum: Vec<Complex<T>>,
+    fft: Vec<Complex<T>>,
+    pilots: Vec<(u32, Complex<T>)>,
+    channel: Vec<Complex<T>>,
+    points: Vec<Complex<T>>,
+    /// Observations of the channel for the [decision directed refinement](OFDMDemodulatorConfig::decision_directed_iterations).
+    estimates: Vec<Option<Complex<T>>>,
+    refined_channel: Vec<Complex<T>>,
+    refined_points: Vec<Complex<T>>,
+    /// The bytes of one symbol before deinterleaving.
+    bytes: Vec<u8>,
+}
+
+impl<T: Float> DemodContext<T> {
+    /// Creates a context demodulating with the given demodulator and allocates its buffers.
+    pub fn new(demodulator: OFDMDemodulator<T>) -> Self {
+        let constants = &demodulator.constants;
+        let scratch = Scratch {
+            symbol: vec![T::zero(); constants.symbol_length()],
+            time: vec![T::zero(); 2 * constants.num_subcarriers as usize],
+            spectrum: demodulator.fft.make_output_vec(),
+            fft: demodulator.fft.make_scratch_vec(),
+            pilots: Vec::with_capacity(constants.pilot_subcarrier_indices.len()),
+            channel: vec![Complex::default(); constants.num_subcarriers as usize + 1],
+            points: Vec::with_capacity(constants.data_subcarrier_indices.len()),
+            estimates: Vec::with_capacity(constants.num_subcarriers as usize + 1),
+            refined_channel: Vec::with_capacity(constants.num_subcarriers as usize + 1),
+            refined_points: Vec::with_capacity(constants.data_subcarrier_indices.len()),
+            bytes: Vec::with_capacity(constants.bits_per_symbol.div_ceil(8) as usize),
+        };
+
+        DemodContext {
+            demodulator,
+            scratch,
+        }
+    }
+
+    /// Returns the demodulator of this context.
+    pub fn demodulator(&self) -> &OFDMDemodulator<T> {
+        &self.demodulator
+    }
+
+    /// Demodulates a sequence of OFDM symbols produced by
+    /// [modulate_packet](crate::ofdm::modulator::OFDMModulator::modulate_packet), replacing the content of `out`.
+    ///
+    /// The symbols are demodulated, deinterleaved and descrambled like by
+    /// [demodulate_packet](OFDMDemodulator::demodulate_packet), including the `window_length` and the `repetition`.
+    /// Trimming the padding and decoding the forward error correction are left to the caller.
+    /// Does not allocate if `out` has room for `self.demodulator().bytes_per_symbol()` bytes per symbol.
+    ///
+    /// # Panics
+    /// If the input length without the trailing `window_length` and `trailing_gap` samples is not a multiple of
+    /// `self.demodulator().get_symbol_length()` times the `repetition`.
+    pub fn demodulate_into(&mut self, input: &[T], out: &mut Vec<u8>) {
+        let demodulator = &self.demodulator;
+        let constants = &demodulator.constants;
+        let scratch = &mut self.scratch;
+
+        let input = demodulator.packet_symbols(demodulator.strip_gap(input));
+        let copies_length = constants.symbol_length() * constants.repetition as usize;
+        let num_payload_subcarriers = demodulator.num_payload_subcarriers();
+
+        out.clear();
+        for (i, copies) in input.chunks(copies_length).enumerate() {
+            let symbol = if constants.repetition > 1 {
+                average_copies_into::<T, _>(copies, &mut scratch.symbol);
+                &scratch.symbol
+            } else {
+                copies
+            };
+
+            demodulator.symbol_spectrum_into(
+                symbol,
+                &mut scratch.time,
+                &mut scratch.spectrum,
+                &mut scratch.fft,
+            );
+            demodulator.channel_into(
+                &scratch.spectrum,
+                i,
+                &mut scratch.pilots,
+                &mut scratch.channel,
+            );
+            demodulator.equalize_spectrum_into(
+                &scratch.spectrum,
+                &scratch.channel,
+                &mut scratch.points,
+            );
+            demodulator.refine_channel(
+                &scratch.spectrum,
+                i,
+                &constants.bit_loading,
+                &mut scratch.channel,
+                &mut scratch.points,
+                &mut scratch.estimates,
+                &mut scratch.refined_channel,
+                &mut scratch.refined_points,
+            );
+            scratch.points.truncate(num_payload_subcarriers);
+
+            match &demodulator.interleaver {
+                Some(interleaver) => {
+                    scratch.bytes.clear();
+                    constants
+                        .bit_loading
+                        .demodulate_into(&scratch.points, &mut scratch.bytes);
+
+                    let start = out.len();
+                    out.resize(start + scratch.bytes.len(), 0);
+                    interleaver.deinterleave_into(&scratch.bytes, &mut out[start..]);
+                }
+                None => constants.bit_loading.demodulate_into(&scratch.points, out),
+            }
+        }
+
+        if demodulator.scrambling {
+            scrambler::scramble_in_place(out);
+        }
+    }
+}
+
+/// Plans the forward FFT the demodulator uses by default for `num_subcarriers` subcarriers.
+///
+/// The FFT transforms the `2 * num_subcarriers` samples of a symbol into the `num_subcarriers + 1` bins from DC to nyquist.
+/// Pass it as [fft](OFDMDemodulatorConfig::fft) to share one plan between several demodulators.
+pub fn plan_default_fft<T: Float>(num_subcarriers: u32) -> Arc<dyn RealToComplex<T>> {
+    RealFftPlanner::<T>::new().plan_fft_forward(2 * num_subcarriers as usize)
+}
+
+/// Mean squared distance of equalized data subcarrier points from their decisions.
+fn decision_error<T: Float>(loading: &BitLoading<T>, points: &[Complex<T>]) -> T {
+    if points.is_empty() {
+        return T::zero();
+    }
+
+    let sum: T = points
+        .iter()
+        .enumerate()
+        .map(|(i, point)| (point - loading.nearest_point(i, point)).norm_sqr())
+        .sum();
+    sum / cast(points.len() as f64)
+}
+
+/// Mean magnitude of the channel estimate on the given subcarriers.
+#[cfg(feature = "log")]
+fn mean_gain<T: Float>(subcarrier_indices: &[u32], channel: &[Complex<T>]) -> T {
+    let sum: T = subcarrier_indices
+        .iter()
+        .map(|&index| channel[index as usize].norm())
+        .sum();
+    sum / cast(subcarrier_indices.len().max(1) as f64)
+}
+
+/// Averages the consecutive copies of a symbol of `symbol_length` samples.
+fn average_copies<T, S>(copies: &[S], symbol_length: usize) -> Vec<S>
+where
+    T: Float,
+    S: Copy + Add<Output = S> + Div<T, Output = S>,
+{
+    let mut symbol = copies[..symbol_length].to_vec();
+    average_copies_into::<T, _>(copies, &mut symbol);
+    symbol
+}
+
+/// Averages the consecutive copies of a symbol like [average_copies] into `symbol`,
+/// whose length is the length of one copy.
+fn average_copies_into<T, S>(copies: &[S], symbol: &mut [S])
+where
+    T: Float,
+    S: Copy + Add<Output = S> + Div<T, Output = S>,
+{
+    let symbol_length = symbol.len();
+    symbol.copy_from_slice(&copies[..symbol_length]);
+    for copy in copies[symbol_length..].chunks(symbol_length) {
+        for (sum, &sample) in symbol.iter_mut().zip(copy) {
+            *sum = *sum + sample;
+        }
+    }
+
+    let num_copies: T = cast((copies.len() / symbol_length) as f64);
+    for sum in symbol.iter_mut() {
+        *sum = *sum / num_copies;
+    }
+}
+
+/// Configuration for the [OFDM Demodulator](OFDMDemodulator).
+///
+/// Just contruct this struct with the desired parameters and pass it to the `OFDMDemodulator::new()` method.
+#[derive(SmartDefault)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct OFDMDemodulatorConfig<T: Float = f32> {
+    pub num_subcarriers: u32,
+    /// Length of the [cyclic prefix](CyclicPrefix), in samples or as a fraction of the symbol.
+    ///
+    /// Has to match the setting of the modulator, see
+    /// [OFDMModulatorConfig::cyclic_prefix](crate::ofdm::modulator::OFDMModulatorConfig::cyclic_prefix).
+    pub cyclic_prefix: CyclicPrefix,
+    /// Length of the raised-cosine window at the edges of every symbol in samples.
+    ///
+    /// Has to match the setting of the modulator, see
+    /// [OFDMModulatorConfig::window_length](crate::ofdm::modulator::OFDMModulatorConfig::window_length).
+    /// It is taken into account when locating the preamble and estimating the frequency offset,
+    /// and the fade out after the last symbol of a packet is skipped.
+    pub window_length: u32,
+    /// Number of silent samples after every packet.
+    ///
+    /// Has to match the setting of the modulator, see
+    /// [OFDMModulatorConfig::trailing_gap](crate::ofdm::modulator::OFDMModulatorConfig::trailing_gap).
+    /// [demodulate_packet](OFDMDemodulator::demodulate_packet) and [DemodContext] skip the gap,
+    /// the length of packets with a header or a frame is known, so they ignore it in any case.
+    pub trailing_gap: u32,
+    /// Interval for pilot subcarriers.
+    ///
+    /// Inserts pilot subcarriers every `pilot_subcarrier_every` subcarrier.
+    /// Only the subcarriers between DC and nyquist are used, so the interval must be between 2 and `num_subcarriers - 1`.
+    /// Ignored if [pilot_subcarrier_indices](OFDMDemodulatorConfig::pilot_subcarrier_indices) are configured.
+    #[default(4)]
+    pub pilot_subcarrier_every: u32,
+    /// Subcarriers carrying pilots, taking precedence over `pilot_subcarrier_every` if not empty.
+    ///
+    /// Has to match the setting of the modulator, see
+    /// [OFDMModulatorConfig::pilot_subcarrier_indices](crate::ofdm::modulator::OFDMModulatorConfig::pilot_subcarrier_indices).
+    pub pilot_subcarrier_indices: Vec<u32>,
+    /// Subcarriers which carry neither data nor pilots.
+    ///
+    /// Has to match the setting of the modulator, see
+    /// [OFDMModulatorConfig::null_subcarrier_indices](crate::ofdm::modulator::OFDMModulatorConfig::null_subcarrier_indices).
+    pub null_subcarrier_indices: Vec<u32>,
+    /// Ranges of subcarriers which are ignored.
+    ///
+    /// Has to match the setting of the modulator, see
+    /// [OFDMModulatorConfig::spectral_mask](crate::ofdm::modulator::OFDMModulatorConfig::spectral_mask).
+    pub spectral_mask: Vec<(u32, u32)>,
+    pub qam_order: QAMOrder,
+    /// QAM order of every data subcarrier, see
+    /// [OFDMModulatorConfig::bit_loading](crate::ofdm::modulator::OFDMModulatorConfig::bit_loading).
+    ///
+    /// Must match the bit loading of the modulator.
+    pub bit_loading: Vec<QAMOrder>,
+    /// Order in which the data is laid onto the data subcarriers.
+    ///
+    /// Has to match the setting of the modulator, see [SubcarrierMapping].
+    pub subcarrier_mapping: SubcarrierMapping,
+    /// Values transmitted on the pilot subcarriers, indexed by pilot position.
+    ///
+    /// Must match the pilot sequence of the modulator, as it is used to estimate the channel.
+    /// If empty, every pilot subcarrier is expected to carry [DEFAULT_PILOT_VALUE](crate::ofdm::DEFAULT_PILOT_VALUE) (`1 + 0i`).
+    pub pilot_sequence: Vec<Complex<T>>,
+    /// Expect the pilots of the symbols of a packet to be inverted following the
+    /// [pilot polarity](crate::ofdm::pilot_polarity) sequence.
+    ///
+    /// Has to match the setting of the modulator, see
+    /// [OFDMModulatorConfig::pilot_polarity](crate::ofdm::modulator::OFDMModulatorConfig::pilot_polarity).
+    pub pilot_polarity: bool,
+    /// Minimum value of the [timing metric](crate::ofdm::preamble::timing_metric) required to detect a preamble.
+    ///
+    /// The metric is close to `1.0` for a clean preamble and drops with noise,
+    /// lower values detect preambles in more noise, but also produce more false detections.
+    #[default(0.5)]
+    pub preamble_threshold: f32,
+    /// How the data subcarriers are equalized with the channel estimate, see [Equalizer].
+    pub equalizer: Equalizer,
+    /// Number of times the channel estimate of a symbol is refined with the decisions on its data subcarriers.
+    ///
+    /// Every iteration slices the equalized data subcarriers, takes the ones close to their decision
+    /// as additional observations of the channel next to the pilots, averages the observations of
+    /// neighbouring subcarriers and equalizes the symbol again with this estimate.
+    /// The many data subcarriers average out more noise than the few pilots,
+    /// which lowers the error at high SNR on a channel which varies slowly over the subcarriers.
+    /// An iteration is only kept if it moves the points closer to their decisions, otherwise the refinement stops,
+    /// so wrong decisions at low SNR can not make the estimate diverge.
+    /// The channel is refined for every way of demodulating a symbol, except the channel returned by
+    /// [estimate_channel](OFDMDemodulator::estimate_channel), which stays the estimate of the pilots.
+    /// Defaults to `0`, which disables the refinement.
+    ///
+    /// # Example
+    /// ```
+    /// use rand::{SeedableRng, rngs::StdRng};
+    /// use software_modem::channel::add_awgn;
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    ///
+    /// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(16),
+    ///     scrambling: true,
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    /// let demodulator = |decision_directed_iterations| {
+    ///     OFDMDemodulator::new(OFDMDemodulatorConfig {
+    ///         num_subcarriers: 64,
+    ///         cyclic_prefix: CyclicPrefix::Samples(16),
+    ///         decision_directed_iterations,
+    ///         ..Default::default()
+    ///     })
+    ///     .unwrap()
+    /// };
+    ///
+    /// let (samples, _) = modulator.modulate_packet(&vec![0x5a; 24 * 40]).unwrap();
+    ///
+    /// // a short echo, whose frequency response varies slowly over the subcarriers
+    /// let mut received: Vec<f32> = (0..samples.len())
+    ///     .map(|i| samples[i] + 0.4 * if i >= 3 { samples[i - 3] } else { 0.0 })
+    ///     .collect();
+    /// add_awgn(&mut received, 25.0, &mut StdRng::seed_from_u64(3));
+    ///
+    /// let mean_snr_db = |demodulator: &OFDMDemodulator| {
+    ///     let symbols = demodulator.chunk_symbols(&received);
+    ///     let estimates: Vec<f32> = symbols.map(|symbol| demodulator.demodulate_with_quality(symbol).1.snr_db).collect();
+    ///     estimates.iter().sum::<f32>() / estimates.len() as f32
+    /// };
+    /// let pilots_only = mean_snr_db(&demodulator(0));
+    /// let refined = mean_snr_db(&demodulator(1));
+    ///
+    /// // the error vector magnitude drops by more than 1 dB
+    /// assert!(refined > pilots_only + 1.0);
+    /// ```
+    pub decision_directed_iterations: u32,
+    /// Largest [error vector magnitude](OFDMDemodulator::evm) in percent of a symbol
+    /// [demodulate_confident_symbol](OFDMDemodulator::demodulate_confident_symbol) accepts.
+    ///
+    /// Rejects a whole symbol instead of delivering its likely corrupted bytes, e.g. to request a retransmission.
+    /// An EVM of 30 % corresponds to an SNR of about 10 dB, independent of the QAM order.
+    /// If `None`, every symbol is accepted.
+    pub max_evm_percent: Option<f32>,
+    /// Decode packets protected with the [convolutional code](crate::fec).
+    ///
+    /// Has to match the setting of the modulator. Applies to the payload of every packet,
+    /// [demodulate_packet](OFDMDemodulator::demodulate_packet) as well as framed packets, packets with a header,
+    /// [receive_frame](OFDMDemodulator::receive_frame), the [StreamingDemodulator](crate::ofdm::streaming::StreamingDemodulator)
+    /// and [demodulate_iq](OFDMDemodulator::demodulate_iq), while [DemodContext] leaves the decoding to the caller.
+    pub convolutional_coding: bool,
+    /// Decode packets protected with the [Reed-Solomon code](crate::fec::reed_solomon), requires the `rs` feature.
+    ///
+    /// Has to match the setting of the modulator. Applies to the payload of every packet,
+    /// [demodulate_packet](OFDMDemodulator::demodulate_packet) as well as framed packets, packets with a header,
+    /// [receive_frame](OFDMDemodulator::receive_frame), the [StreamingDemodulator](crate::ofdm::streaming::StreamingDemodulator)
+    /// and [demodulate_iq](OFDMDemodulator::demodulate_iq), while [DemodContext] leaves the decoding to the caller,
+    /// see [OFDMModulatorConfig::reed_solomon](crate::ofdm::modulator::OFDMModulatorConfig::reed_solomon).
+    #[cfg(feature = "rs")]
+    pub reed_solomon: bool,
+    /// Descramble packets [scrambled](crate::scrambler) by the modulator.
+    ///
+    /// Has to match the setting of the modulator. Applies to the payload of every packet,
+    /// [demodulate_packet](OFDMDemodulator::demodulate_packet) as well as framed packets, packets with a header,
+    /// [receive_frame](OFDMDemodulator::receive_frame), [DemodContext], the [StreamingDemodulator](crate::ofdm::streaming::StreamingDemodulator)
+    /// and [demodulate_iq](OFDMDemodulator::demodulate_iq).
+    pub scrambling: bool,
+    /// Deinterleave packets [interleaved](crate::interleaver) by the modulator.
+    ///
+    /// Has to match the setting of the modulator. Applies to the payload of every packet,
+    /// [demodulate_packet](OFDMDemodulator::demodulate_packet) as well as framed packets, packets with a header,
+    /// [receive_frame](OFDMDemodulator::receive_frame), [DemodContext], the [StreamingDemodulator](crate::ofdm::streaming::StreamingDemodulator)
+    /// and [demodulate_iq](OFDMDemodulator::demodulate_iq).
+    pub interleaving: bool,
+    /// Number of phase sequences the modulator chooses from with [selective mapping](crate::ofdm::slm).
+    ///
+    /// Has to match the setting of the modulator, see
+    /// [OFDMModulatorConfig::num_slm_candidates](crate::ofdm::modulator::OFDMModulatorConfig::num_slm_candidates).
+    #[default(1)]
+    pub num_slm_candidates: u32,
+    /// Number of times every symbol of a packet is transmitted, the copies are averaged before demodulation.
+    ///
+    /// Has to match the setting of the modulator, see
+    /// [OFDMModulatorConfig::repetition](crate::ofdm::modulator::OFDMModulatorConfig::repetition).
+    #[default(1)]
+    pub repetition: u32,
+    /// Optional FFT implementation/planner to use.
+    ///
+    /// If `None`, the FFT of [plan_default_fft] is used.
+    /// A configured FFT has to transform `2 * num_subcarriers` samples, other lengths are rejected,
+    /// see [OFDMModulatorConfig::fft](crate::ofdm::modulator::OFDMModulatorConfig::fft).
+    /// Not (de)serialized with the `serde` feature, a deserialized configuration always uses the default planner.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub fft: Option<Arc<dyn RealToComplex<T>>>,
+}
+
+/// Quality of a received OFDM symbol, see [demodulate_with_quality](OFDMDemodulator::demodulate_with_quality).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct EstimatedQuality<T: Float = f32> {
+    /// Signal to noise ratio of the data subcarriers in dB, estimated from the error vector magnitude.
+    ///
+    /// Infinite for a symbol without any error.
+    pub snr_db: T,
+    /// Mean phase rotation of the data subcarriers after equalization in radians.
+    pub phase_error: T,
+}
+
+/// Equalization of the data subcarriers with the channel estimate of the pilots.
+///
+/// # Example
+/// ```
+/// use rand::{SeedableRng, rngs::StdRng};
+/// use software_modem::channel::add_awgn;
+/// use software_modem::ofdm::CyclicPrefix;
+/// use software_modem::ofdm::demodulator::{Equalizer, OFDMDemodulator, OFDMDemodulatorConfig};
+/// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+/// use software_modem::qam::{QAMModem, QAMOrder};
+///
+/// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+///     num_subcarriers: 64,
+///     cyclic_prefix: CyclicPrefix::Samples(16),
+///     ..Default::default()
+/// })
+/// .unwrap();
+/// let payload: Vec<u8> = (0..2400u32).map(|i| (i * 37 % 251) as u8).collect();
+/// let (samples, _) = modulator.modulate_packet(&payload).unwrap();
+///
+/// // a second path of almost the same strength notches the subcarriers close to nyquist
+/// let mut received: Vec<f32> = samples
+///     .iter()
+///     .enumerate()
+///     .map(|(n, &s)| s + 0.95 * if n > 0 { samples[n - 1] } else { 0.0 })
+///     .collect();
+/// add_awgn(&mut received, 20.0, &mut StdRng::seed_from_u64(7));
+///
+/// let transmitted = QAMModem::new(QAMOrder::QAM16).modulate(&payload);
+/// let mean_square_error = |equalizer| {
+///     let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+///         num_subcarriers: 64,
+///         cyclic_prefix: CyclicPrefix::Samples(16),
+///         equalizer,
+///         ..Default::default()
+///     })
+///     .unwrap();
+///     let points: Vec<_> = received
+///         .chunks_exact(demodulator.get_symbol_length())
+///         .flat_map(|symbol| demodulator.received_constellation(symbol))
+///         .collect();
+///     let errors = points.iter().zip(&transmitted).map(|(point, ideal)| (point - ideal).norm_sqr());
+///     errors.sum::<f32>() / points.len() as f32
+/// };
+///
+/// // the noise of the faded subcarriers no longer dominates the error
+/// let zero_forcing = mean_square_error(Equalizer::ZeroForcing);
+/// let mmse = mean_square_error(Equalizer::Mmse { noise_var: 0.01 });
+/// assert!(mmse < zero_forcing * 0.75);
+/// ```
+#[derive(Default, Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Equalizer {
+    /// Divides every subcarrier by its channel estimate.
+    ///
+    /// Removes the channel completely, but amplifies the noise on faded subcarriers.
+    #[default]
+    ZeroForcing,
+    /// Minimum mean square error equalization, weighting every subcarrier by `conj(H) / (|H|^2 + noise_var)`.
     ///
-    /// If `None`, a default FFT planner will be used.
-    pub fft: Option<Arc<dyn RealToComplex<f32>>>,
+    /// Leaves strong subcarriers almost untouched, but pulls faded ones towards zero instead of amplifying their noise.
+    /// This lowers the error of the [received constellation](OFDMDemodulator::received_constellation),
+    /// while the hard decisions of the QAM demodulation change little, as the weight only scales the points.
+    Mmse {
+        /// Noise power relative to the power of the transmitted subcarriers, the inverse of the linear SNR.
+        noise_var: f32,
+    },
 }
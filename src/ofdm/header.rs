@@ -0,0 +1,54 @@
+//! The header announcing the payload length and QAM order of a packet.
+//!
+//! The header consists of the little endian `u32` payload length, the QAM order
+//! as its number of bits per symbol and a little endian CRC-32 checksum over both:
+//!
+//! ```text
+//! | payload length (4 bytes) | qam order (1 byte) | crc-32 (4 bytes) |
+//! ```
+
+use crate::{error::HeaderError, frame::crc32, qam::QAMOrder};
+
+/// Size of the header in bytes.
+pub(super) const HEADER_LENGTH: usize = 9;
+/// Size of the payload length and the QAM order, which the checksum covers.
+const CONTENT_LENGTH: usize = 5;
+
+/// Builds the header of a packet carrying `payload_length` bytes modulated with `qam_order`.
+///
+/// # Panics
+/// If the payload is larger than `u32::MAX` bytes.
+pub(super) fn encode(payload_length: usize, qam_order: QAMOrder) -> Vec<u8> {
+    let length = u32::try_from(payload_length).expect("Payload must not exceed u32::MAX bytes");
+
+    let mut header = Vec::with_capacity(HEADER_LENGTH);
+    header.extend(length.to_le_bytes());
+    header.push(qam_order.bits_per_symbol() as u8);
+    header.extend(crc32(&header).to_le_bytes());
+
+    header
+}
+
+/// Verifies the checksum of a received header and returns the payload length and QAM order it announces.
+///
+/// Bytes after the header are ignored.
+///
+/// # Panics
+/// If `header` is shorter than [HEADER_LENGTH].
+pub(super) fn decode(header: &[u8]) -> Result<(usize, QAMOrder), HeaderError> {
+    let expected = u32::from_le_bytes(header[CONTENT_LENGTH..HEADER_LENGTH].try_into().unwrap());
+    let actual = crc32(&header[..CONTENT_LENGTH]);
+    if expected != actual {
+        return Err(HeaderError::ChecksumMismatch { expected, actual });
+    }
+
+    let qam_order = match header[4] {
+        1 => QAMOrder::BPSK,
+        2 => QAMOrder::QAM4,
+        4 => QAMOrder::QAM16,
+        value => return Err(HeaderError::UnknownQamOrder { value }),
+    };
+    let length = u32::from_le_bytes(header[..4].try_into().unwrap()) as usize;
+
+    Ok((length, qam_order))
+}
@@ -2,56 +2,564 @@
 //!
 //! The [OFDM Modulator](modulator) modulates data into OFDM symbols.
 //! And the [OFDM Demodulator](demodulator) demodulates OFDM symbols back into data.
+//! The [Streaming Demodulator](streaming) receives framed packets from a continuous stream of samples.
 
-use crate::qam::QAMOrder;
+use realfft::num_complex::{Complex, Complex32};
 
+use crate::{
+    error::ConfigError,
+    float::{Float, cast},
+    ofdm::{
+        bit_loading::BitLoading,
+        slm::{MAX_SLM_CANDIDATES, phase_sequence},
+    },
+    qam::QAMOrder,
+};
+
+mod bit_loading;
 pub mod demodulator;
+mod header;
 pub mod modulator;
+pub mod preamble;
+pub mod slm;
+pub mod streaming;
+
+/// The value transmitted on every pilot subcarrier if no pilot sequence is configured.
+pub const DEFAULT_PILOT_VALUE: Complex32 = Complex32 { re: 1.0, im: 0.0 };
+
+/// Number of symbols after which the [pilot polarity](pilot_polarity) repeats.
+pub const PILOT_POLARITY_PERIOD: usize = 127;
+
+/// Returns the polarity, `1` or `-1`, of the pilots of the symbol with the given index within a packet.
+///
+/// The sequence is the pilot polarity of IEEE 802.11: the output of the LFSR `x^7 + x^4 + 1`
+/// of the [scrambler](crate::scrambler) starting from the all-ones state, a one bit inverting the pilots.
+/// It repeats every [PILOT_POLARITY_PERIOD] symbols.
+/// See [pilot_polarity](modulator::OFDMModulatorConfig::pilot_polarity) to apply it to the pilots of a packet.
+///
+/// # Example
+/// ```
+/// use software_modem::ofdm::{PILOT_POLARITY_PERIOD, pilot_polarity};
+///
+/// let polarities: Vec<i8> = (0..16).map(pilot_polarity).collect();
+/// assert_eq!(polarities, [1, 1, 1, 1, -1, -1, -1, 1, -1, -1, -1, -1, 1, 1, -1, 1]);
+///
+/// // balanced up to the one extra inversion of a maximum length sequence
+/// let sum: i32 = (0..PILOT_POLARITY_PERIOD).map(|i| pilot_polarity(i) as i32).sum();
+/// assert_eq!(sum, -1);
+/// assert_eq!(pilot_polarity(PILOT_POLARITY_PERIOD + 4), pilot_polarity(4));
+/// ```
+pub fn pilot_polarity(symbol_index: usize) -> i8 {
+    let mut lfsr: u8 = 0x7f;
+    let mut bit = 0;
+    for _ in 0..=symbol_index % PILOT_POLARITY_PERIOD {
+        // x^7 + x^4 + 1
+        bit = ((lfsr >> 6) ^ (lfsr >> 3)) & 1;
+        lfsr = ((lfsr << 1) | bit) & 0x7f;
+    }
+
+    if bit == 0 { 1 } else { -1 }
+}
+
+/// Returns whether the FFT of `2 * num_subcarriers` samples has no prime factors other than 2, 3, 5 and 7.
+///
+/// The default planners transform such lengths with fast mixed-radix algorithms.
+/// Lengths with larger prime factors fall back to slower algorithms, e.g. Bluestein's,
+/// which take several times as long per sample, see [next_efficient_num_subcarriers] to avoid them.
+///
+/// # Example
+/// ```
+/// use software_modem::ofdm::is_efficient_num_subcarriers;
+///
+/// assert!(is_efficient_num_subcarriers(64));
+/// assert!(is_efficient_num_subcarriers(60));
+/// // 2 * 53 samples, a prime factor of 53
+/// assert!(!is_efficient_num_subcarriers(53));
+/// ```
+pub fn is_efficient_num_subcarriers(num_subcarriers: u32) -> bool {
+    let mut length = 2 * num_subcarriers as u64;
+    if length == 0 {
+        return false;
+    }
+    for factor in [2, 3, 5, 7] {
+        while length.is_multiple_of(factor) {
+            length /= factor;
+        }
+    }
+    length == 1
+}
+
+/// Returns the smallest number of subcarriers, at least `num_subcarriers`, with an efficient FFT length.
+///
+/// Round an awkward number of subcarriers up to it and null the added subcarriers
+/// with [null_subcarrier_indices](modulator::OFDMModulatorConfig::null_subcarrier_indices),
+/// which zero pads the spectrum above the requested subcarriers.
+/// With the sample rate unchanged, the subcarriers move closer together
+/// and the used ones only occupy `num_subcarriers / rounded` of the band below nyquist.
+///
+/// # Example
+/// ```
+/// use software_modem::ofdm::CyclicPrefix;
+/// use software_modem::ofdm::next_efficient_num_subcarriers;
+/// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+///
+/// assert_eq!(next_efficient_num_subcarriers(64), 64);
+/// assert_eq!(next_efficient_num_subcarriers(53), 54);
+/// assert_eq!(next_efficient_num_subcarriers(211), 216);
+///
+/// // 211 subcarriers worth of data on a fast FFT of 2 * 216 samples
+/// let num_subcarriers = next_efficient_num_subcarriers(211);
+/// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+///     num_subcarriers,
+///     cyclic_prefix: CyclicPrefix::Samples(32),
+///     null_subcarrier_indices: (211..num_subcarriers).collect(),
+///     ..Default::default()
+/// })
+/// .unwrap();
+/// assert!(modulator.is_efficient_size());
+/// ```
+pub fn next_efficient_num_subcarriers(num_subcarriers: u32) -> u32 {
+    (num_subcarriers.max(1)..)
+        .find(|&n| is_efficient_num_subcarriers(n))
+        .unwrap()
+}
+
+/// Length of the cyclic prefix in front of every OFDM symbol.
+///
+/// The length is either given in samples or as a fraction of the `2 * num_subcarriers` samples of a symbol,
+/// which is rounded to the nearest sample.
+///
+/// # Example
+/// ```
+/// use software_modem::error::ConfigError;
+/// use software_modem::ofdm::CyclicPrefix;
+/// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+///
+/// // a quarter of the 128 samples of a symbol with 64 subcarriers
+/// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+///     num_subcarriers: 64,
+///     cyclic_prefix: CyclicPrefix::Fraction(0.25),
+///     ..Default::default()
+/// })
+/// .unwrap();
+/// assert_eq!(modulator.get_symbol_length(), 128 + 32);
+///
+/// // the cyclic prefix has to be shorter than the symbol
+/// let result = OFDMModulator::new(OFDMModulatorConfig {
+///     num_subcarriers: 64,
+///     cyclic_prefix: CyclicPrefix::Fraction(1.0),
+///     ..Default::default()
+/// });
+/// assert_eq!(
+///     result.err(),
+///     Some(ConfigError::CyclicPrefixFractionOutOfRange { fraction: 1.0 })
+/// );
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CyclicPrefix {
+    /// A fixed number of samples.
+    Samples(u32),
+    /// A fraction of the symbol length, at least `0.0` and below `1.0`.
+    Fraction(f32),
+}
+
+impl Default for CyclicPrefix {
+    fn default() -> Self {
+        CyclicPrefix::Samples(0)
+    }
+}
+
+/// Order in which the QAM symbols of an OFDM symbol are laid onto the data subcarriers.
+///
+/// The first QAM symbol, i.e. the first bits of the symbol, goes to the first subcarrier of the order.
+/// Data subcarriers which are not covered by a whole byte stay empty, so the order also decides which ones those are.
+/// The [bit loading](modulator::OFDMModulatorConfig::bit_loading) stays in ascending subcarrier order,
+/// every subcarrier keeps its QAM order wherever it falls in the mapping.
+/// The demodulator has to be configured with the same mapping.
+///
+/// # Example
+/// ```
+/// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+/// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+/// use software_modem::ofdm::{CyclicPrefix, SubcarrierMapping};
+///
+/// let demodulator = |subcarrier_mapping: SubcarrierMapping| {
+///     OFDMDemodulator::new(OFDMDemodulatorConfig {
+///         num_subcarriers: 16,
+///         cyclic_prefix: CyclicPrefix::Samples(4),
+///         subcarrier_mapping,
+///         ..Default::default()
+///     })
+///     .unwrap()
+/// };
+/// let linear = demodulator(SubcarrierMapping::Linear);
+/// assert_eq!(linear.data_subcarrier_indices(), [1, 2, 3, 5, 6, 7, 9, 10, 11, 13, 14, 15]);
+/// let center_out = demodulator(SubcarrierMapping::CenterOut);
+/// assert_eq!(center_out.data_subcarrier_indices(), [7, 9, 6, 10, 5, 11, 3, 13, 2, 14, 1, 15]);
+///
+/// // a custom order, e.g. to spread neighbouring symbols over the band
+/// let custom = SubcarrierMapping::Custom(vec![1, 9, 2, 10, 3, 11, 5, 13, 6, 14, 7, 15]);
+/// let modulator = |subcarrier_mapping: SubcarrierMapping| {
+///     OFDMModulator::new(OFDMModulatorConfig {
+///         num_subcarriers: 16,
+///         cyclic_prefix: CyclicPrefix::Samples(4),
+///         subcarrier_mapping,
+///         ..Default::default()
+///     })
+///     .unwrap()
+/// };
+///
+/// let data = b"Mapped";
+/// let transmit = |modulator: OFDMModulator| {
+///     let mut symbol = vec![0.0; modulator.get_symbol_length()];
+///     modulator.modulate_buffer_as_symbol(data, &mut symbol).unwrap();
+///     symbol
+/// };
+/// let linear_symbol = transmit(modulator(SubcarrierMapping::Linear));
+/// let custom_symbol = transmit(modulator(custom.clone()));
+///
+/// // the same data round trips with either mapping, but occupies the subcarriers differently
+/// let custom_demodulator = demodulator(custom);
+/// assert_eq!(custom_demodulator.demodulate_symbol_from_buffer(&custom_symbol), data);
+/// assert_eq!(linear.demodulate_symbol_from_buffer(&linear_symbol), data);
+/// assert_ne!(linear.demodulate_symbol_from_buffer(&custom_symbol), data);
+///
+/// let linear_spectrum = linear.extract_subcarriers(&linear_symbol);
+/// let custom_spectrum = custom_demodulator.extract_subcarriers(&custom_symbol);
+/// assert!((linear_spectrum[2] - custom_spectrum[2]).norm() > 1.0);
+/// // the second QAM symbol moved from subcarrier 2 to subcarrier 9
+/// assert!((linear_spectrum[2] - custom_spectrum[9]).norm() < 1e-3);
+///
+/// // a custom mapping has to list every data subcarrier once
+/// let result = OFDMModulator::new(OFDMModulatorConfig {
+///     num_subcarriers: 16,
+///     cyclic_prefix: CyclicPrefix::Samples(4),
+///     subcarrier_mapping: SubcarrierMapping::Custom(vec![1, 2, 3]),
+///     ..Default::default()
+/// });
+/// assert!(result.is_err());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SubcarrierMapping {
+    /// Ascending subcarrier index, from the lowest to the highest data subcarrier.
+    #[default]
+    Linear,
+    /// From the middle of the data subcarriers outwards, alternating between the lower and the upper side.
+    ///
+    /// Short symbols stay in the center of the band, away from the edges where filters attenuate most.
+    CenterOut,
+    /// The listed data subcarrier indices in order.
+    ///
+    /// Has to hold every data subcarrier exactly once,
+    /// i.e. be a permutation of the [linear](SubcarrierMapping::Linear) order.
+    Custom(Vec<u32>),
+}
+
+impl SubcarrierMapping {
+    /// Returns the positions within the ascending `data_subcarrier_indices` in the order of the mapping.
+    fn order(&self, data_subcarrier_indices: &[u32]) -> Result<Vec<usize>, ConfigError> {
+        let mut order: Vec<usize> = (0..data_subcarrier_indices.len()).collect();
+        match self {
+            SubcarrierMapping::Linear => {}
+            SubcarrierMapping::CenterOut => {
+                if let (Some(&lowest), Some(&highest)) = (
+                    data_subcarrier_indices.first(),
+                    data_subcarrier_indices.last(),
+                ) {
+                    // twice the distance to the middle, the stable sort puts the lower side first on ties
+                    let center = (lowest + highest) as i64;
+                    order.sort_by_key(|&i| (2 * data_subcarrier_indices[i] as i64 - center).abs());
+                }
+            }
+            SubcarrierMapping::Custom(indices) => {
+                let positions: Vec<Option<usize>> = indices
+                    .iter()
+                    .map(|index| data_subcarrier_indices.binary_search(index).ok())
+                    .collect();
+                let mut sorted = positions.clone();
+                sorted.sort_unstable();
+                sorted.dedup();
+                if indices.len() != data_subcarrier_indices.len()
+                    || sorted.len() != indices.len()
+                    || sorted.contains(&None)
+                {
+                    return Err(ConfigError::InvalidSubcarrierMapping {
+                        num_data_subcarriers: data_subcarrier_indices.len(),
+                    });
+                }
+                order = positions.into_iter().flatten().collect();
+            }
+        }
+        Ok(order)
+    }
+}
+
+impl CyclicPrefix {
+    /// Resolves the cyclic prefix into samples for a symbol of `symbol_length` samples.
+    fn num_samples(self, symbol_length: u32) -> Result<u32, ConfigError> {
+        match self {
+            CyclicPrefix::Samples(num_samples) => Ok(num_samples),
+            CyclicPrefix::Fraction(fraction) if (0.0..1.0).contains(&fraction) => {
+                Ok((fraction as f64 * symbol_length as f64).round() as u32)
+            }
+            CyclicPrefix::Fraction(fraction) => {
+                Err(ConfigError::CyclicPrefixFractionOutOfRange { fraction })
+            }
+        }
+    }
+}
 
 #[allow(dead_code)]
-struct OFDMConstants {
+struct OFDMConstants<T: Float> {
     num_data_subcarriers: u32,
     num_pilot_subcarriers: u32,
     qam_order: QAMOrder,
     num_subcarriers: u32,
     cyclic_prefix_length: u32,
+    window_length: u32,
 
+    /// Subcarriers carrying data, all of them between DC and nyquist, i.e. in `1..num_subcarriers`,
+    /// in the order of the [SubcarrierMapping].
     data_subcarrier_indices: Vec<u32>,
+    /// Subcarriers carrying pilots, in `1..num_subcarriers` like the data subcarriers.
     pilot_subcarrier_indices: Vec<u32>,
+    pilot_values: Vec<Complex<T>>,
+    /// Whether the pilots of the symbols of a packet are inverted by the [pilot_polarity] sequence.
+    pilot_polarity: bool,
 
-    bits_per_subcarrier: u32,
+    /// Subcarriers carrying the index of the selective mapping candidate, most significant bit first.
+    slm_index_subcarrier_indices: Vec<u32>,
+    /// Phase rotation of the data subcarriers for every selective mapping candidate.
+    slm_phases: Vec<Vec<Complex<T>>>,
+
+    /// QAM modems of the data subcarriers.
+    bit_loading: BitLoading<T>,
     bits_per_symbol: u32,
+    /// BPSK modems of the data subcarriers for the header symbols.
+    header_loading: BitLoading<T>,
+
+    /// Number of times every symbol of a packet is transmitted.
+    repetition: u32,
+}
+/// Checks that a configured FFT transforms the `2 * num_subcarriers` samples of a symbol.
+fn check_fft_length(fft_length: usize, num_subcarriers: u32) -> Result<(), ConfigError> {
+    let expected = 2 * num_subcarriers as usize;
+    if fft_length != expected {
+        return Err(ConfigError::FftLengthMismatch {
+            expected,
+            actual: fft_length,
+        });
+    }
+    Ok(())
 }
-impl OFDMConstants {
+
+impl<T: Float> OFDMConstants<T> {
+    #[allow(clippy::too_many_arguments)]
     fn new(
         num_subcarriers: u32,
         pilot_subcarrier_every: u32,
-        cyclic_prefix_length: u32,
+        pilot_subcarrier_indices: &[u32],
+        null_subcarrier_indices: &[u32],
+        spectral_mask: &[(u32, u32)],
+        subcarrier_mapping: &SubcarrierMapping,
+        cyclic_prefix: CyclicPrefix,
+        window_length: u32,
         qam_order: QAMOrder,
-        bits_per_subcarrier: u32,
-    ) -> Self {
-        let pilot_subcarrier_indices: Vec<u32> = (1..num_subcarriers)
-            .filter(|&i| i % pilot_subcarrier_every == 0)
+        bit_loading: &[QAMOrder],
+        pilot_sequence: &[Complex<T>],
+        pilot_polarity: bool,
+        num_slm_candidates: u32,
+        repetition: u32,
+    ) -> Result<Self, ConfigError> {
+        if num_subcarriers == 0 {
+            return Err(ConfigError::NoSubcarriers);
+        }
+        let cyclic_prefix_length = cyclic_prefix.num_samples(2 * num_subcarriers)?;
+        if cyclic_prefix_length >= 2 * num_subcarriers {
+            return Err(ConfigError::CyclicPrefixTooLong {
+                cyclic_prefix_length,
+                symbol_length: 2 * num_subcarriers,
+            });
+        }
+        // the interval only places the pilots if they are not listed explicitly
+        if pilot_subcarrier_indices.is_empty() {
+            if pilot_subcarrier_every < 2 {
+                return Err(ConfigError::PilotSpacingTooSmall {
+                    pilot_subcarrier_every,
+                });
+            }
+            // the real FFT only takes the bins up to nyquist, a pilot beyond it would be dropped
+            if pilot_subcarrier_every >= num_subcarriers {
+                return Err(ConfigError::PilotSpacingTooLarge {
+                    pilot_subcarrier_every,
+                    num_subcarriers,
+                });
+            }
+        }
+        if let Some(&index) = pilot_subcarrier_indices
+            .iter()
+            .find(|&&index| index == 0 || index >= num_subcarriers)
+        {
+            return Err(ConfigError::PilotSubcarrierOutOfRange {
+                index,
+                num_subcarriers,
+            });
+        }
+        if window_length > cyclic_prefix_length {
+            return Err(ConfigError::WindowTooLong {
+                window_length,
+                cyclic_prefix_length,
+            });
+        }
+
+        if num_slm_candidates == 0 || num_slm_candidates > MAX_SLM_CANDIDATES {
+            return Err(ConfigError::SlmCandidatesOutOfRange { num_slm_candidates });
+        }
+        if repetition == 0 {
+            return Err(ConfigError::NoRepetitions);
+        }
+
+        if let Some(&index) = null_subcarrier_indices
+            .iter()
+            .find(|&&index| index > num_subcarriers)
+        {
+            return Err(ConfigError::NullSubcarrierOutOfRange {
+                index,
+                num_subcarriers,
+            });
+        }
+
+        if let Some(&(first, last)) = spectral_mask
+            .iter()
+            .find(|&&(first, last)| first > last || last > num_subcarriers)
+        {
+            return Err(ConfigError::SpectralMaskOutOfRange {
+                first,
+                last,
+                num_subcarriers,
+            });
+        }
+
+        // DC and nyquist are never used, the remaining subcarriers are pilots or data unless nulled or masked
+        let active_subcarriers = (1..num_subcarriers).filter(|i| {
+            !null_subcarrier_indices.contains(i)
+                && !spectral_mask
+                    .iter()
+                    .any(|&(first, last)| (first..=last).contains(i))
+        });
+
+        let is_pilot = |i: u32| {
+            if pilot_subcarrier_indices.is_empty() {
+                i.is_multiple_of(pilot_subcarrier_every)
+            } else {
+                pilot_subcarrier_indices.contains(&i)
+            }
+        };
+        let pilot_subcarrier_indices: Vec<u32> = active_subcarriers
+            .clone()
+            .filter(|&i| is_pilot(i))
             .collect();
         let num_pilot_subcarriers = pilot_subcarrier_indices.len() as u32;
 
-        let data_subcarrier_indices: Vec<u32> = (1..num_subcarriers)
-            .filter(|&i| i % pilot_subcarrier_every != 0)
+        let pilot_values = if pilot_sequence.is_empty() {
+            let pilot = Complex::new(
+                cast(DEFAULT_PILOT_VALUE.re as f64),
+                cast(DEFAULT_PILOT_VALUE.im as f64),
+            );
+            vec![pilot; num_pilot_subcarriers as usize]
+        } else if pilot_sequence.len() < num_pilot_subcarriers as usize {
+            return Err(ConfigError::PilotSequenceTooShort {
+                expected: num_pilot_subcarriers as usize,
+                actual: pilot_sequence.len(),
+            });
+        } else {
+            pilot_sequence[..num_pilot_subcarriers as usize].to_vec()
+        };
+
+        let mut data_subcarrier_indices: Vec<u32> =
+            active_subcarriers.filter(|&i| !is_pilot(i)).collect();
+
+        // the lowest data subcarriers are reserved for the candidate index, one BPSK bit each
+        let num_index_bits = (u32::BITS - (num_slm_candidates - 1).leading_zeros()) as usize;
+        let slm_index_subcarrier_indices = data_subcarrier_indices
+            .drain(..num_index_bits.min(data_subcarrier_indices.len()))
+            .collect();
+        let slm_phases = (0..num_slm_candidates)
+            .map(|candidate| phase_sequence(candidate, data_subcarrier_indices.len()))
             .collect();
+
         let num_data_subcarriers = data_subcarrier_indices.len() as u32;
 
-        let bits_per_symbol = num_data_subcarriers * bits_per_subcarrier;
+        if !bit_loading.is_empty() && bit_loading.len() != num_data_subcarriers as usize {
+            return Err(ConfigError::BitLoadingLengthMismatch {
+                expected: num_data_subcarriers as usize,
+                actual: bit_loading.len(),
+            });
+        }
+
+        // the data subcarriers and their QAM orders follow the mapping, the bit loading table stays ascending
+        let order = subcarrier_mapping.order(&data_subcarrier_indices)?;
+        let data_subcarrier_indices: Vec<u32> =
+            order.iter().map(|&i| data_subcarrier_indices[i]).collect();
+        let bit_loading = if bit_loading.is_empty() {
+            BitLoading::new(&vec![qam_order; num_data_subcarriers as usize])
+        } else {
+            BitLoading::new(&order.iter().map(|&i| bit_loading[i]).collect::<Vec<_>>())
+        };
+        let bits_per_symbol = bit_loading.bits_per_symbol();
+        if bits_per_symbol < 8 {
+            return Err(ConfigError::NoPayloadCapacity { bits_per_symbol });
+        }
+        let header_loading = BitLoading::new(&vec![QAMOrder::BPSK; num_data_subcarriers as usize]);
 
-        OFDMConstants {
+        Ok(OFDMConstants {
             num_data_subcarriers,
             num_pilot_subcarriers,
             qam_order,
             num_subcarriers,
             cyclic_prefix_length,
+            window_length,
             data_subcarrier_indices,
             pilot_subcarrier_indices,
-            bits_per_subcarrier,
+            pilot_values,
+            pilot_polarity,
+            slm_index_subcarrier_indices,
+            slm_phases,
+            bit_loading,
             bits_per_symbol,
+            header_loading,
+            repetition,
+        })
+    }
+
+    /// Number of whole payload bytes one OFDM symbol carries.
+    fn bytes_per_symbol(&self) -> usize {
+        (self.bits_per_symbol / 8) as usize
+    }
+
+    /// Length of one OFDM symbol in samples, including the cyclic prefix.
+    fn symbol_length(&self) -> usize {
+        (2 * self.num_subcarriers + self.cyclic_prefix_length) as usize
+    }
+
+    /// Number of BPSK symbols the header of a packet takes, one bit per data subcarrier.
+    fn num_header_symbols(&self) -> usize {
+        (8 * header::HEADER_LENGTH).div_ceil(self.num_data_subcarriers as usize)
+    }
+
+    /// Factor of the pilots of the symbol with the given index within a packet.
+    fn pilot_sign(&self, symbol_index: usize) -> T {
+        if self.pilot_polarity && pilot_polarity(symbol_index) < 0 {
+            -T::one()
+        } else {
+            T::one()
         }
     }
+
+    /// Number of samples at the start of the cyclic prefix which overlap with the previous symbol.
+    fn window_length(&self) -> usize {
+        self.window_length as usize
+    }
 }
@@ -1,50 +1,239 @@
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
 
-use realfft::{ComplexToReal, num_complex::Complex32};
+use realfft::{ComplexToReal, RealFftPlanner, num_complex::Complex};
+use rustfft::{Fft, FftPlanner};
 use smart_default::SmartDefault;
 
 use crate::{
-    ofdm::OFDMConstants,
-    qam::{QAMModem, QAMOrder},
+    bits::BitReader,
+    dsp::{clip_peaks, quantize},
+    error::{ConfigError, ModulationError},
+    fec,
+    float::{Float, cast},
+    frame,
+    interleaver::Interleaver,
+    ofdm::{
+        CyclicPrefix, OFDMConstants, SubcarrierMapping, check_fft_length, header,
+        is_efficient_num_subcarriers,
+        preamble::{long_training_spectrum, preamble_spectrum},
+    },
+    qam::QAMOrder,
+    scrambler,
 };
 
-const PILOT_VALUE_TO_BE_CHANGED: Complex32 = Complex32 { re: 1.0, im: 0.0 };
-
 /// OFDM Modulator
 ///
 /// With this modulator, you can modulate data into OFDM symbols.
 /// It supports QAM modulation and allows for pilot subcarriers.
 /// The modulator can be configured with the number of subcarriers, cyclic prefix length,
 /// pilot subcarrier interval, and QAM order.
-pub struct OFDMModulator {
-    fft: Arc<dyn ComplexToReal<f32>>,
-    qam_modem: QAMModem,
-    constants: OFDMConstants,
+///
+/// The samples are `f32` by default, see [from_config](OFDMModulator::from_config) for other [float types](Float).
+///
+/// The modulator keeps the buffers for the FFT between calls, so modulating a symbol does not allocate.
+/// The buffers are guarded by a mutex, which keeps the modulator `Send` and `Sync`,
+/// but calls from several threads on the same modulator wait for each other.
+/// Create one modulator per thread to modulate in parallel.
+/// With the `rayon` feature, [modulate_packet](OFDMModulator::modulate_packet) modulates the symbols
+/// of a packet in parallel itself, using separate buffers per worker thread.
+pub struct OFDMModulator<T: Float = f32> {
+    fft: Arc<dyn ComplexToReal<T>>,
+    /// Complex inverse FFT of the [I/Q mode](OFDMModulator::modulate_iq).
+    iq_fft: Arc<dyn Fft<T>>,
+    constants: OFDMConstants<T>,
+    convolutional_coding: bool,
+    #[cfg(feature = "rs")]
+    reed_solomon: bool,
+    scrambling: bool,
+    interleaver: Option<Interleaver>,
+    clipping_ratio: Option<T>,
+    quantization_bits: Option<u32>,
+    /// Factor of [normalize_output](OFDMModulatorConfig::normalize_output).
+    output_scale: Option<T>,
+    trailing_gap: usize,
+    scratch: Mutex<Scratch<T>>,
+}
+
+/// Buffers reused between symbols.
+struct Scratch<T: Float> {
+    qam_symbols: Vec<Complex<T>>,
+    spectrum: Vec<Complex<T>>,
+    time: Vec<T>,
+    fft: Vec<Complex<T>>,
+    /// One symbol including the fade out of its window.
+    symbol: Vec<T>,
+    /// The selective mapping candidate with the lowest peak so far.
+    best_symbol: Vec<T>,
+}
+
+impl<T: Float> Scratch<T> {
+    /// Allocates the buffers for symbols of the given FFT and constants.
+    fn new(fft: &dyn ComplexToReal<T>, constants: &OFDMConstants<T>) -> Self {
+        Scratch {
+            qam_symbols: Vec::with_capacity(constants.num_data_subcarriers as usize),
+            spectrum: fft.make_input_vec(),
+            time: fft.make_output_vec(),
+            fft: fft.make_scratch_vec(),
+            symbol: vec![T::zero(); constants.symbol_length() + constants.window_length()],
+            best_symbol: vec![T::zero(); constants.symbol_length() + constants.window_length()],
+        }
+    }
 }
 
-impl OFDMModulator {
+impl OFDMModulator<f32> {
     /// Creates a new OFDM modulator with the given [configuration](OFDMModulatorConfig).
-    pub fn new(config: OFDMModulatorConfig) -> Self {
-        let qam_modem = QAMModem::new(config.qam_order);
-
-        let constants = OFDMConstants::new(
-            config.num_subcarriers,
-            config.pilot_subcarrier_every,
-            config.cyclic_prefix_length,
-            config.qam_order,
-            qam_modem.bits_per_symbol(),
-        );
+    ///
+    /// # Errors
+    /// - [ConfigError::NoSubcarriers] if `num_subcarriers` is zero.
+    /// - [ConfigError::CyclicPrefixTooLong] if the cyclic prefix is not shorter than `2 * num_subcarriers` samples.
+    /// - [ConfigError::CyclicPrefixFractionOutOfRange] if the cyclic prefix is given as a fraction
+    ///   which is not at least `0.0` and below `1.0`.
+    /// - [ConfigError::PilotSpacingTooSmall] if `pilot_subcarrier_every` is below 2
+    ///   and no `pilot_subcarrier_indices` are configured.
+    /// - [ConfigError::PilotSpacingTooLarge] if `pilot_subcarrier_every` is not below `num_subcarriers`,
+    ///   so no pilot lies between DC and nyquist, and no `pilot_subcarrier_indices` are configured.
+    /// - [ConfigError::PilotSubcarrierOutOfRange] if a pilot subcarrier index is not between DC and nyquist.
+    /// - [ConfigError::NullSubcarrierOutOfRange] if a null subcarrier index exceeds `num_subcarriers`.
+    /// - [ConfigError::SpectralMaskOutOfRange] if a range of the spectral mask is reversed or exceeds `num_subcarriers`.
+    /// - [ConfigError::PilotSequenceTooShort] if a pilot sequence is configured
+    ///   which holds fewer values than there are pilot subcarriers.
+    /// - [ConfigError::WindowTooLong] if `window_length` exceeds the length of the cyclic prefix.
+    /// - [ConfigError::SlmCandidatesOutOfRange] if `num_slm_candidates` is zero or exceeds
+    ///   [MAX_SLM_CANDIDATES](crate::ofdm::slm::MAX_SLM_CANDIDATES).
+    /// - [ConfigError::NoRepetitions] if `repetition` is zero.
+    /// - [ConfigError::QuantizationBitsOutOfRange] if `quantization_bits` is zero or exceeds 32.
+    /// - [ConfigError::BitLoadingLengthMismatch] if a bit loading table is configured
+    ///   which does not hold one QAM order per data subcarrier.
+    /// - [ConfigError::InvalidSubcarrierMapping] if a custom subcarrier mapping does not list every data subcarrier once.
+    /// - [ConfigError::NoPayloadCapacity] if the data subcarriers of a symbol carry less than one byte,
+    ///   e.g. because all of them are nulled.
+    /// - [ConfigError::FftLengthMismatch] if an FFT is configured which does not transform `2 * num_subcarriers` samples.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::error::ConfigError;
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    /// use realfft::num_complex::Complex32;
+    ///
+    /// // 64 subcarriers with a pilot every 4th subcarrier results in 15 pilot subcarriers
+    /// let bpsk_pilots = (0..15)
+    ///     .map(|i| Complex32::new(if i % 2 == 0 { 1.0 } else { -1.0 }, 0.0))
+    ///     .collect();
+    /// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(4),
+    ///     pilot_sequence: bpsk_pilots,
+    ///     ..Default::default()
+    /// });
+    /// assert!(modulator.is_ok());
+    ///
+    /// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(4),
+    ///     pilot_sequence: vec![Complex32::new(1.0, 0.0); 4],
+    ///     ..Default::default()
+    /// });
+    /// assert_eq!(
+    ///     modulator.err(),
+    ///     Some(ConfigError::PilotSequenceTooShort { expected: 15, actual: 4 })
+    /// );
+    ///
+    /// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(4),
+    ///     window_length: 8,
+    ///     ..Default::default()
+    /// });
+    /// assert_eq!(
+    ///     modulator.err(),
+    ///     Some(ConfigError::WindowTooLong { window_length: 8, cyclic_prefix_length: 4 })
+    /// );
+    ///
+    /// // with every subcarrier nulled, no byte fits into a symbol
+    /// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(4),
+    ///     null_subcarrier_indices: (1..64).collect(),
+    ///     ..Default::default()
+    /// });
+    /// assert_eq!(
+    ///     modulator.err(),
+    ///     Some(ConfigError::NoPayloadCapacity { bits_per_symbol: 0 })
+    /// );
+    /// ```
+    pub fn new(config: OFDMModulatorConfig) -> Result<Self, ConfigError> {
+        Self::from_config(config)
+    }
+}
+
+impl<T: Float> OFDMModulator<T> {
+    /// Creates a new OFDM modulator with the given [configuration](OFDMModulatorConfig), working with the float type `T`.
+    ///
+    /// The default FFT is planned for `T` as well.
+    ///
+    /// # Errors
+    /// See [new](OFDMModulator::new).
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    ///
+    /// let modulator = OFDMModulator::<f64>::from_config(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(4),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    /// let demodulator = OFDMDemodulator::<f64>::from_config(OFDMDemodulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(4),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    ///
+    /// let payload = b"Double precision round trip";
+    /// let (samples, padding) = modulator.modulate_packet(payload).unwrap();
+    /// let _: &[f64] = &samples;
+    ///
+    /// assert_eq!(demodulator.demodulate_packet(&samples, padding), payload);
+    /// ```
+    pub fn from_config(config: OFDMModulatorConfig<T>) -> Result<Self, ConfigError> {
+        let constants = config.constants()?;
+
+        let fft = config
+            .fft
+            .unwrap_or_else(|| plan_default_fft(config.num_subcarriers));
+
+        let iq_fft = FftPlanner::<T>::new().plan_fft_inverse(2 * config.num_subcarriers as usize);
+
+        let scratch = Scratch::new(fft.as_ref(), &constants);
 
-        let fft = config.fft.unwrap_or_else(|| {
-            realfft::RealFftPlanner::<f32>::new()
-                .plan_fft_inverse(2 * config.num_subcarriers as usize)
-        });
+        let interleaver = config
+            .interleaving
+            .then(|| Interleaver::new(constants.bytes_per_symbol()));
 
-        OFDMModulator {
+        let output_scale = config
+            .normalize_output
+            .then(|| T::one() / mean_symbol_power(&constants).sqrt());
+
+        Ok(OFDMModulator {
             fft,
-            qam_modem,
+            iq_fft,
             constants,
-        }
+            convolutional_coding: config.convolutional_coding,
+            #[cfg(feature = "rs")]
+            reed_solomon: config.reed_solomon,
+            scrambling: config.scrambling,
+            interleaver,
+            clipping_ratio: config.clipping_ratio.map(|ratio| cast(ratio as f64)),
+            quantization_bits: config.quantization_bits,
+            output_scale,
+            trailing_gap: config.trailing_gap as usize,
+            scratch: Mutex::new(scratch),
+        })
     }
 
     /// Modulates the given data buffer into an OFDM symbol.
@@ -55,9 +244,15 @@ impl OFDMModulator {
     /// The length of the output buffer must be double the total length of the OFDM symbol plus the cyclic prefix length.
     /// You can calculate the expected length of the output buffer using `get_symbol_length()`.
     ///
-    /// # Panics
-    /// If the data length does not match the expected length,
-    /// which is `bits_per_symbol / 8`.
+    /// If a `window_length` is configured, the symbol fades in over the first `window_length` samples.
+    /// The matching fade out overlaps with the next symbol and is only produced by
+    /// [modulate_packet](OFDMModulator::modulate_packet).
+    ///
+    /// # Errors
+    /// - [ModulationError::DataLengthMismatch] if the data length does not match the expected length,
+    ///   which is `bits_per_symbol / 8`.
+    /// - [ModulationError::OutputBufferLengthMismatch] if the output buffer length does not match `get_symbol_length()`.
+    /// - [ModulationError::Fft] if a configured FFT rejects its buffers.
     ///
     /// # Arguments
     /// - `data` - A slice of bytes to be modulated.
@@ -65,96 +260,2475 @@ impl OFDMModulator {
     ///
     /// # Example
     /// ```
+    /// use software_modem::error::ModulationError;
+    /// use software_modem::ofdm::CyclicPrefix;
     /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
     /// use software_modem::qam::QAMOrder;
     ///
     /// let ofdm_modulator = OFDMModulator::new(OFDMModulatorConfig {
     ///   num_subcarriers: 64,
-    ///   cyclic_prefix_length: 4,
+    ///   cyclic_prefix: CyclicPrefix::Samples(4),
     ///   pilot_subcarrier_every: 4,
     ///   qam_order: QAMOrder::QAM16,
-    ///  fft: None,
-    /// });
+    ///   ..Default::default()
+    /// }).unwrap();
     ///
     /// let mut output_buffer = vec![0.0; ofdm_modulator.get_symbol_length()];
     /// let mut data_buffer = "Hello, OFDM!            ".as_bytes(); // 32 bytes for QAM16 * 64 Subcarriers minus 6 pilot subcarriers and first and last subcarrier
     ///
-    /// ofdm_modulator.modulate_buffer_as_symbol(&data_buffer, &mut output_buffer);
+    /// ofdm_modulator.modulate_buffer_as_symbol(&data_buffer, &mut output_buffer).unwrap();
+    ///
+    /// // a buffer of the wrong size is rejected
+    /// assert_eq!(
+    ///     ofdm_modulator.modulate_buffer_as_symbol(b"Hello", &mut output_buffer),
+    ///     Err(ModulationError::DataLengthMismatch { expected: 24, actual: 5 })
+    /// );
+    /// assert_eq!(
+    ///     ofdm_modulator.modulate_buffer_as_symbol(&data_buffer, &mut output_buffer[..100]),
+    ///     Err(ModulationError::OutputBufferLengthMismatch { expected: 132, actual: 100 })
+    /// );
+    /// ```
+    pub fn modulate_buffer_as_symbol(
+        &self,
+        data: &[u8],
+        output_buffer: &mut [T],
+    ) -> Result<(), ModulationError> {
+        if output_buffer.len() != self.get_symbol_length() {
+            return Err(ModulationError::OutputBufferLengthMismatch {
+                expected: self.get_symbol_length(),
+                actual: output_buffer.len(),
+            });
+        }
+
+        let mut scratch = self.lock_scratch();
+        self.modulate_windowed_symbol(data, 0, &mut scratch)?;
+
+        output_buffer.copy_from_slice(&scratch.symbol[..self.get_symbol_length()]);
+        Ok(())
+    }
+
+    /// Modulates the data into an OFDM symbol like [modulate_buffer_as_symbol](OFDMModulator::modulate_buffer_as_symbol),
+    /// but returns only the `2 * num_subcarriers` samples of the inverse FFT, without the cyclic prefix.
+    ///
+    /// The cyclic prefix is a copy of the end of these samples, see [CyclicPrefix].
+    /// A forward FFT of them gives back the subcarriers exactly, e.g. for analyses or custom framing.
+    /// No window is applied, the [window_length](OFDMModulatorConfig::window_length) only tapers the cyclic prefix.
+    ///
+    /// # Errors
+    /// - [ModulationError::DataLengthMismatch] if the data length does not match the expected length,
+    ///   which is `bits_per_symbol / 8`.
+    /// - [ModulationError::Fft] if a configured FFT rejects its buffers.
+    ///
+    /// # Example
+    /// ```
+    /// use realfft::RealFftPlanner;
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    ///
+    /// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(16),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    /// let data: Vec<u8> = (0..modulator.bytes_per_symbol() as u8).collect();
+    ///
+    /// let mut symbol = vec![0.0; modulator.get_symbol_length()];
+    /// modulator.modulate_buffer_as_symbol(&data, &mut symbol).unwrap();
+    /// let mut body = modulator.modulate_symbol_no_cp(&data).unwrap();
+    /// assert_eq!(body.len(), 128);
+    ///
+    /// // the cyclic prefix is the tail of the symbol body
+    /// assert_eq!(symbol[..16], body[128 - 16..]);
+    /// assert_eq!(symbol[16..], body);
+    ///
+    /// // the unscaled transforms multiply the pilot value of 1 by the 128 samples
+    /// let fft = RealFftPlanner::<f32>::new().plan_fft_forward(128);
+    /// let mut spectrum = fft.make_output_vec();
+    /// fft.process(&mut body, &mut spectrum).unwrap();
+    /// assert!((spectrum[4].re - 128.0).abs() < 1e-3 && spectrum[4].im.abs() < 1e-3);
+    /// ```
+    pub fn modulate_symbol_no_cp(&self, data: &[u8]) -> Result<Vec<T>, ModulationError> {
+        let mut scratch = self.lock_scratch();
+        self.modulate_windowed_symbol(data, 0, &mut scratch)?;
+
+        let cyclic_prefix_length = self.constants.cyclic_prefix_length as usize;
+        let num_samples = 2 * self.constants.num_subcarriers as usize;
+        Ok(scratch.symbol[cyclic_prefix_length..cyclic_prefix_length + num_samples].to_vec())
+    }
+
+    /// Modulates the symbol with the given index within a packet into `scratch.symbol`,
+    /// including the fade out of its window.
+    fn modulate_windowed_symbol(
+        &self,
+        data: &[u8],
+        symbol_index: usize,
+        scratch: &mut Scratch<T>,
+    ) -> Result<(), ModulationError> {
+        let expected = self.constants.bytes_per_symbol();
+        if data.len() != expected {
+            return Err(ModulationError::DataLengthMismatch {
+                expected,
+                actual: data.len(),
+            });
+        }
+
+        self.constants
+            .bit_loading
+            .modulate_into(data, &mut scratch.qam_symbols);
+
+        self.modulate_ofdm_symbol(symbol_index, scratch)
+    }
+
+    /// Modulates a payload of arbitrary length into a sequence of OFDM symbols.
+    ///
+    /// If `convolutional_coding` is enabled, the payload is [encoded](crate::fec::encode) first,
+    /// after the [Reed-Solomon code](crate::fec::reed_solomon) if `reed_solomon` is enabled as well.
+    /// The payload is split into chunks of `bits_per_symbol / 8` bytes,
+    /// the last chunk is padded with zeros, and the modulated symbols are concatenated.
+    /// If `scrambling` is enabled, the padded data is [scrambled](crate::scrambler::scramble) before modulation.
+    /// If `interleaving` is enabled, the bits of every chunk are [interleaved](crate::interleaver) across the subcarriers.
+    /// With a [repetition](OFDMModulatorConfig::repetition) above 1, every symbol is repeated as often.
+    /// With [pilot_polarity](OFDMModulatorConfig::pilot_polarity), the pilots of every symbol are inverted
+    /// according to its index in the packet.
+    /// An empty payload results in an empty output, without `window_length` or `trailing_gap`.
+    ///
+    /// If a `window_length` is configured, the fade out of every symbol is added onto the start of the next one
+    /// and the fade out of the last symbol is appended, so the output is `window_length` samples longer.
+    /// If a `clipping_ratio` is configured, the output is [clipped](crate::dsp::clip_peaks),
+    /// and with `quantization_bits` it is [quantized](crate::dsp::quantize) after that,
+    /// after which the silence of the [trailing_gap](OFDMModulatorConfig::trailing_gap) is appended.
+    ///
+    /// With the `rayon` feature, the symbols are modulated in parallel on the rayon thread pool,
+    /// which produces exactly the same samples as modulating them one after the other.
+    ///
+    /// Returns the time domain samples and the number of padding bytes appended to the payload,
+    /// which the demodulator needs to [trim the padding](crate::ofdm::demodulator::OFDMDemodulator::demodulate_packet).
+    ///
+    /// # Errors
+    /// Returns [ModulationError::Fft] if a configured FFT rejects its buffers.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    ///
+    /// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(4),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    /// let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(4),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    ///
+    /// // 200 bytes need 9 symbols of 24 bytes each
+    /// let payload: Vec<u8> = (0..200).map(|i| (i * 7) as u8).collect();
+    /// let (samples, padding) = modulator.modulate_packet(&payload).unwrap();
+    ///
+    /// assert_eq!(padding, 16);
+    /// assert_eq!(samples.len(), 9 * modulator.get_symbol_length());
+    /// assert_eq!(demodulator.demodulate_packet(&samples, padding), payload);
+    ///
+    /// // an empty payload takes no symbols
+    /// let (samples, padding) = modulator.modulate_packet(&[]).unwrap();
+    /// assert!(samples.is_empty());
+    /// assert_eq!(padding, 0);
+    /// assert!(demodulator.demodulate_packet(&samples, padding).is_empty());
+    /// ```
+    ///
+    /// Parallel or not, a long packet results in the same samples as modulating its symbols one by one:
+    /// ```
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    ///
+    /// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(16),
+    ///     interleaving: true,
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    /// let interleaver = software_modem::interleaver::Interleaver::new(modulator.bytes_per_symbol());
+    ///
+    /// let payload: Vec<u8> = (0..24 * 1000).map(|i| (i * 37 % 251) as u8).collect();
+    /// let (samples, padding) = modulator.modulate_packet(&payload).unwrap();
+    /// assert_eq!(padding, 0);
+    ///
+    /// let mut symbol = vec![0.0; modulator.get_symbol_length()];
+    /// for (chunk, samples) in payload.chunks(24).zip(samples.chunks(modulator.get_symbol_length())) {
+    ///     modulator
+    ///         .modulate_buffer_as_symbol(&interleaver.interleave(chunk), &mut symbol)
+    ///         .unwrap();
+    ///     assert_eq!(samples, symbol);
+    /// }
+    /// ```
+    pub fn modulate_packet(&self, data: &[u8]) -> Result<(Vec<T>, usize), ModulationError> {
+        let (mut output, padding) = self.modulate_raw_packet(data)?;
+        let has_symbols = !output.is_empty();
+        self.finish_output(&mut output, has_symbols);
+
+        Ok((output, padding))
+    }
+
+    /// Modulates a packet like [modulate_packet](OFDMModulator::modulate_packet),
+    /// without clipping, quantization and the trailing gap.
+    fn modulate_raw_packet(&self, data: &[u8]) -> Result<(Vec<T>, usize), ModulationError> {
+        let bytes_per_symbol = self.constants.bytes_per_symbol();
+        let symbol_length = self.get_symbol_length();
+
+        let (padded_data, padding) = self.prepare_packet(data, bytes_per_symbol);
+        let repetition = self.constants.repetition as usize;
+        let num_symbols = padded_data.len() / bytes_per_symbol * repetition;
+
+        let window_length = if num_symbols > 0 {
+            self.constants.window_length()
+        } else {
+            0
+        };
+        let mut output = vec![T::zero(); num_symbols * symbol_length + window_length];
+        // overlap-add the fade out with the start of the next symbol
+        let mut add_symbol = |i: usize, symbol: &[T]| {
+            for copy in i * repetition..(i + 1) * repetition {
+                for (output, &sample) in output[copy * symbol_length..].iter_mut().zip(symbol) {
+                    *output = *output + sample;
+                }
+            }
+        };
+
+        #[cfg(not(feature = "rayon"))]
+        {
+            let mut scratch = self.lock_scratch();
+            for (i, chunk) in padded_data.chunks(bytes_per_symbol).enumerate() {
+                self.modulate_packet_symbol(chunk, i, &mut scratch)?;
+                add_symbol(i, &scratch.symbol);
+            }
+        }
+
+        #[cfg(feature = "rayon")]
+        {
+            use rayon::prelude::*;
+
+            let symbols = padded_data
+                .par_chunks(bytes_per_symbol)
+                .enumerate()
+                .map_init(
+                    || Scratch::new(self.fft.as_ref(), &self.constants),
+                    |scratch, (i, chunk)| {
+                        self.modulate_packet_symbol(chunk, i, scratch)?;
+                        Ok(scratch.symbol.clone())
+                    },
+                )
+                .collect::<Result<Vec<_>, ModulationError>>()?;
+            for (i, symbol) in symbols.iter().enumerate() {
+                add_symbol(i, symbol);
+            }
+        }
+
+        Ok((output, padding))
+    }
+
+    /// Clips and quantizes the assembled output as configured, then appends the trailing gap if `gap` is set.
+    ///
+    /// The RMS of the clipping and the full scale of the quantization are taken over the whole output,
+    /// so every sample ends up on the same grid.
+    fn finish_output(&self, output: &mut Vec<T>, gap: bool) {
+        if let Some(clipping_ratio) = self.clipping_ratio
+            && !output.is_empty()
+        {
+            let rms = (output.iter().map(|&s| s * s).sum::<T>() / cast(output.len() as f64)).sqrt();
+            clip_peaks(output, rms * clipping_ratio);
+        }
+
+        if let Some(bits) = self.quantization_bits {
+            let peak = output.iter().fold(T::zero(), |peak, s| peak.max(s.abs()));
+            quantize(output, bits, peak);
+        }
+
+        if gap {
+            output.resize(output.len() + self.trailing_gap, T::zero());
+        }
+    }
+
+    /// Modulates the chunk with the given index of a packet into `scratch.symbol`, interleaved if configured.
+    fn modulate_packet_symbol(
+        &self,
+        chunk: &[u8],
+        symbol_index: usize,
+        scratch: &mut Scratch<T>,
+    ) -> Result<(), ModulationError> {
+        match &self.interleaver {
+            Some(interleaver) => {
+                self.modulate_windowed_symbol(&interleaver.interleave(chunk), symbol_index, scratch)
+            }
+            None => self.modulate_windowed_symbol(chunk, symbol_index, scratch),
+        }
+    }
+
+    /// Modulates a payload like [modulate_packet](OFDMModulator::modulate_packet) into 16-bit signed samples,
+    /// e.g. to feed an integer DAC directly.
+    ///
+    /// The peaks of an OFDM signal are much higher than its average, so the samples are scaled
+    /// to put the largest absolute sample `headroom_db` dB below full scale (`i16::MAX`).
+    /// A headroom of `0.0` uses the full range, a negative headroom clips the peaks.
+    ///
+    /// Returns the samples, the number of padding bytes and the scale factor,
+    /// every sample is `round(sample * scale)` of the float output,
+    /// so dividing by the scale restores the amplitudes of [modulate_packet](OFDMModulator::modulate_packet).
+    /// An empty payload results in an empty output and a scale of `i16::MAX`.
+    ///
+    /// # Errors
+    /// Returns [ModulationError::Fft] if a configured FFT rejects its buffers.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    ///
+    /// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(4),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    ///
+    /// let payload: Vec<u8> = (0..200).map(|i| (i * 7) as u8).collect();
+    /// let (samples, padding) = modulator.modulate_packet(&payload).unwrap();
+    /// let (integers, integer_padding, scale) = modulator.modulate_packet_i16(&payload, 6.0).unwrap();
+    /// assert_eq!(integers.len(), samples.len());
+    /// assert_eq!(integer_padding, padding);
+    ///
+    /// // the peak sits 6 dB below full scale
+    /// let peak = integers.iter().map(|s| s.unsigned_abs()).max().unwrap();
+    /// assert!((peak as f32 - i16::MAX as f32 / 10f32.powf(6.0 / 20.0)).abs() <= 1.0);
+    ///
+    /// // rescaled, the samples match within half a quantization step
+    /// for (&integer, &sample) in integers.iter().zip(&samples) {
+    ///     assert!((integer as f32 / scale - sample).abs() <= 0.5 / scale + 1e-6);
+    /// }
+    /// ```
+    pub fn modulate_packet_i16(
+        &self,
+        data: &[u8],
+        headroom_db: T,
+    ) -> Result<(Vec<i16>, usize, T), ModulationError> {
+        let (samples, padding) = self.modulate_packet(data)?;
+
+        let full_scale: T = cast(i16::MAX as f64);
+        let peak = samples
+            .iter()
+            .fold(T::zero(), |peak, sample| peak.max(sample.abs()));
+        let scale = if peak > T::zero() {
+            full_scale / cast::<T>(10.0).powf(headroom_db / cast(20.0)) / peak
+        } else {
+            full_scale
+        };
+
+        let integers = samples
+            .iter()
+            .map(|&sample| {
+                // float to integer casts saturate, so a negative headroom clips
+                (sample * scale).round().to_f64().unwrap_or_default() as i16
+            })
+            .collect();
+
+        Ok((integers, padding, scale))
+    }
+
+    /// Encodes the payload, pads it to a multiple of `chunk_length` bytes and scrambles it,
+    /// as configured.
+    ///
+    /// Returns the padded data and the number of padding bytes.
+    fn prepare_packet(&self, data: &[u8], chunk_length: usize) -> (Vec<u8>, usize) {
+        #[cfg(feature = "rs")]
+        let data = &if self.reed_solomon {
+            fec::reed_solomon::encode(data)
+        } else {
+            data.to_vec()
+        };
+
+        let mut padded_data = if self.convolutional_coding {
+            fec::encode(data)
+        } else {
+            data.to_vec()
+        };
+
+        let padding = padded_data.len().next_multiple_of(chunk_length) - padded_data.len();
+        padded_data.resize(padded_data.len() + padding, 0);
+        if self.scrambling {
+            padded_data = scrambler::scramble(&padded_data);
+        }
+
+        (padded_data, padding)
+    }
+
+    /// Modulates a payload of arbitrary length into complex baseband (I/Q) OFDM symbols.
+    ///
+    /// The real output of [modulate_packet](OFDMModulator::modulate_packet) mirrors its subcarriers
+    /// onto the negative frequencies. In complex baseband, the negative frequencies are independent,
+    /// so every symbol carries two chunks of data, which doubles the capacity of a symbol:
+    /// the first chunk is placed on the subcarriers `k` and the second on the subcarriers `-k`,
+    /// i.e. the FFT bins `2 * num_subcarriers - k`, each with its own pilots.
+    /// The symbol length is the same `get_symbol_length()` samples as for real symbols.
+    ///
+    /// Coding, scrambling, interleaving and repetition work like in [modulate_packet](OFDMModulator::modulate_packet),
+    /// but the payload is padded to a multiple of `2 * bytes_per_symbol()`.
+    /// The symbols are not windowed, selective mapping always uses the first candidate,
+    /// and neither [clipping_ratio](OFDMModulatorConfig::clipping_ratio),
+    /// [quantization_bits](OFDMModulatorConfig::quantization_bits) nor the
+    /// [trailing_gap](OFDMModulatorConfig::trailing_gap) apply to the I/Q output.
+    ///
+    /// Returns the samples and the number of padding bytes, which
+    /// [demodulate_iq](crate::ofdm::demodulator::OFDMDemodulator::demodulate_iq) needs to trim the padding.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    /// use realfft::num_complex::Complex32;
+    /// use rustfft::FftPlanner;
+    ///
+    /// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(16),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    /// let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(16),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    ///
+    /// // 96 bytes take 4 real symbols of 24 bytes, but only 2 I/Q symbols
+    /// let payload: Vec<u8> = (0..96).map(|i| (i * 13) as u8).collect();
+    /// let (samples, padding) = modulator.modulate_iq(&payload);
+    /// let _: &[Complex32] = &samples;
+    ///
+    /// assert_eq!(padding, 0);
+    /// assert_eq!(samples.len(), 2 * modulator.get_symbol_length());
+    /// assert_eq!(demodulator.demodulate_iq(&samples, padding), payload);
+    ///
+    /// // the data subcarriers occupy both halves of the spectrum
+    /// let mut spectrum = samples[16..16 + 128].to_vec();
+    /// FftPlanner::new().plan_fft_forward(128).process(&mut spectrum);
+    /// let positive: f32 = spectrum[1..64].iter().map(|bin| bin.norm_sqr()).sum();
+    /// let negative: f32 = spectrum[65..].iter().map(|bin| bin.norm_sqr()).sum();
+    /// assert!(positive > 0.0 && (negative / positive - 1.0).abs() < 0.2);
+    ///
+    /// // unlike a real signal, the halves are not mirror images of each other
+    /// let mirrored = (1..64).filter(|&k| (spectrum[k] - spectrum[128 - k].conj()).norm() < 1e-3);
+    /// assert!(mirrored.count() < 32);
+    /// ```
+    pub fn modulate_iq(&self, data: &[u8]) -> (Vec<Complex<T>>, usize) {
+        let bytes_per_symbol = self.constants.bytes_per_symbol();
+        let fft_length = 2 * self.constants.num_subcarriers as usize;
+        let cyclic_prefix_length = self.constants.cyclic_prefix_length as usize;
+
+        let (padded_data, padding) = self.prepare_packet(data, 2 * bytes_per_symbol);
+
+        let repetition = self.constants.repetition as usize;
+        let mut output = Vec::with_capacity(
+            padded_data.len() / (2 * bytes_per_symbol) * repetition * self.get_symbol_length(),
+        );
+        let mut spectrum = vec![Complex::default(); fft_length];
+        let mut scratch = self.lock_scratch();
+        for (i, chunk) in padded_data.chunks(2 * bytes_per_symbol).enumerate() {
+            // the previous FFT used the spectrum as output
+            spectrum.fill(Complex::default());
+
+            for (half, chunk) in chunk.chunks(bytes_per_symbol).enumerate() {
+                let interleaved;
+                let chunk = match &self.interleaver {
+                    Some(interleaver) => {
+                        interleaved = interleaver.interleave(chunk);
+                        &interleaved
+                    }
+                    None => chunk,
+                };
+                self.constants
+                    .bit_loading
+                    .modulate_into(chunk, &mut scratch.qam_symbols);
+                self.place_subcarriers(0, i, &mut scratch);
+
+                // the first and the last bin are never active, so the halves do not overlap
+                for k in 1..self.constants.num_subcarriers as usize {
+                    let bin = if half == 0 { k } else { fft_length - k };
+                    spectrum[bin] = scratch.spectrum[k];
+                }
+            }
+
+            self.iq_fft.process(&mut spectrum);
+            self.scale_output(&mut spectrum);
+
+            for _ in 0..repetition {
+                output.extend_from_slice(&spectrum[fft_length - cyclic_prefix_length..]);
+                output.extend_from_slice(&spectrum);
+            }
+        }
+
+        (output, padding)
+    }
+
+    /// Wraps the payload into a [frame](crate::frame) and modulates it like [modulate_packet](OFDMModulator::modulate_packet).
+    ///
+    /// The frame carries the payload length, the sequence number `seq` and a checksum,
+    /// so the receiver needs no information about the padding.
+    /// Use [demodulate_framed_packet](crate::ofdm::demodulator::OFDMDemodulator::demodulate_framed_packet) to receive it.
+    pub fn modulate_framed_packet(
+        &self,
+        payload: &[u8],
+        seq: u16,
+    ) -> Result<Vec<T>, ModulationError> {
+        let (samples, _) = self.modulate_packet(&frame::frame(payload, seq))?;
+        Ok(samples)
+    }
+
+    /// Modulates a payload like [modulate_packet](OFDMModulator::modulate_packet)
+    /// behind a header announcing its length, so the receiver needs no information about the padding.
+    ///
+    /// The header holds the payload length as a little endian `u32`, the configured `qam_order`
+    /// as its number of bits per symbol (1, 2 or 4) and a little endian CRC-32 checksum over both:
+    ///
+    /// ```text
+    /// | payload length (4 bytes) | qam order (1 byte) | crc-32 (4 bytes) |
+    /// ```
+    ///
+    /// The 72 bits are sent in BPSK, one bit on every data subcarrier,
+    /// so they take 2 symbols with 48 data subcarriers and the last symbol is filled up with zero bits.
+    /// The header symbols are neither coded, scrambled, interleaved nor repeated,
+    /// but boosted to the mean power of the data subcarriers, which gives BPSK its full margin over the payload.
+    /// With [pilot_polarity](OFDMModulatorConfig::pilot_polarity), the header and the payload
+    /// each start the sequence at their first symbol.
+    /// Clipping and quantization apply to the header and the payload together.
+    /// Use [demodulate_packet_with_header](crate::ofdm::demodulator::OFDMDemodulator::demodulate_packet_with_header)
+    /// to receive the packet.
+    ///
+    /// # Errors
+    /// Returns [ModulationError::Fft] if a configured FFT rejects its buffers.
+    ///
+    /// # Panics
+    /// If the payload is larger than `u32::MAX` bytes.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::error::HeaderError;
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    /// use software_modem::qam::QAMOrder;
+    ///
+    /// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(16),
+    ///     window_length: 4,
+    ///     convolutional_coding: true,
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    /// let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(16),
+    ///     window_length: 4,
+    ///     convolutional_coding: true,
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    ///
+    /// for length in [0, 1, 23, 24, 100, 1000] {
+    ///     let payload: Vec<u8> = (0..length).map(|i| (i * 37 % 251) as u8).collect();
+    ///     let mut samples = modulator.modulate_packet_with_header(&payload).unwrap();
+    ///
+    ///     // the receiver knows neither the length nor the padding and ignores what follows the packet
+    ///     samples.extend(vec![0.0; 500]);
+    ///     assert_eq!(demodulator.demodulate_packet_with_header(&samples).unwrap(), payload);
+    /// }
+    ///
+    /// // a demodulator for another QAM order is told so instead of returning garbage
+    /// let qam4 = OFDMDemodulator::new(OFDMDemodulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(16),
+    ///     window_length: 4,
+    ///     qam_order: QAMOrder::QAM4,
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    /// let samples = modulator.modulate_packet_with_header(b"QAM-16").unwrap();
+    /// assert_eq!(
+    ///     qam4.demodulate_packet_with_header(&samples),
+    ///     Err(HeaderError::QamOrderMismatch { expected: QAMOrder::QAM4, actual: QAMOrder::QAM16 })
+    /// );
+    ///
+    /// // a truncated packet is detected
+    /// assert!(matches!(
+    ///     demodulator.demodulate_packet_with_header(&samples[..samples.len() - 100]),
+    ///     Err(HeaderError::TooShort { .. })
+    /// ));
+    ///
+    /// // the header spans as many symbols as it needs, here 12 with 6 data subcarriers
+    /// let small = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 8,
+    ///     cyclic_prefix: CyclicPrefix::Samples(2),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    /// let small_demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+    ///     num_subcarriers: 8,
+    ///     cyclic_prefix: CyclicPrefix::Samples(2),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    /// let samples = small.modulate_packet_with_header(b"Tiny").unwrap();
+    /// assert_eq!(small_demodulator.demodulate_packet_with_header(&samples).unwrap(), b"Tiny");
     /// ```
-    pub fn modulate_buffer_as_symbol(&self, data: &[u8], output_buffer: &mut [f32]) {
-        if data.len() != ((self.constants.bits_per_symbol / 8) as usize) {
-            panic!(
-                "Data length must be {} bytes, but got {} bytes",
-                self.constants.bits_per_symbol / 8,
-                data.len()
-            );
+    pub fn modulate_packet_with_header(&self, data: &[u8]) -> Result<Vec<T>, ModulationError> {
+        let (mut output, has_symbols) = self.modulate_raw_packet_with_header(data)?;
+        self.finish_output(&mut output, has_symbols);
+
+        Ok(output)
+    }
+
+    /// Modulates a packet like [modulate_packet_with_header](OFDMModulator::modulate_packet_with_header),
+    /// without clipping, quantization and the trailing gap.
+    ///
+    /// Also returns whether the packet has data symbols, which are followed by the trailing gap.
+    fn modulate_raw_packet_with_header(
+        &self,
+        data: &[u8],
+    ) -> Result<(Vec<T>, bool), ModulationError> {
+        let num_header_symbols = self.constants.num_header_symbols();
+        let mut header = header::encode(data.len(), self.constants.qam_order);
+        // the last header symbol is filled up with zero bits
+        header.resize(
+            (num_header_symbols * self.constants.num_data_subcarriers as usize).div_ceil(8),
+            0,
+        );
+        let (packet, _) = self.modulate_raw_packet(data)?;
+
+        let symbol_length = self.get_symbol_length();
+        let header_length = num_header_symbols * symbol_length;
+        let mut output =
+            vec![T::zero(); header_length + packet.len().max(self.constants.window_length())];
+
+        // the header points get the mean power of the data points
+        let num_data_subcarriers: T = cast(self.constants.num_data_subcarriers as f64);
+        let gain = (self.constants.bit_loading.average_power() / num_data_subcarriers).sqrt();
+
+        let mut reader = BitReader::new(&header, 1);
+        let mut scratch = self.lock_scratch();
+        for i in 0..num_header_symbols {
+            self.constants
+                .header_loading
+                .modulate_from(&mut reader, &mut scratch.qam_symbols);
+            scratch
+                .qam_symbols
+                .iter_mut()
+                .for_each(|point| *point = *point * gain);
+            self.modulate_ofdm_symbol(i, &mut scratch)?;
+
+            // overlap-add the fade out with the start of the next symbol
+            for (output, &sample) in output[i * symbol_length..].iter_mut().zip(&scratch.symbol) {
+                *output = *output + sample;
+            }
+        }
+
+        for (output, &sample) in output[header_length..].iter_mut().zip(&packet) {
+            *output = *output + sample;
         }
 
-        let qam_symbols = self.qam_modem.modulate(data);
+        Ok((output, !packet.is_empty()))
+    }
+
+    /// Assembles a complete frame, the turnkey counterpart of
+    /// [receive_frame](crate::ofdm::demodulator::OFDMDemodulator::receive_frame).
+    ///
+    /// The frame holds the parts in the canonical order:
+    ///
+    /// ```text
+    /// | preamble | long training symbol | header | data symbols |
+    /// ```
+    ///
+    /// The [preamble](OFDMModulator::generate_preamble) lets the receiver find the frame and its timing,
+    /// the [long training symbol](OFDMModulator::generate_long_training_symbol) gives the channel estimate
+    /// and the payload follows as a [packet with a header](OFDMModulator::modulate_packet_with_header),
+    /// so the receiver learns its length.
+    /// A configured [clipping_ratio](OFDMModulatorConfig::clipping_ratio) and
+    /// [quantization_bits](OFDMModulatorConfig::quantization_bits) apply to the whole frame at once.
+    ///
+    /// # Errors
+    /// Returns [ModulationError::Fft] if a configured FFT rejects its buffers.
+    ///
+    /// # Example
+    /// ```
+    /// use rand::{SeedableRng, rngs::StdRng};
+    /// use software_modem::channel::add_awgn;
+    /// use software_modem::error::RxError;
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    ///
+    /// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(16),
+    ///     convolutional_coding: true,
+    ///     scrambling: true,
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    /// let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(16),
+    ///     convolutional_coding: true,
+    ///     scrambling: true,
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    ///
+    /// let payload = b"Hello, turnkey OFDM!";
+    /// let frame = modulator.build_frame(payload).unwrap();
+    /// let mut samples = vec![0.0; 700];
+    /// samples.extend(&frame);
+    /// samples.extend(vec![0.0; 500]);
+    ///
+    /// // the SNR is relative to the mean power of the whole signal, silence included
+    /// let mut rng = StdRng::seed_from_u64(1);
+    /// add_awgn(&mut samples, 15.0, &mut rng);
+    /// assert_eq!(demodulator.receive_frame(&samples).unwrap(), payload);
+    ///
+    /// let mut noise = vec![0.0; 2000];
+    /// add_awgn(&mut noise, 0.0, &mut rng);
+    /// assert_eq!(demodulator.receive_frame(&noise), Err(RxError::NoPreamble));
+    ///
+    /// // a narrow band with only 6 data subcarriers works just as well
+    /// let small = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(16),
+    ///     null_subcarrier_indices: (8..=64).collect(),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    /// let small_demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(16),
+    ///     null_subcarrier_indices: (8..=64).collect(),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    /// assert_eq!(small.num_data_subcarriers(), 6);
+    /// let mut samples = vec![0.0; 700];
+    /// samples.extend(small.build_frame(b"Tiny").unwrap());
+    /// samples.extend(vec![0.0; 500]);
+    /// assert_eq!(small_demodulator.receive_frame(&samples).unwrap(), b"Tiny");
+    /// ```
+    pub fn build_frame(&self, payload: &[u8]) -> Result<Vec<T>, ModulationError> {
+        let (packet, has_symbols) = self.modulate_raw_packet_with_header(payload)?;
+        let mut frame = self.generate_preamble();
+        frame.extend(self.generate_long_training_symbol());
+        frame.extend(packet);
+        self.finish_output(&mut frame, has_symbols);
 
-        self.modulate_ofdm_symbol(qam_symbols, output_buffer)
-            .unwrap();
+        Ok(frame)
     }
 
+    /// Places `scratch.qam_symbols` and the pilots of the symbol with the given index within a packet
+    /// on their subcarriers and transforms the symbol.
+    ///
+    /// With selective mapping, every candidate is transformed and the one with the lowest peak is kept.
     fn modulate_ofdm_symbol(
         &self,
-        qam_symbols: Vec<realfft::num_complex::Complex<f32>>,
-        output: &mut [f32],
-    ) -> Result<(), String> {
-        // data prep
-        let mut input: Vec<realfft::num_complex::Complex<f32>> = self.fft.make_input_vec();
+        symbol_index: usize,
+        scratch: &mut Scratch<T>,
+    ) -> Result<(), ModulationError> {
+        let num_candidates = self.constants.slm_phases.len();
+        if num_candidates == 1 {
+            self.place_subcarriers(0, symbol_index, scratch);
+            return self.transform_spectrum(scratch);
+        }
+
+        let mut lowest_peak = T::infinity();
+        for candidate in 0..num_candidates {
+            self.place_subcarriers(candidate, symbol_index, scratch);
+            self.transform_spectrum(scratch)?;
+
+            let peak = scratch
+                .symbol
+                .iter()
+                .fold(T::zero(), |peak, sample| peak.max(sample.abs()));
+            if peak < lowest_peak {
+                lowest_peak = peak;
+                scratch.best_symbol.copy_from_slice(&scratch.symbol);
+            }
+        }
+        std::mem::swap(&mut scratch.symbol, &mut scratch.best_symbol);
+
+        Ok(())
+    }
 
-        for (i, &idx) in self.constants.data_subcarrier_indices.iter().enumerate() {
-            input[idx as usize] = qam_symbols[i];
+    /// Fills `scratch.spectrum` with the data rotated by the given selective mapping candidate,
+    /// the index of the candidate and the pilots of the symbol with the given index within a packet.
+    fn place_subcarriers(&self, candidate: usize, symbol_index: usize, scratch: &mut Scratch<T>) {
+        // data prep, the previous FFT used the spectrum as scratch space
+        scratch.spectrum.fill(Complex::default());
+
+        // data subcarriers not covered by a whole byte are left empty
+        for ((&idx, &symbol), &phase) in self
+            .constants
+            .data_subcarrier_indices
+            .iter()
+            .zip(scratch.qam_symbols.iter())
+            .zip(self.constants.slm_phases[candidate].iter())
+        {
+            scratch.spectrum[idx as usize] = symbol * phase;
+        }
+
+        let index_subcarriers = &self.constants.slm_index_subcarrier_indices;
+        for (bit, &idx) in index_subcarriers.iter().rev().enumerate() {
+            let value = if (candidate >> bit) & 1 == 0 {
+                T::one()
+            } else {
+                -T::one()
+            };
+            scratch.spectrum[idx as usize] = Complex::new(value, T::zero());
         }
 
-        for &idx in &self.constants.pilot_subcarrier_indices {
-            input[idx as usize] = PILOT_VALUE_TO_BE_CHANGED;
+        let sign = self.constants.pilot_sign(symbol_index);
+        for (&idx, &pilot) in self
+            .constants
+            .pilot_subcarrier_indices
+            .iter()
+            .zip(self.constants.pilot_values.iter())
+        {
+            scratch.spectrum[idx as usize] = pilot * sign;
         }
+    }
 
-        let mut output_buffer = self.fft.make_output_vec();
+    /// Transforms `scratch.spectrum` into the time domain and adds the cyclic prefix.
+    ///
+    /// The resulting `scratch.symbol` holds the symbol followed by `window_length` samples of the cyclic continuation,
+    /// both ends are tapered with a raised-cosine window.
+    fn transform_spectrum(&self, scratch: &mut Scratch<T>) -> Result<(), ModulationError> {
+        let Scratch {
+            spectrum,
+            time,
+            fft,
+            symbol,
+            ..
+        } = scratch;
 
         // frequency domain to time domain
-        self.fft.process(&mut input, &mut output_buffer).unwrap();
+        self.fft
+            .process_with_scratch(spectrum, time, fft)
+            .map_err(|error| ModulationError::Fft {
+                message: error.to_string(),
+            })?;
+        self.scale_output(time);
+
+        let cyclic_prefix_length = self.constants.cyclic_prefix_length as usize;
+        let window_length = self.constants.window_length();
+        let body_end = cyclic_prefix_length + time.len();
 
         // add cp
-        output[self.constants.cyclic_prefix_length as usize..].copy_from_slice(&output_buffer);
+        symbol[cyclic_prefix_length..body_end].copy_from_slice(time);
 
-        output[..self.constants.cyclic_prefix_length as usize].copy_from_slice(
-            &output_buffer
-                [(output_buffer.len() - (self.constants.cyclic_prefix_length as usize))..],
-        );
+        symbol[..cyclic_prefix_length]
+            .copy_from_slice(&time[(time.len() - cyclic_prefix_length)..]);
+
+        // continue the symbol for the fade out of the window
+        symbol[body_end..].copy_from_slice(&time[..window_length]);
+
+        for i in 0..window_length {
+            let ramp: T = cast(raised_cosine(i, window_length));
+            symbol[i] = symbol[i] * ramp;
+            symbol[body_end + i] = symbol[body_end + i] * (T::one() - ramp);
+        }
 
         Ok(())
     }
 
-    /// Returns the length of the OFDM symbol, including the cyclic prefix.
-    ///
-    /// The length is calculated as:
-    /// `2 * num_subcarriers + cyclic_prefix_length`.
-    pub fn get_symbol_length(&self) -> usize {
-        (2 * self.constants.num_subcarriers + self.constants.cyclic_prefix_length) as usize
+    /// Applies the factor of [normalize_output](OFDMModulatorConfig::normalize_output), if enabled.
+    fn scale_output<S: Copy + std::ops::Mul<T, Output = S>>(&self, samples: &mut [S]) {
+        if let Some(scale) = self.output_scale {
+            samples
+                .iter_mut()
+                .for_each(|sample| *sample = *sample * scale);
+        }
     }
-}
 
-/// Configuration for the [OFDM Modulator](OFDMModulator).
-///
-/// Just contruct this struct with the desired parameters and pass it to the `OFDMModulator::new()` method.
-#[derive(SmartDefault)]
-pub struct OFDMModulatorConfig {
-    pub num_subcarriers: u32,
-    /// Length of the cyclic prefix in samples.
+    /// Locks the scratch buffers, which hold no state worth protecting from a panic in another thread.
+    fn lock_scratch(&self) -> MutexGuard<'_, Scratch<T>> {
+        self.scratch.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Generates the [Schmidl-Cox preamble](crate::ofdm::preamble) used for frame synchronization.
     ///
-    /// One OFDM symbol double num_subcarriers samples. If you want to have a CP of 1/4 you need to set this to `(2 * num_subcarriers) / 4`
-    pub cyclic_prefix_length: u32,
-    /// Interval for pilot subcarriers.
+    /// The preamble has the same length as a regular OFDM symbol including the cyclic prefix.
+    /// It carries pseudo random BPSK values on the even subcarriers only,
+    /// so both halves of the symbol after the cyclic prefix are identical.
+    /// Send it in front of the data symbols so the receiver can find them with
+    /// [find_frame_start](crate::ofdm::demodulator::OFDMDemodulator::find_frame_start).
     ///
-    /// Inserts pilot subcarriers every `pilot_subcarrier_every` subcarrier.
-    #[default(4)]
-    pub pilot_subcarrier_every: u32,
+    /// # Panics
+    /// If a configured FFT rejects its buffers.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    ///
+    /// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(16),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    ///
+    /// let preamble = modulator.generate_preamble();
+    /// assert_eq!(preamble.len(), modulator.get_symbol_length());
+    ///
+    /// let (first_half, second_half) = preamble[16..].split_at(64);
+    /// for (a, b) in first_half.iter().zip(second_half) {
+    ///     assert!((a - b).abs() < 1e-4);
+    /// }
+    /// ```
+    pub fn generate_preamble(&self) -> Vec<T> {
+        let mut scratch = self.lock_scratch();
+        scratch
+            .spectrum
+            .copy_from_slice(&preamble_spectrum(&self.constants));
+        self.transform_spectrum(&mut scratch).unwrap();
+
+        scratch.symbol[..self.get_symbol_length()].to_vec()
+    }
+
+    /// Generates the long training symbol, which carries a known pseudo random BPSK value on every subcarrier.
+    ///
+    /// Unlike the pilots, which only sample the channel every `pilot_subcarrier_every` subcarriers,
+    /// it allows the receiver to estimate the channel of every subcarrier on its own with
+    /// [estimate_channel_from_ltf](crate::ofdm::demodulator::OFDMDemodulator::estimate_channel_from_ltf).
+    /// Send it after the [preamble](OFDMModulator::generate_preamble) and in front of the data symbols.
+    /// The BPSK values are sent with the mean power of the data points. Null subcarriers stay empty.
+    ///
+    /// # Panics
+    /// If a configured FFT rejects its buffers.
+    pub fn generate_long_training_symbol(&self) -> Vec<T> {
+        let mut scratch = self.lock_scratch();
+        scratch
+            .spectrum
+            .copy_from_slice(&long_training_spectrum(&self.constants));
+        self.transform_spectrum(&mut scratch).unwrap();
+
+        scratch.symbol[..self.get_symbol_length()].to_vec()
+    }
+
+    /// Generates `duration_samples` samples of silence to separate packets.
+    ///
+    /// Configure a [trailing_gap](OFDMModulatorConfig::trailing_gap) to append the gap to every packet instead.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    ///
+    /// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(16),
+    ///     window_length: 4,
+    ///     trailing_gap: 300,
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    /// let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(16),
+    ///     window_length: 4,
+    ///     trailing_gap: 300,
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    ///
+    /// let payload = b"Mind the gap";
+    /// let (samples, padding) = modulator.modulate_packet(payload).unwrap();
+    /// let packet_length = samples.len() - 300;
+    /// assert_eq!(packet_length % modulator.get_symbol_length(), 4);
+    ///
+    /// // the fade out of the last symbol is followed by exactly the gap
+    /// assert_ne!(samples[packet_length - 1], 0.0);
+    /// assert_eq!(samples[packet_length..], modulator.generate_gap(300));
+    /// assert_eq!(demodulator.demodulate_packet(&samples, padding), payload);
+    /// ```
+    ///
+    /// Packets without a configured gap can be separated by hand:
+    /// ```
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    ///
+    /// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(16),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    ///
+    /// let mut stream = Vec::new();
+    /// for payload in [b"first", b"other"] {
+    ///     stream.extend(modulator.modulate_packet(payload).unwrap().0);
+    ///     stream.extend(modulator.generate_gap(100));
+    /// }
+    /// assert_eq!(stream.len(), 2 * (modulator.get_symbol_length() + 100));
+    /// ```
+    pub fn generate_gap(&self, duration_samples: usize) -> Vec<T> {
+        vec![T::zero(); duration_samples]
+    }
+
+    /// Returns the length of the OFDM symbol, including the cyclic prefix.
+    ///
+    /// The length is calculated as:
+    /// `2 * num_subcarriers` plus the length of the cyclic prefix.
+    pub fn get_symbol_length(&self) -> usize {
+        self.constants.symbol_length()
+    }
+
+    /// Returns the number of payload bytes carried by one OFDM symbol.
+    ///
+    /// This is `bits_per_symbol() / 8`, data subcarriers not covered by a whole byte stay empty.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    ///
+    /// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(4),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    ///
+    /// // 63 subcarriers between DC and nyquist, every 4th one is a pilot
+    /// assert_eq!(modulator.num_pilot_subcarriers(), 15);
+    /// assert_eq!(modulator.num_data_subcarriers(), 48);
+    /// assert_eq!(modulator.bits_per_symbol(), 48 * 4);
+    ///
+    /// let data = vec![0x5a; modulator.bytes_per_symbol()];
+    /// let mut symbol = vec![0.0; modulator.get_symbol_length()];
+    /// modulator.modulate_buffer_as_symbol(&data, &mut symbol).unwrap();
+    /// ```
+    pub fn bytes_per_symbol(&self) -> usize {
+        self.constants.bytes_per_symbol()
+    }
+
+    /// Returns the number of bits the data subcarriers of one OFDM symbol can carry.
+    pub fn bits_per_symbol(&self) -> u32 {
+        self.constants.bits_per_symbol
+    }
+
+    /// Returns the number of subcarriers carrying data.
+    pub fn num_data_subcarriers(&self) -> u32 {
+        self.constants.num_data_subcarriers
+    }
+
+    /// Returns the number of subcarriers carrying pilots.
+    pub fn num_pilot_subcarriers(&self) -> u32 {
+        self.constants.num_pilot_subcarriers
+    }
+
+    /// Returns whether the FFT length of `2 * num_subcarriers` samples is
+    /// [efficient](crate::ofdm::is_efficient_num_subcarriers) for the default planner.
+    ///
+    /// Any number of subcarriers works, but one with a large prime factor in its FFT length
+    /// makes every symbol several times slower to transform.
+    /// Round it up with [next_efficient_num_subcarriers](crate::ofdm::next_efficient_num_subcarriers) instead.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    ///
+    /// let modulator = |num_subcarriers| {
+    ///     OFDMModulator::new(OFDMModulatorConfig {
+    ///         num_subcarriers,
+    ///         cyclic_prefix: CyclicPrefix::Samples(16),
+    ///         ..Default::default()
+    ///     })
+    ///     .unwrap()
+    /// };
+    ///
+    /// assert!(modulator(64).is_efficient_size());
+    /// // an FFT of 2 * 127 samples
+    /// assert!(!modulator(127).is_efficient_size());
+    /// ```
+    pub fn is_efficient_size(&self) -> bool {
+        is_efficient_num_subcarriers(self.constants.num_subcarriers)
+    }
+
+    /// Returns the spacing of the subcarriers in Hz when the samples are played at `sample_rate` Hz.
+    ///
+    /// One symbol without the cyclic prefix lasts `2 * num_subcarriers` samples,
+    /// so the subcarriers are `sample_rate / (2 * num_subcarriers)` apart.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    ///
+    /// // only the subcarriers 5 to 52 are used, the rest is nulled
+    /// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Fraction(0.25),
+    ///     null_subcarrier_indices: (1..5).chain(53..64).collect(),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    ///
+    /// assert_eq!(modulator.subcarrier_spacing(8000.0), 62.5);
+    /// assert_eq!(modulator.occupied_bandwidth(8000.0), 48.0 * 62.5);
+    ///
+    /// // fits into a 3 kHz SSB channel
+    /// assert!(modulator.occupied_bandwidth(8000.0) <= 3000.0);
+    /// ```
+    pub fn subcarrier_spacing(&self, sample_rate: f32) -> f32 {
+        sample_rate / (2 * self.constants.num_subcarriers) as f32
+    }
+
+    /// Returns the bandwidth in Hz occupied by the signal when the samples are played at `sample_rate` Hz.
+    ///
+    /// The bandwidth spans from the lowest to the highest subcarrier in use,
+    /// plus half a subcarrier spacing on either side.
+    /// Nulled subcarriers between used ones count as occupied, DC and nyquist are never used.
+    /// See [subcarrier_spacing](OFDMModulator::subcarrier_spacing) for an example.
+    pub fn occupied_bandwidth(&self, sample_rate: f32) -> f32 {
+        let used_subcarriers = || {
+            self.constants
+                .data_subcarrier_indices
+                .iter()
+                .chain(&self.constants.pilot_subcarrier_indices)
+                .chain(&self.constants.slm_index_subcarrier_indices)
+        };
+        match (used_subcarriers().min(), used_subcarriers().max()) {
+            (Some(lowest), Some(highest)) => {
+                (highest - lowest + 1) as f32 * self.subcarrier_spacing(sample_rate)
+            }
+            _ => 0.0,
+        }
+    }
+
+    /// Returns the payload bits per second of a long packet when the samples are played at `sample_rate` Hz.
+    ///
+    /// Every symbol carries `bytes_per_symbol() * 8` bits and lasts `get_symbol_length()` samples,
+    /// so pilots, empty data subcarriers and the cyclic prefix lower the throughput.
+    /// The rate is divided by the `repetition` and multiplied by the code rate of the configured coding,
+    /// `1 / 2` for the convolutional code and `223 / 255` for the Reed-Solomon code.
+    /// The tail of the convolutional code, the padding of the last symbol
+    /// and the preamble or header of a packet are not included, so short packets stay below this rate.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    ///
+    /// let throughput = |cyclic_prefix_length: u32, convolutional_coding: bool| {
+    ///     let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///         num_subcarriers: 64,
+    ///         cyclic_prefix: CyclicPrefix::Samples(cyclic_prefix_length),
+    ///         convolutional_coding,
+    ///         ..Default::default()
+    ///     })
+    ///     .unwrap();
+    ///     modulator.throughput_bps(8000.0)
+    /// };
+    ///
+    /// // 24 bytes every 144 samples at 8 kHz
+    /// let uncoded = throughput(16, false);
+    /// println!("{uncoded:.0} bit/s");
+    /// assert!((uncoded - 192.0 * 8000.0 / 144.0).abs() < 1e-6);
+    ///
+    /// // the code halves the rate, a longer cyclic prefix costs a little more
+    /// assert!((throughput(16, true) - uncoded / 2.0).abs() < 1e-6);
+    /// assert!(throughput(32, false) < uncoded);
+    /// ```
+    pub fn throughput_bps(&self, sample_rate: f32) -> f64 {
+        let bits_per_symbol = (self.constants.bytes_per_symbol() * 8) as f64;
+        let symbol_duration = self.get_symbol_length() as f64 / sample_rate as f64;
+        let mut throughput = bits_per_symbol / symbol_duration / self.constants.repetition as f64;
+
+        if self.convolutional_coding {
+            throughput /= 2.0;
+        }
+        #[cfg(feature = "rs")]
+        if self.reed_solomon {
+            throughput *=
+                fec::reed_solomon::DATA_LENGTH as f64 / fec::reed_solomon::BLOCK_LENGTH as f64;
+        }
+
+        throughput
+    }
+}
+
+/// Mean power of the samples of a symbol with every data subcarrier in use.
+///
+/// By Parseval, the power is the energy of the spectrum, which the real output has on both
+/// the positive and the mirrored negative frequencies.
+fn mean_symbol_power<T: Float>(constants: &OFDMConstants<T>) -> T {
+    let pilot_power = constants
+        .pilot_values
+        .iter()
+        .take(constants.pilot_subcarrier_indices.len())
+        .map(|pilot| pilot.norm_sqr())
+        .sum::<T>();
+    let index_power: T = cast(constants.slm_index_subcarrier_indices.len() as f64);
+
+    cast::<T>(2.0) * (constants.bit_loading.average_power() + pilot_power + index_power)
+}
+
+/// Plans the inverse FFT the modulator uses by default for `num_subcarriers` subcarriers.
+///
+/// The FFT transforms the `num_subcarriers + 1` bins from DC to nyquist into the `2 * num_subcarriers` samples of a symbol.
+/// Pass it as [fft](OFDMModulatorConfig::fft) to share one plan between several modulators.
+/// The plan is only fast for [efficient](crate::ofdm::is_efficient_num_subcarriers) numbers of subcarriers.
+pub fn plan_default_fft<T: Float>(num_subcarriers: u32) -> Arc<dyn ComplexToReal<T>> {
+    RealFftPlanner::<T>::new().plan_fft_inverse(2 * num_subcarriers as usize)
+}
+
+/// Returns the rising half of a raised-cosine window of `length` samples at sample `i`.
+fn raised_cosine(i: usize, length: usize) -> f64 {
+    0.5 * (1.0 - (std::f64::consts::PI * (i as f64 + 0.5) / length as f64).cos())
+}
+
+/// Configuration for the [OFDM Modulator](OFDMModulator).
+///
+/// Just contruct this struct with the desired parameters and pass it to the `OFDMModulator::new()` method.
+/// Use the [builder](OFDMModulatorConfig::builder) to validate the configuration while constructing it.
+///
+/// With the `serde` feature the configuration can be loaded from a file, e.g. as JSON.
+/// Missing fields take their default value and the [fft](OFDMModulatorConfig::fft) is skipped.
+///
+/// # Example
+/// ```
+/// # #[cfg(feature = "serde")]
+/// # {
+/// use software_modem::ofdm::CyclicPrefix;
+/// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+/// use software_modem::qam::QAMOrder;
+/// use realfft::num_complex::Complex32;
+///
+/// let config = OFDMModulatorConfig {
+///     num_subcarriers: 64,
+///     cyclic_prefix: CyclicPrefix::Samples(16),
+///     window_length: 4,
+///     qam_order: QAMOrder::QAM4,
+///     pilot_sequence: vec![Complex32::new(0.5, -0.25); 15],
+///     scrambling: true,
+///     num_slm_candidates: 4,
+///     ..Default::default()
+/// };
+/// let json = serde_json::to_string(&config).unwrap();
+///
+/// let loaded: OFDMModulatorConfig = serde_json::from_str(&json).unwrap();
+/// assert_eq!(serde_json::to_string(&loaded).unwrap(), json);
+/// assert_eq!(loaded.cyclic_prefix, CyclicPrefix::Samples(16));
+/// assert_eq!(loaded.qam_order, QAMOrder::QAM4);
+/// assert_eq!(loaded.pilot_sequence, config.pilot_sequence);
+/// assert!(OFDMModulator::new(loaded).is_ok());
+///
+/// // missing fields are taken from the default configuration
+/// let loaded: OFDMModulatorConfig = serde_json::from_str(r#"{ "num_subcarriers": 32 }"#).unwrap();
+/// assert_eq!(loaded.num_subcarriers, 32);
+/// assert_eq!(loaded.num_slm_candidates, 1);
+/// # }
+/// ```
+#[derive(SmartDefault)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct OFDMModulatorConfig<T: Float = f32> {
+    pub num_subcarriers: u32,
+    /// Length of the [cyclic prefix](CyclicPrefix), in samples or as a fraction of the symbol.
+    ///
+    /// One OFDM symbol has `2 * num_subcarriers` samples, so `CyclicPrefix::Fraction(0.25)` adds a quarter of that.
+    pub cyclic_prefix: CyclicPrefix,
+    /// Length of the raised-cosine window at the edges of every symbol in samples.
+    ///
+    /// Rectangular symbols cause high out-of-band emissions.
+    /// With a window, every symbol fades in over the first `window_length` samples of its cyclic prefix
+    /// while the previous symbol fades out, which leaves the remaining samples of the cyclic prefix
+    /// to absorb the delay spread of the channel.
+    /// Must not exceed the length of the cyclic prefix, `0` disables windowing.
+    /// The demodulator has to be configured with the same value.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    /// use realfft::RealFftPlanner;
+    ///
+    /// let payload: Vec<u8> = (0..24 * 40).map(|i: u32| (i.wrapping_mul(2654435761) >> 13) as u8).collect();
+    ///
+    /// // share of the energy within a quarter subcarrier spacing of DC and nyquist, outside the used subcarriers
+    /// let out_of_band = |mut samples: Vec<f32>| {
+    ///     samples.resize(1 << 15, 0.0);
+    ///     let fft = RealFftPlanner::<f32>::new().plan_fft_forward(samples.len());
+    ///     let mut spectrum = fft.make_output_vec();
+    ///     fft.process(&mut samples, &mut spectrum).unwrap();
+    ///
+    ///     let guard = spectrum.len() / 64 / 4;
+    ///     let total: f32 = spectrum.iter().map(|bin| bin.norm_sqr()).sum();
+    ///     let edges: f32 = spectrum[..guard]
+    ///         .iter()
+    ///         .chain(&spectrum[spectrum.len() - guard..])
+    ///         .map(|bin| bin.norm_sqr())
+    ///         .sum();
+    ///     edges / total
+    /// };
+    ///
+    /// let rectangular = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(32),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    /// let windowed = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(32),
+    ///     window_length: 32,
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    ///
+    /// let (rectangular_samples, _) = rectangular.modulate_packet(&payload).unwrap();
+    /// let (windowed_samples, padding) = windowed.modulate_packet(&payload).unwrap();
+    /// assert_eq!(windowed_samples.len(), rectangular_samples.len() + 32);
+    /// assert!(out_of_band(windowed_samples.clone()) < 0.5 * out_of_band(rectangular_samples));
+    ///
+    /// // the overlap stays within the cyclic prefix, so the demodulator is not affected
+    /// let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(32),
+    ///     window_length: 32,
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    /// assert_eq!(demodulator.demodulate_packet(&windowed_samples, padding), payload);
+    /// ```
+    ///
+    /// Over a multipath channel the part of the cyclic prefix left by the window still keeps the symbols apart,
+    /// while symbols which are just concatenated interfere with each other:
+    /// ```
+    /// use software_modem::metrics::bit_error_rate;
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    ///
+    /// let payload: Vec<u8> = (0..24 * 40).map(|i: u32| (i.wrapping_mul(2654435761) >> 13) as u8).collect();
+    ///
+    /// let bit_errors = |cyclic_prefix_length: u32, window_length: u32| {
+    ///     let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///         num_subcarriers: 64,
+    ///         cyclic_prefix: CyclicPrefix::Samples(cyclic_prefix_length),
+    ///         window_length,
+    ///         ..Default::default()
+    ///     })
+    ///     .unwrap();
+    ///     let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+    ///         num_subcarriers: 64,
+    ///         cyclic_prefix: CyclicPrefix::Samples(cyclic_prefix_length),
+    ///         window_length,
+    ///         ..Default::default()
+    ///     })
+    ///     .unwrap();
+    ///
+    ///     // a second path arriving 4 samples after the direct one
+    ///     let (samples, padding) = modulator.modulate_packet(&payload).unwrap();
+    ///     let received: Vec<f32> = (0..samples.len())
+    ///         .map(|n| samples[n] + 0.5 * if n >= 4 { samples[n - 4] } else { 0.0 })
+    ///         .collect();
+    ///
+    ///     bit_error_rate(&payload, &demodulator.demodulate_packet(&received, padding))
+    /// };
+    ///
+    /// assert!(bit_errors(0, 0) > 0.05);
+    /// assert_eq!(bit_errors(32, 16), 0.0);
+    /// ```
+    pub window_length: u32,
+    /// Number of silent samples [modulate_packet](OFDMModulator::modulate_packet) appends after every packet.
+    ///
+    /// The gap follows the fade out of the last symbol and lets the automatic gain control
+    /// and the synchronization of the receiver settle before the next packet, e.g. in continuous audio playback.
+    /// A packet without symbols, i.e. an empty payload, gets no gap either.
+    /// The packets of [modulate_framed_packet](OFDMModulator::modulate_framed_packet)
+    /// and [modulate_packet_with_header](OFDMModulator::modulate_packet_with_header) end with the gap as well,
+    /// [modulate_iq](OFDMModulator::modulate_iq) does not add one.
+    /// Set [OFDMDemodulatorConfig::trailing_gap](crate::ofdm::demodulator::OFDMDemodulatorConfig::trailing_gap)
+    /// to the same length to demodulate the packets, see [generate_gap](OFDMModulator::generate_gap) for an example.
+    pub trailing_gap: u32,
+    /// Interval for pilot subcarriers.
+    ///
+    /// Inserts pilot subcarriers every `pilot_subcarrier_every` subcarrier.
+    /// Only the subcarriers between DC and nyquist are used, so the interval must be between 2 and `num_subcarriers - 1`.
+    /// Ignored if [pilot_subcarrier_indices](OFDMModulatorConfig::pilot_subcarrier_indices) are configured.
+    #[default(4)]
+    pub pilot_subcarrier_every: u32,
+    /// Subcarriers carrying pilots, taking precedence over `pilot_subcarrier_every` if not empty.
+    ///
+    /// Allows pilot layouts beyond a fixed interval, e.g. the ones of established standards.
+    /// Every index must lie between DC and nyquist, i.e. in `1..num_subcarriers`, the order does not matter.
+    /// All active subcarriers which are not listed carry data.
+    /// The demodulator has to be configured with the same indices.
+    ///
+    /// # Example
+    /// ```
+    /// use realfft::RealFftPlanner;
+    /// use realfft::num_complex::Complex32;
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    ///
+    /// // the pilots of IEEE 802.11a at ±7 and ±21 on a grid of 64 subcarriers, -k being 64 - k
+    /// let pilot_subcarrier_indices = vec![7, 21, 43, 57];
+    /// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(4),
+    ///     pilot_subcarrier_indices: pilot_subcarrier_indices.clone(),
+    ///     pilot_sequence: vec![Complex32::new(0.0, 3.0); 4],
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    /// let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(4),
+    ///     pilot_subcarrier_indices: pilot_subcarrier_indices.clone(),
+    ///     pilot_sequence: vec![Complex32::new(0.0, 3.0); 4],
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    /// assert_eq!(modulator.num_pilot_subcarriers(), 4);
+    /// assert_eq!(modulator.num_data_subcarriers(), 59);
+    ///
+    /// let data: Vec<u8> = (0..modulator.bytes_per_symbol()).map(|i| (i * 37) as u8).collect();
+    /// let mut symbol = vec![0.0; modulator.get_symbol_length()];
+    /// modulator.modulate_buffer_as_symbol(&data, &mut symbol).unwrap();
+    /// assert_eq!(demodulator.demodulate_symbol_from_buffer(&symbol), data);
+    ///
+    /// let fft = RealFftPlanner::<f32>::new().plan_fft_forward(128);
+    /// let mut spectrum = fft.make_output_vec();
+    /// fft.process(&mut symbol.clone()[4..], &mut spectrum).unwrap();
+    ///
+    /// // the imaginary pilots sit exactly on the listed subcarriers, the data points are never imaginary
+    /// for (index, value) in spectrum.iter().enumerate().take(64).skip(1) {
+    ///     let is_pilot = pilot_subcarrier_indices.contains(&(index as u32));
+    ///     assert_eq!(value.re.abs() < 1e-3 && value.im > 1.0, is_pilot, "subcarrier {index}");
+    /// }
+    /// ```
+    pub pilot_subcarrier_indices: Vec<u32>,
+    /// Subcarriers which carry neither data nor pilots, e.g. to form guard bands.
+    ///
+    /// DC (index `0`) and nyquist (index `num_subcarriers`) are always null,
+    /// the listed subcarriers are removed from the data and the pilot subcarriers.
+    /// Configured pilot values are assigned to the remaining pilot subcarriers in order.
+    /// The demodulator has to be configured with the same indices.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    /// use realfft::RealFftPlanner;
+    ///
+    /// // guard bands of four subcarriers at both edges
+    /// let null_subcarrier_indices = vec![1, 2, 3, 4, 60, 61, 62, 63];
+    /// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(4),
+    ///     null_subcarrier_indices: null_subcarrier_indices.clone(),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    /// assert_eq!(modulator.num_pilot_subcarriers(), 13);
+    /// assert_eq!(modulator.num_data_subcarriers(), 42);
+    ///
+    /// let data = vec![0xff; modulator.bytes_per_symbol()];
+    /// let mut symbol = vec![0.0; modulator.get_symbol_length()];
+    /// modulator.modulate_buffer_as_symbol(&data, &mut symbol).unwrap();
+    ///
+    /// let fft = RealFftPlanner::<f32>::new().plan_fft_forward(128);
+    /// let mut spectrum = fft.make_output_vec();
+    /// fft.process(&mut symbol[4..], &mut spectrum).unwrap();
+    ///
+    /// for index in [0, 64].into_iter().chain(null_subcarrier_indices) {
+    ///     assert!(spectrum[index as usize].norm() < 1e-3);
+    /// }
+    /// // data subcarriers carry about 500 after the unnormalized transforms
+    /// assert!(spectrum[5].norm() > 100.0);
+    /// ```
+    pub null_subcarrier_indices: Vec<u32>,
+    /// Ranges of subcarriers which are not transmitted on, e.g. to spare a band used by another signal.
+    ///
+    /// Every range `(first, last)` masks the subcarriers `first` to `last`, both included.
+    /// Like the [null subcarriers](OFDMModulatorConfig::null_subcarrier_indices),
+    /// the masked subcarriers are removed after the pilots were placed, so the other pilots keep their positions,
+    /// and the payload capacity shrinks by the masked data subcarriers.
+    /// The demodulator has to be configured with the same mask.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    /// use realfft::RealFftPlanner;
+    ///
+    /// let unmasked = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(4),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    /// // keep the band of the subcarriers 20 to 30 free
+    /// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(4),
+    ///     spectral_mask: vec![(20, 30)],
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    /// let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(4),
+    ///     spectral_mask: vec![(20, 30)],
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    /// // the band held 3 pilots and 8 data subcarriers
+    /// assert_eq!(modulator.num_pilot_subcarriers(), unmasked.num_pilot_subcarriers() - 3);
+    /// assert_eq!(modulator.num_data_subcarriers(), unmasked.num_data_subcarriers() - 8);
+    /// assert!(modulator.bytes_per_symbol() < unmasked.bytes_per_symbol());
+    ///
+    /// let data: Vec<u8> = (0..modulator.bytes_per_symbol() as u8).collect();
+    /// let mut symbol = vec![0.0; modulator.get_symbol_length()];
+    /// modulator.modulate_buffer_as_symbol(&data, &mut symbol).unwrap();
+    /// assert_eq!(demodulator.demodulate_symbol_from_buffer(&symbol), data);
+    ///
+    /// let fft = RealFftPlanner::<f32>::new().plan_fft_forward(128);
+    /// let mut spectrum = fft.make_output_vec();
+    /// fft.process(&mut symbol[4..], &mut spectrum).unwrap();
+    ///
+    /// for index in 20..=30 {
+    ///     assert!(spectrum[index].norm() < 1e-3);
+    /// }
+    /// for index in (1..20).chain(31..64) {
+    ///     assert!(spectrum[index].norm() > 1.0);
+    /// }
+    /// ```
+    pub spectral_mask: Vec<(u32, u32)>,
     pub qam_order: QAMOrder,
+    /// QAM order of every data subcarrier in ascending subcarrier order, also known as adaptive bit loading.
+    ///
+    /// Allows denser constellations on the subcarriers with a good SNR and robust ones on the weak subcarriers.
+    /// Must hold one order per [data subcarrier](OFDMModulator::num_data_subcarriers),
+    /// the subcarriers carrying the selective mapping index are not included.
+    /// If empty, every data subcarrier uses `qam_order`.
+    /// The demodulator has to use the same bit loading.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::error::ConfigError;
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    /// use software_modem::qam::QAMOrder;
+    ///
+    /// // QAM-16 on the lower half of the 48 data subcarriers and QAM-4 on the upper half
+    /// let bit_loading: Vec<QAMOrder> = (0..48)
+    ///     .map(|i| if i < 24 { QAMOrder::QAM16 } else { QAMOrder::QAM4 })
+    ///     .collect();
+    ///
+    /// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(4),
+    ///     bit_loading: bit_loading.clone(),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    /// assert_eq!(modulator.bits_per_symbol(), 24 * 4 + 24 * 2);
+    /// assert_eq!(modulator.bytes_per_symbol(), 18);
+    ///
+    /// let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(4),
+    ///     bit_loading,
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    ///
+    /// let payload: Vec<u8> = (0..100).map(|i| (i * 37 % 251) as u8).collect();
+    /// let (samples, padding) = modulator.modulate_packet(&payload).unwrap();
+    /// assert_eq!(samples.len(), 6 * modulator.get_symbol_length());
+    /// assert_eq!(demodulator.demodulate_packet(&samples, padding), payload);
+    ///
+    /// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(4),
+    ///     bit_loading: vec![QAMOrder::QAM4; 40],
+    ///     ..Default::default()
+    /// });
+    /// assert_eq!(
+    ///     modulator.err(),
+    ///     Some(ConfigError::BitLoadingLengthMismatch { expected: 48, actual: 40 })
+    /// );
+    /// ```
+    pub bit_loading: Vec<QAMOrder>,
+    /// Order in which the data is laid onto the data subcarriers, see [SubcarrierMapping].
+    pub subcarrier_mapping: SubcarrierMapping,
+    /// Values transmitted on the pilot subcarriers, indexed by pilot position.
+    ///
+    /// Must hold at least one value per pilot subcarrier, additional values are ignored.
+    /// If empty, every pilot subcarrier carries [DEFAULT_PILOT_VALUE](crate::ofdm::DEFAULT_PILOT_VALUE) (`1 + 0i`).
+    pub pilot_sequence: Vec<Complex<T>>,
+    /// Invert the pilots of the symbols of a packet following the [pilot polarity](crate::ofdm::pilot_polarity) sequence.
+    ///
+    /// With the same pilots on every symbol, a receiver can not tell a phase jump of 180° from the data.
+    /// The deterministic sign pattern lets it detect such flips, while the receiver still estimates
+    /// the channel and tracks the phase from the pilots of every symbol.
+    /// The sequence starts at the first symbol of every packet, so single symbols, e.g. of
+    /// [modulate_buffer_as_symbol](OFDMModulator::modulate_buffer_as_symbol), keep their pilots.
+    /// The demodulator has to be configured with the same value.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::metrics::bit_error_rate;
+    /// use software_modem::ofdm::{CyclicPrefix, pilot_polarity};
+    /// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    /// use realfft::RealFftPlanner;
+    ///
+    /// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(16),
+    ///     pilot_polarity: true,
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    ///
+    /// let payload: Vec<u8> = (0..20 * 24).map(|i| (i * 37 % 251) as u8).collect();
+    /// let (mut samples, padding) = modulator.modulate_packet(&payload).unwrap();
+    ///
+    /// // the first pilot sits on subcarrier 4 of every symbol
+    /// let fft = RealFftPlanner::<f32>::new().plan_fft_forward(128);
+    /// let pilots: Vec<f32> = samples
+    ///     .chunks(modulator.get_symbol_length())
+    ///     .map(|symbol| {
+    ///         let mut spectrum = fft.make_output_vec();
+    ///         fft.process(&mut symbol[16..].to_vec(), &mut spectrum).unwrap();
+    ///         spectrum[4].re / 128.0
+    ///     })
+    ///     .collect();
+    /// assert!(pilots[3] > 0.0 && pilots[4] < 0.0);
+    /// for (i, pilot) in pilots.iter().enumerate() {
+    ///     assert!((pilot - pilot_polarity(i) as f32).abs() < 1e-4);
+    /// }
+    ///
+    /// // the pilots of every symbol still track a residual offset which turns the phase by 0.4 radians
+    /// let demodulator = |pilot_polarity| {
+    ///     OFDMDemodulator::new(OFDMDemodulatorConfig {
+    ///         num_subcarriers: 64,
+    ///         cyclic_prefix: CyclicPrefix::Samples(16),
+    ///         pilot_polarity,
+    ///         ..Default::default()
+    ///     })
+    ///     .unwrap()
+    /// };
+    /// let cfo = 0.4 / samples.len() as f32;
+    /// demodulator(true).correct_cfo(&mut samples, -cfo);
+    ///
+    /// let received = demodulator(true).demodulate_packet(&samples, padding);
+    /// for (sent, received) in payload.chunks(24).zip(received.chunks(24)) {
+    ///     assert_eq!(bit_error_rate(sent, received), 0.0);
+    /// }
+    ///
+    /// // a receiver expecting the same pilots on every symbol takes the inverted ones for a flipped channel
+    /// assert_ne!(demodulator(false).demodulate_packet(&samples, padding), payload);
+    /// ```
+    pub pilot_polarity: bool,
+    /// Protect packets with the [convolutional code](crate::fec).
+    ///
+    /// Halves the payload capacity, but allows the demodulator to correct bit errors.
+    /// Applies to the payload of every packet, [modulate_packet](OFDMModulator::modulate_packet)
+    /// as well as framed packets, packets with a header (but not the header itself), [build_frame](OFDMModulator::build_frame)
+    /// and [modulate_iq](OFDMModulator::modulate_iq), the demodulator has to enable it as well.
+    pub convolutional_coding: bool,
+    /// Protect packets with the [Reed-Solomon code](crate::fec::reed_solomon) as well, requires the `rs` feature.
+    ///
+    /// The outer code corrects up to 16 wrong bytes per block of 255, which the Viterbi decoder leaves behind
+    /// as bursts when it fails, at the price of 32 parity bytes per 223 data bytes.
+    /// It is applied before the convolutional code, but also works on its own.
+    /// Applies to the payload of every packet, [modulate_packet](OFDMModulator::modulate_packet)
+    /// as well as framed packets, packets with a header (but not the header itself), [build_frame](OFDMModulator::build_frame)
+    /// and [modulate_iq](OFDMModulator::modulate_iq), the demodulator has to enable it as well.
+    ///
+    /// # Example
+    /// ```
+    /// # #[cfg(feature = "rs")]
+    /// # {
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    ///
+    /// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(4),
+    ///     reed_solomon: true,
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    /// let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(4),
+    ///     reed_solomon: true,
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    ///
+    /// let payload: Vec<u8> = (0..100).map(|i| (i * 37 % 251) as u8).collect();
+    /// let (mut samples, padding) = modulator.modulate_packet(&payload).unwrap();
+    /// assert_eq!(samples.len(), (100 + 32_usize).div_ceil(24) * modulator.get_symbol_length());
+    ///
+    /// // strong tones jam the data subcarriers 10 and 21, a few bytes of every symbol
+    /// let rms = (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt();
+    /// for (n, sample) in samples.iter_mut().enumerate() {
+    ///     for subcarrier in [10, 21] {
+    ///         let phase = std::f32::consts::PI * (subcarrier * n) as f32 / 64.0;
+    ///         *sample += 2.0 * rms * (phase + subcarrier as f32).cos();
+    ///     }
+    /// }
+    ///
+    /// let uncoded = OFDMDemodulator::new(OFDMDemodulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(4),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    /// assert_ne!(uncoded.demodulate_packet(&samples, padding)[..100], payload);
+    /// assert_eq!(demodulator.demodulate_packet(&samples, padding), payload);
+    /// # }
+    /// ```
+    #[cfg(feature = "rs")]
+    pub reed_solomon: bool,
+    /// [Scramble](crate::scrambler) packets to avoid long runs of identical symbols.
+    ///
+    /// Applies to the payload of every packet, [modulate_packet](OFDMModulator::modulate_packet)
+    /// as well as framed packets, packets with a header (but not the header itself), [build_frame](OFDMModulator::build_frame)
+    /// and [modulate_iq](OFDMModulator::modulate_iq), the demodulator has to enable it as well.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    ///
+    /// let peak_to_average = |samples: &[f32]| {
+    ///     let peak = samples.iter().map(|s| s * s).fold(0.0, f32::max);
+    ///     let average = samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32;
+    ///     peak / average
+    /// };
+    ///
+    /// let config = || OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(4),
+    ///     ..Default::default()
+    /// };
+    /// let plain = OFDMModulator::new(config()).unwrap();
+    /// let scrambled = OFDMModulator::new(OFDMModulatorConfig { scrambling: true, ..config() }).unwrap();
+    ///
+    /// // the same value on every subcarrier adds up to a single peak per symbol
+    /// let payload = vec![0u8; 240];
+    /// let (plain_samples, _) = plain.modulate_packet(&payload).unwrap();
+    /// let (scrambled_samples, padding) = scrambled.modulate_packet(&payload).unwrap();
+    /// assert!(peak_to_average(&scrambled_samples) < peak_to_average(&plain_samples) / 4.0);
+    ///
+    /// let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(4),
+    ///     scrambling: true,
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    /// assert_eq!(demodulator.demodulate_packet(&scrambled_samples, padding), payload);
+    /// ```
+    pub scrambling: bool,
+    /// [Interleave](crate::interleaver) the bits of every symbol across the data subcarriers.
+    ///
+    /// Spreads the bit errors caused by a fade of adjacent subcarriers over the symbol,
+    /// which allows the convolutional code to correct them.
+    /// Applies to the payload of every packet, [modulate_packet](OFDMModulator::modulate_packet)
+    /// as well as framed packets, packets with a header (but not the header itself), [build_frame](OFDMModulator::build_frame)
+    /// and [modulate_iq](OFDMModulator::modulate_iq), the demodulator has to enable it as well.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    ///
+    /// let transmit = |interleaving: bool| {
+    ///     let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///         num_subcarriers: 64,
+    ///         cyclic_prefix: CyclicPrefix::Samples(4),
+    ///         convolutional_coding: true,
+    ///         interleaving,
+    ///         ..Default::default()
+    ///     })
+    ///     .unwrap();
+    ///     let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+    ///         num_subcarriers: 64,
+    ///         cyclic_prefix: CyclicPrefix::Samples(4),
+    ///         convolutional_coding: true,
+    ///         interleaving,
+    ///         ..Default::default()
+    ///     })
+    ///     .unwrap();
+    ///
+    ///     let payload: Vec<u8> = (0..120).map(|i| (i * 37 % 251) as u8).collect();
+    ///     let (mut samples, padding) = modulator.modulate_packet(&payload).unwrap();
+    ///
+    ///     // strong tones jam the data subcarriers 5 to 11 around the pilot on subcarrier 8
+    ///     let rms = (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt();
+    ///     for (n, sample) in samples.iter_mut().enumerate() {
+    ///         for subcarrier in [5, 6, 7, 9, 10, 11] {
+    ///             let phase = std::f32::consts::PI * (subcarrier * n) as f32 / 64.0;
+    ///             *sample += 2.0 * rms * (phase + subcarrier as f32).cos();
+    ///         }
+    ///     }
+    ///
+    ///     demodulator.demodulate_packet(&samples, padding) == payload
+    /// };
+    ///
+    /// assert!(!transmit(false));
+    /// assert!(transmit(true));
+    /// ```
+    pub interleaving: bool,
+    /// Number of phase sequences [selective mapping](crate::ofdm::slm) chooses from to lower the peak power of a symbol.
+    ///
+    /// `1` disables selective mapping.
+    /// Otherwise the lowest `ceil(log2(num_slm_candidates))` data subcarriers carry the index of the chosen candidate,
+    /// which lowers the capacity of a symbol, and the demodulator has to use the same number of candidates.
+    /// Every candidate costs one inverse FFT per symbol.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::metrics::papr_db;
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    ///
+    /// let config = || OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(4),
+    ///     scrambling: true,
+    ///     ..Default::default()
+    /// };
+    /// let plain = OFDMModulator::new(config()).unwrap();
+    /// let slm = OFDMModulator::new(OFDMModulatorConfig { num_slm_candidates: 8, ..config() }).unwrap();
+    ///
+    /// let payload: Vec<u8> = (0..2000u32).map(|i| (i * 37 % 251) as u8).collect();
+    /// let (plain_samples, _) = plain.modulate_packet(&payload).unwrap();
+    /// let (slm_samples, padding) = slm.modulate_packet(&payload).unwrap();
+    /// assert!(papr_db(&slm_samples) < papr_db(&plain_samples) - 2.0);
+    ///
+    /// let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(4),
+    ///     scrambling: true,
+    ///     num_slm_candidates: 8,
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    /// assert_eq!(demodulator.demodulate_packet(&slm_samples, padding), payload);
+    /// ```
+    #[default(1)]
+    pub num_slm_candidates: u32,
+    /// Clip the output at this multiple of its RMS, a simpler way to limit the peaks than selective mapping.
+    ///
+    /// The [clipping](crate::dsp::clip_peaks) distorts the subcarriers, so lower ratios cost more bit errors.
+    /// Applies to the packets of [modulate_packet](OFDMModulator::modulate_packet) and the functions built on it,
+    /// with the RMS taken over the header as well in [modulate_packet_with_header](OFDMModulator::modulate_packet_with_header)
+    /// and over the whole frame in [build_frame](OFDMModulator::build_frame), but not to [modulate_iq](OFDMModulator::modulate_iq).
+    /// `None` disables clipping.
+    ///
+    /// # Example
+    /// ```
+    /// use rand::{SeedableRng, rngs::StdRng};
+    /// use software_modem::channel::add_awgn;
+    /// use software_modem::metrics::bit_error_rate;
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    ///
+    /// let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(4),
+    ///     scrambling: true,
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    /// let payload: Vec<u8> = (0..2400u32).map(|i| (i * 37 % 251) as u8).collect();
+    ///
+    /// let transmit = |clipping_ratio: Option<f32>| {
+    ///     let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///         num_subcarriers: 64,
+    ///         cyclic_prefix: CyclicPrefix::Samples(4),
+    ///         scrambling: true,
+    ///         clipping_ratio,
+    ///         ..Default::default()
+    ///     })
+    ///     .unwrap();
+    ///     let (samples, padding) = modulator.modulate_packet(&payload).unwrap();
+    ///
+    ///     let mut received = samples.clone();
+    ///     add_awgn(&mut received, 25.0, &mut StdRng::seed_from_u64(1));
+    ///     let ber = bit_error_rate(&payload, &demodulator.demodulate_packet(&received, padding));
+    ///     (samples, ber)
+    /// };
+    ///
+    /// let (plain, plain_ber) = transmit(None);
+    /// let (clipped, clipped_ber) = transmit(Some(2.0));
+    ///
+    /// // the peaks stay within twice the RMS of the unclipped signal
+    /// let rms = (plain.iter().map(|s| s * s).sum::<f32>() / plain.len() as f32).sqrt();
+    /// let peak = |samples: &[f32]| samples.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+    /// assert!(peak(&plain) > 3.0 * rms);
+    /// assert!(peak(&clipped) <= 2.0 * rms);
+    /// // at the price of more bit errors
+    /// assert!(clipped_ber > plain_ber);
+    /// assert!(clipped_ber < 0.05);
+    /// ```
+    pub clipping_ratio: Option<f32>,
+    /// Quantize the output to this many bits, e.g. to study the effect of the bit depth of the DAC.
+    ///
+    /// The full scale of the [quantization](crate::dsp::quantize) is the peak of the output after clipping,
+    /// as if the packet were scaled to the range of the converter.
+    /// Fewer bits add more quantization noise to the subcarriers.
+    /// Applies to the packets of [modulate_packet](OFDMModulator::modulate_packet) and the functions built on it,
+    /// e.g. [modulate_packet_with_header](OFDMModulator::modulate_packet_with_header),
+    /// where the header is quantized together with the data, and the whole frame of
+    /// [build_frame](OFDMModulator::build_frame), but not to [modulate_iq](OFDMModulator::modulate_iq).
+    /// `None` keeps the full precision.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    ///
+    /// let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(4),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    /// let payload: Vec<u8> = (0..240u32).map(|i| (i * 37 % 251) as u8).collect();
+    ///
+    /// let mean_evm = |quantization_bits: u32| {
+    ///     let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///         num_subcarriers: 64,
+    ///         cyclic_prefix: CyclicPrefix::Samples(4),
+    ///         quantization_bits: Some(quantization_bits),
+    ///         ..Default::default()
+    ///     })
+    ///     .unwrap();
+    ///     let (samples, padding) = modulator.modulate_packet(&payload).unwrap();
+    ///     assert_eq!(demodulator.demodulate_packet(&samples, padding), payload);
+    ///
+    ///     let symbols = samples.chunks(demodulator.get_symbol_length());
+    ///     symbols.clone().map(|symbol| demodulator.evm(symbol).0).sum::<f32>() / symbols.len() as f32
+    /// };
+    ///
+    /// // 4 bits more lower the EVM by about 24 dB
+    /// let (evm_8, evm_12) = (mean_evm(8), mean_evm(12));
+    /// assert!(evm_8 < 3.0);
+    /// assert!(evm_8 > 8.0 * evm_12);
+    ///
+    /// // the preamble, the training symbol and the header of a frame share the grid of the data
+    /// let frame = |quantization_bits: Option<u32>| {
+    ///     let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///         num_subcarriers: 64,
+    ///         cyclic_prefix: CyclicPrefix::Samples(4),
+    ///         quantization_bits,
+    ///         ..Default::default()
+    ///     })
+    ///     .unwrap();
+    ///     modulator.build_frame(&payload).unwrap()
+    /// };
+    /// let full_scale = frame(None).iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+    /// let step = full_scale / 128.0;
+    /// for sample in frame(Some(8)) {
+    ///     assert!(sample.abs() <= full_scale);
+    ///     assert!((sample / step - (sample / step).round()).abs() < 1e-3);
+    /// }
+    /// ```
+    pub quantization_bits: Option<u32>,
+    /// Scale the output to an RMS of `1.0`, independent of the number of subcarriers and the QAM orders.
+    ///
+    /// The inverse FFT sums up all subcarriers, so without normalization the output level grows
+    /// with the number of active subcarriers and their constellation energy.
+    /// With normalization, a symbol with every data subcarrier in use has a mean power of `1.0` on average,
+    /// which keeps the level predictable for mixing and [writing WAV files](crate::io).
+    /// The same factor applies to every symbol, the [preamble](OFDMModulator::generate_preamble)
+    /// and the [long training symbol](OFDMModulator::generate_long_training_symbol),
+    /// so their levels relative to each other stay the same.
+    ///
+    /// The demodulator needs no setting, the pilots equalize the gain of the normalization like the one of the channel.
+    /// [estimate_gain](crate::ofdm::demodulator::OFDMDemodulator::estimate_gain) however compares with the
+    /// unnormalized level and reports the normalization as part of the gain.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    ///
+    /// let rms = |samples: &[f32]| (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt();
+    /// let payload: Vec<u8> = (0..2400).map(|i| (i * 37 % 251) as u8).collect();
+    /// let transmit = |null_subcarrier_indices: Vec<u32>, normalize_output: bool| {
+    ///     let config = || OFDMModulatorConfig {
+    ///         num_subcarriers: 64,
+    ///         cyclic_prefix: CyclicPrefix::Samples(16),
+    ///         null_subcarrier_indices: null_subcarrier_indices.clone(),
+    ///         scrambling: true,
+    ///         normalize_output,
+    ///         ..Default::default()
+    ///     };
+    ///     let modulator = OFDMModulator::new(config()).unwrap();
+    ///     let (samples, padding) = modulator.modulate_packet(&payload).unwrap();
+    ///
+    ///     let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+    ///         num_subcarriers: 64,
+    ///         cyclic_prefix: CyclicPrefix::Samples(16),
+    ///         null_subcarrier_indices,
+    ///         scrambling: true,
+    ///         ..Default::default()
+    ///     })
+    ///     .unwrap();
+    ///     assert_eq!(demodulator.demodulate_packet(&samples, padding), payload);
+    ///     rms(&samples)
+    /// };
+    ///
+    /// // all subcarriers against only 16 of them
+    /// let narrow: Vec<u32> = (1..24).chain(41..64).collect();
+    /// assert!(transmit(vec![], false) > 1.5 * transmit(narrow.clone(), false));
+    ///
+    /// assert!((transmit(vec![], true) - 1.0).abs() < 0.05);
+    /// assert!((transmit(narrow, true) - 1.0).abs() < 0.05);
+    /// ```
+    pub normalize_output: bool,
+    /// Number of times every symbol of a packet is transmitted.
+    ///
+    /// The demodulator averages the copies of a symbol before demodulating it.
+    /// The signal adds up coherently while the noise does not, which gains `10 * log10(repetition)` dB of SNR
+    /// at the price of a `repetition` times lower data rate.
+    /// A frequency offset rotates the copies against each other, so it has to be
+    /// [corrected](crate::ofdm::demodulator::OFDMDemodulator::correct_cfo) before demodulating.
+    /// Applies to [modulate_packet](OFDMModulator::modulate_packet) and [modulate_iq](OFDMModulator::modulate_iq),
+    /// the demodulator has to be configured with the same value.
+    ///
+    /// # Example
+    /// ```
+    /// use rand::{SeedableRng, rngs::StdRng};
+    /// use software_modem::channel::add_awgn;
+    /// use software_modem::metrics::bit_error_rate;
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    /// use software_modem::qam::QAMOrder;
+    ///
+    /// let payload: Vec<u8> = (0..480u32).map(|i| (i * 37 % 251) as u8).collect();
+    ///
+    /// let bit_errors = |repetition: u32| {
+    ///     let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///         num_subcarriers: 64,
+    ///         cyclic_prefix: CyclicPrefix::Samples(16),
+    ///         qam_order: QAMOrder::QAM4,
+    ///         repetition,
+    ///         ..Default::default()
+    ///     })
+    ///     .unwrap();
+    ///     let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+    ///         num_subcarriers: 64,
+    ///         cyclic_prefix: CyclicPrefix::Samples(16),
+    ///         qam_order: QAMOrder::QAM4,
+    ///         repetition,
+    ///         ..Default::default()
+    ///     })
+    ///     .unwrap();
+    ///
+    ///     let (mut samples, padding) = modulator.modulate_packet(&payload).unwrap();
+    ///     assert_eq!(samples.len(), 40 * repetition as usize * modulator.get_symbol_length());
+    ///
+    ///     add_awgn(&mut samples, 8.0, &mut StdRng::seed_from_u64(3));
+    ///     bit_error_rate(&payload, &demodulator.demodulate_packet(&samples, padding))
+    /// };
+    ///
+    /// // 6 dB more SNR with four copies of every symbol
+    /// assert!(bit_errors(1) > 0.01);
+    /// assert_eq!(bit_errors(4), 0.0);
+    /// ```
+    #[default(1)]
+    pub repetition: u32,
     /// Optional FFT implementation/planner to use.
     ///
-    /// If `None`, a default FFT planner will be used.
-    pub fft: Option<Arc<dyn ComplexToReal<f32>>>,
+    /// If `None`, the FFT of [plan_default_fft] is used.
+    /// A configured FFT has to transform `2 * num_subcarriers` samples, other lengths are rejected.
+    /// Not (de)serialized with the `serde` feature, a deserialized configuration always uses the default planner.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::error::ConfigError;
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig, plan_default_fft};
+    /// use realfft::RealFftPlanner;
+    ///
+    /// let fft = plan_default_fft(64);
+    /// assert_eq!(fft.len(), 128);
+    /// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     fft: Some(fft),
+    ///     ..Default::default()
+    /// });
+    /// assert!(modulator.is_ok());
+    ///
+    /// // an FFT of num_subcarriers instead of 2 * num_subcarriers samples
+    /// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     fft: Some(RealFftPlanner::new().plan_fft_inverse(64)),
+    ///     ..Default::default()
+    /// });
+    /// assert_eq!(
+    ///     modulator.err(),
+    ///     Some(ConfigError::FftLengthMismatch { expected: 128, actual: 64 })
+    /// );
+    /// ```
+    ///
+    /// A naive inverse DFT makes the samples independent of the optimizations of the FFT library,
+    /// so a test can check exact values:
+    /// ```
+    /// use std::f64::consts::PI;
+    /// use std::sync::Arc;
+    ///
+    /// use realfft::{ComplexToReal, FftError, num_complex::Complex32};
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    ///
+    /// /// `x[n] = sum of X[k] * e^(j2πkn/N)` over all N bins, the negative ones mirrored, unnormalized like realfft.
+    /// struct NaiveInverseDft(usize);
+    ///
+    /// impl ComplexToReal<f32> for NaiveInverseDft {
+    ///     fn process(&self, input: &mut [Complex32], output: &mut [f32]) -> Result<(), FftError> {
+    ///         self.process_with_scratch(input, output, &mut [])
+    ///     }
+    ///
+    ///     fn process_with_scratch(
+    ///         &self,
+    ///         input: &mut [Complex32],
+    ///         output: &mut [f32],
+    ///         _scratch: &mut [Complex32],
+    ///     ) -> Result<(), FftError> {
+    ///         let n = self.0;
+    ///         for (t, output) in output.iter_mut().enumerate() {
+    ///             let mut sum = input[0].re as f64 + input[n / 2].re as f64 * if t % 2 == 0 { 1.0 } else { -1.0 };
+    ///             for (k, bin) in input.iter().enumerate().take(n / 2).skip(1) {
+    ///                 let angle = 2.0 * PI * ((k * t) % n) as f64 / n as f64;
+    ///                 sum += 2.0 * (bin.re as f64 * angle.cos() - bin.im as f64 * angle.sin());
+    ///             }
+    ///             *output = sum as f32;
+    ///         }
+    ///         Ok(())
+    ///     }
+    ///
+    ///     fn get_scratch_len(&self) -> usize { 0 }
+    ///     fn len(&self) -> usize { self.0 }
+    ///     fn make_input_vec(&self) -> Vec<Complex32> { vec![Complex32::default(); self.0 / 2 + 1] }
+    ///     fn make_output_vec(&self) -> Vec<f32> { vec![0.0; self.0] }
+    ///     fn make_scratch_vec(&self) -> Vec<Complex32> { Vec::new() }
+    /// }
+    ///
+    /// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///     num_subcarriers: 64,
+    ///     cyclic_prefix: CyclicPrefix::Samples(16),
+    ///     fft: Some(Arc::new(NaiveInverseDft(128))),
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    ///
+    /// // zero bytes put 1 + 1i on the 48 data subcarriers, next to the 15 pilots of 1
+    /// let mut symbol = vec![0.0; 144];
+    /// modulator.modulate_buffer_as_symbol(&[0; 24], &mut symbol).unwrap();
+    /// assert_eq!(symbol[16], 2.0 * (48.0 + 15.0));
+    ///
+    /// // the cyclic prefix is an exact copy of the tail of the symbol
+    /// assert_eq!(symbol[..16], symbol[128..]);
+    /// ```
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub fft: Option<Arc<dyn ComplexToReal<T>>>,
+}
+
+impl<T: Float> OFDMModulatorConfig<T> {
+    /// Returns a [builder](OFDMModulatorConfigBuilder) starting from the default configuration.
+    pub fn builder() -> OFDMModulatorConfigBuilder<T> {
+        OFDMModulatorConfigBuilder::default()
+    }
+
+    /// Derives the constants of the modulator, validating the configuration.
+    fn constants(&self) -> Result<OFDMConstants<T>, ConfigError> {
+        if let Some(fft) = &self.fft {
+            check_fft_length(fft.len(), self.num_subcarriers)?;
+        }
+        if let Some(bits) = self.quantization_bits
+            && (bits == 0 || bits > 32)
+        {
+            return Err(ConfigError::QuantizationBitsOutOfRange { bits });
+        }
+
+        OFDMConstants::new(
+            self.num_subcarriers,
+            self.pilot_subcarrier_every,
+            &self.pilot_subcarrier_indices,
+            &self.null_subcarrier_indices,
+            &self.spectral_mask,
+            &self.subcarrier_mapping,
+            self.cyclic_prefix,
+            self.window_length,
+            self.qam_order,
+            &self.bit_loading,
+            &self.pilot_sequence,
+            self.pilot_polarity,
+            self.num_slm_candidates,
+            self.repetition,
+        )
+    }
+}
+
+/// Builder for an [OFDMModulatorConfig] which validates the configuration.
+///
+/// Every setter defaults to the value of `OFDMModulatorConfig::default()`.
+///
+/// # Example
+/// ```
+/// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+/// use software_modem::qam::QAMOrder;
+///
+/// let config = OFDMModulatorConfig::builder()
+///     .num_subcarriers(64)
+///     .cyclic_prefix_length(16)
+///     .qam_order(QAMOrder::QAM4)
+///     .build()
+///     .unwrap();
+///
+/// let modulator = OFDMModulator::new(config).unwrap();
+/// assert_eq!(modulator.get_symbol_length(), 144);
+/// ```
+#[derive(Default)]
+pub struct OFDMModulatorConfigBuilder<T: Float = f32> {
+    config: OFDMModulatorConfig<T>,
+}
+
+impl<T: Float> OFDMModulatorConfigBuilder<T> {
+    /// Sets [num_subcarriers](OFDMModulatorConfig::num_subcarriers).
+    pub fn num_subcarriers(mut self, num_subcarriers: u32) -> Self {
+        self.config.num_subcarriers = num_subcarriers;
+        self
+    }
+
+    /// Sets [cyclic_prefix](OFDMModulatorConfig::cyclic_prefix).
+    pub fn cyclic_prefix(mut self, cyclic_prefix: CyclicPrefix) -> Self {
+        self.config.cyclic_prefix = cyclic_prefix;
+        self
+    }
+
+    /// Sets [cyclic_prefix](OFDMModulatorConfig::cyclic_prefix) to a number of samples.
+    pub fn cyclic_prefix_length(mut self, cyclic_prefix_length: u32) -> Self {
+        self.config.cyclic_prefix = CyclicPrefix::Samples(cyclic_prefix_length);
+        self
+    }
+
+    /// Sets [window_length](OFDMModulatorConfig::window_length).
+    pub fn window_length(mut self, window_length: u32) -> Self {
+        self.config.window_length = window_length;
+        self
+    }
+
+    /// Sets [trailing_gap](OFDMModulatorConfig::trailing_gap).
+    pub fn trailing_gap(mut self, trailing_gap: u32) -> Self {
+        self.config.trailing_gap = trailing_gap;
+        self
+    }
+
+    /// Sets [pilot_subcarrier_every](OFDMModulatorConfig::pilot_subcarrier_every).
+    pub fn pilot_subcarrier_every(mut self, pilot_subcarrier_every: u32) -> Self {
+        self.config.pilot_subcarrier_every = pilot_subcarrier_every;
+        self
+    }
+
+    /// Sets [pilot_subcarrier_indices](OFDMModulatorConfig::pilot_subcarrier_indices).
+    pub fn pilot_subcarrier_indices(mut self, pilot_subcarrier_indices: Vec<u32>) -> Self {
+        self.config.pilot_subcarrier_indices = pilot_subcarrier_indices;
+        self
+    }
+
+    /// Sets [null_subcarrier_indices](OFDMModulatorConfig::null_subcarrier_indices).
+    pub fn null_subcarrier_indices(mut self, null_subcarrier_indices: Vec<u32>) -> Self {
+        self.config.null_subcarrier_indices = null_subcarrier_indices;
+        self
+    }
+
+    /// Sets [spectral_mask](OFDMModulatorConfig::spectral_mask).
+    pub fn spectral_mask(mut self, spectral_mask: Vec<(u32, u32)>) -> Self {
+        self.config.spectral_mask = spectral_mask;
+        self
+    }
+
+    /// Sets [qam_order](OFDMModulatorConfig::qam_order).
+    pub fn qam_order(mut self, qam_order: QAMOrder) -> Self {
+        self.config.qam_order = qam_order;
+        self
+    }
+
+    /// Sets [bit_loading](OFDMModulatorConfig::bit_loading).
+    pub fn bit_loading(mut self, bit_loading: Vec<QAMOrder>) -> Self {
+        self.config.bit_loading = bit_loading;
+        self
+    }
+
+    /// Sets [subcarrier_mapping](OFDMModulatorConfig::subcarrier_mapping).
+    pub fn subcarrier_mapping(mut self, subcarrier_mapping: SubcarrierMapping) -> Self {
+        self.config.subcarrier_mapping = subcarrier_mapping;
+        self
+    }
+
+    /// Sets [pilot_sequence](OFDMModulatorConfig::pilot_sequence).
+    pub fn pilot_sequence(mut self, pilot_sequence: Vec<Complex<T>>) -> Self {
+        self.config.pilot_sequence = pilot_sequence;
+        self
+    }
+
+    /// Sets [pilot_polarity](OFDMModulatorConfig::pilot_polarity).
+    pub fn pilot_polarity(mut self, pilot_polarity: bool) -> Self {
+        self.config.pilot_polarity = pilot_polarity;
+        self
+    }
+
+    /// Sets [convolutional_coding](OFDMModulatorConfig::convolutional_coding).
+    pub fn convolutional_coding(mut self, convolutional_coding: bool) -> Self {
+        self.config.convolutional_coding = convolutional_coding;
+        self
+    }
+
+    /// Sets [reed_solomon](OFDMModulatorConfig::reed_solomon).
+    #[cfg(feature = "rs")]
+    pub fn reed_solomon(mut self, reed_solomon: bool) -> Self {
+        self.config.reed_solomon = reed_solomon;
+        self
+    }
+
+    /// Sets [scrambling](OFDMModulatorConfig::scrambling).
+    pub fn scrambling(mut self, scrambling: bool) -> Self {
+        self.config.scrambling = scrambling;
+        self
+    }
+
+    /// Sets [interleaving](OFDMModulatorConfig::interleaving).
+    pub fn interleaving(mut self, interleaving: bool) -> Self {
+        self.config.interleaving = interleaving;
+        self
+    }
+
+    /// Sets [num_slm_candidates](OFDMModulatorConfig::num_slm_candidates).
+    pub fn num_slm_candidates(mut self, num_slm_candidates: u32) -> Self {
+        self.config.num_slm_candidates = num_slm_candidates;
+        self
+    }
+
+    /// Sets [clipping_ratio](OFDMModulatorConfig::clipping_ratio).
+    pub fn clipping_ratio(mut self, clipping_ratio: f32) -> Self {
+        self.config.clipping_ratio = Some(clipping_ratio);
+        self
+    }
+
+    /// Sets [quantization_bits](OFDMModulatorConfig::quantization_bits).
+    pub fn quantization_bits(mut self, quantization_bits: u32) -> Self {
+        self.config.quantization_bits = Some(quantization_bits);
+        self
+    }
+
+    /// Sets [normalize_output](OFDMModulatorConfig::normalize_output).
+    pub fn normalize_output(mut self, normalize_output: bool) -> Self {
+        self.config.normalize_output = normalize_output;
+        self
+    }
+
+    /// Sets [repetition](OFDMModulatorConfig::repetition).
+    pub fn repetition(mut self, repetition: u32) -> Self {
+        self.config.repetition = repetition;
+        self
+    }
+
+    /// Sets [fft](OFDMModulatorConfig::fft).
+    pub fn fft(mut self, fft: Arc<dyn ComplexToReal<T>>) -> Self {
+        self.config.fft = Some(fft);
+        self
+    }
+
+    /// Validates the configuration and returns it.
+    ///
+    /// # Errors
+    /// Returns the same errors as [OFDMModulator::new].
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::error::ConfigError;
+    /// use software_modem::ofdm::modulator::OFDMModulatorConfig;
+    ///
+    /// let builder = || OFDMModulatorConfig::<f32>::builder().num_subcarriers(64);
+    ///
+    /// assert_eq!(
+    ///     OFDMModulatorConfig::<f32>::builder().build().err(),
+    ///     Some(ConfigError::NoSubcarriers)
+    /// );
+    /// assert_eq!(
+    ///     builder().cyclic_prefix_length(128).build().err(),
+    ///     Some(ConfigError::CyclicPrefixTooLong { cyclic_prefix_length: 128, symbol_length: 128 })
+    /// );
+    /// assert_eq!(
+    ///     builder().pilot_subcarrier_every(0).build().err(),
+    ///     Some(ConfigError::PilotSpacingTooSmall { pilot_subcarrier_every: 0 })
+    /// );
+    /// assert_eq!(
+    ///     builder().pilot_subcarrier_every(1).build().err(),
+    ///     Some(ConfigError::PilotSpacingTooSmall { pilot_subcarrier_every: 1 })
+    /// );
+    /// assert_eq!(
+    ///     builder().pilot_subcarrier_every(64).build().err(),
+    ///     Some(ConfigError::PilotSpacingTooLarge { pilot_subcarrier_every: 64, num_subcarriers: 64 })
+    /// );
+    /// assert_eq!(
+    ///     builder().cyclic_prefix_length(8).window_length(16).build().err(),
+    ///     Some(ConfigError::WindowTooLong { window_length: 16, cyclic_prefix_length: 8 })
+    /// );
+    /// assert_eq!(
+    ///     builder().num_slm_candidates(0).build().err(),
+    ///     Some(ConfigError::SlmCandidatesOutOfRange { num_slm_candidates: 0 })
+    /// );
+    /// assert_eq!(
+    ///     builder().num_slm_candidates(129).build().err(),
+    ///     Some(ConfigError::SlmCandidatesOutOfRange { num_slm_candidates: 129 })
+    /// );
+    ///
+    /// assert!(builder().cyclic_prefix_length(127).pilot_subcarrier_every(2).build().is_ok());
+    /// ```
+    pub fn build(self) -> Result<OFDMModulatorConfig<T>, ConfigError> {
+        self.config.constants()?;
+        Ok(self.config)
+    }
 }
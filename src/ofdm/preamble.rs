@@ -0,0 +1,153 @@
+//! This module provides the Schmidl-Cox preamble used for frame synchronization.
+//!
+//! The preamble is a training symbol which only carries energy on the even subcarriers.
+//! This makes the two halves of the symbol (without cyclic prefix) identical in the time domain,
+//! which the receiver detects by correlating the signal with a copy of itself delayed by half a symbol.
+//!
+//! Use [generate_preamble](crate::ofdm::modulator::OFDMModulator::generate_preamble) on the modulator
+//! to create the preamble and [find_frame_start](crate::ofdm::demodulator::OFDMDemodulator::find_frame_start)
+//! on the demodulator to locate it in a stream of samples.
+//! Its known energy also gives the gain of the channel, see
+//! [estimate_gain](crate::ofdm::demodulator::OFDMDemodulator::estimate_gain).
+//!
+//! The long training symbol follows the preamble to estimate the channel of every subcarrier,
+//! see [generate_long_training_symbol](crate::ofdm::modulator::OFDMModulator::generate_long_training_symbol).
+
+use std::f64::consts::SQRT_2;
+
+use realfft::num_complex::Complex;
+
+use crate::{
+    float::{Float, cast},
+    ofdm::OFDMConstants,
+};
+
+/// Energy of a window relative to the mean energy of a window of the samples,
+/// below which the [timing_metric] is zero.
+const ENERGY_FLOOR: f64 = 1e-6;
+
+/// Builds the frequency domain content of the preamble.
+///
+/// Every even subcarrier between DC and nyquist carries a pseudo random BPSK value,
+/// scaled by `sqrt(2)` to make up for the unused odd subcarriers.
+pub(super) fn preamble_spectrum<T: Float>(constants: &OFDMConstants<T>) -> Vec<Complex<T>> {
+    let num_subcarriers = constants.num_subcarriers as usize;
+    let mut spectrum = vec![Complex::default(); num_subcarriers + 1];
+
+    let mut lfsr: u8 = 0x7f;
+    for bin in (2..num_subcarriers).step_by(2) {
+        // x^7 + x^4 + 1
+        let bit = ((lfsr >> 6) ^ (lfsr >> 3)) & 1;
+        lfsr = ((lfsr << 1) | bit) & 0x7f;
+
+        let value = if bit == 0 { SQRT_2 } else { -SQRT_2 };
+        spectrum[bin] = Complex::new(cast(value), T::zero());
+    }
+
+    spectrum
+}
+
+/// Builds the frequency domain content of the long training symbol.
+///
+/// Every subcarrier which is not nulled carries a pseudo random BPSK value, including pilots.
+/// The values are scaled to the mean power of the data points, so the estimate is as accurate as the data needs it.
+pub(super) fn long_training_spectrum<T: Float>(constants: &OFDMConstants<T>) -> Vec<Complex<T>> {
+    let mut subcarrier_indices: Vec<u32> = constants
+        .data_subcarrier_indices
+        .iter()
+        .chain(&constants.pilot_subcarrier_indices)
+        .chain(&constants.slm_index_subcarrier_indices)
+        .copied()
+        .collect();
+    subcarrier_indices.sort_unstable();
+
+    let mut spectrum = vec![Complex::default(); constants.num_subcarriers as usize + 1];
+
+    // the training values get the mean power of the data points
+    let num_data_subcarriers: T = cast(constants.num_data_subcarriers as f64);
+    let gain = (constants.bit_loading.average_power() / num_data_subcarriers).sqrt();
+
+    // a different seed than the preamble
+    let mut lfsr: u8 = 0x2a;
+    for index in subcarrier_indices {
+        // x^7 + x^4 + 1
+        let bit = ((lfsr >> 6) ^ (lfsr >> 3)) & 1;
+        lfsr = ((lfsr << 1) | bit) & 0x7f;
+
+        let value = if bit == 0 { gain } else { -gain };
+        spectrum[index as usize] = Complex::new(value, T::zero());
+    }
+
+    spectrum
+}
+
+/// Computes the Schmidl-Cox timing metric for every possible start position in `samples`.
+///
+/// For a start position `d` the metric is `|P(d)|² / R(d)²` with
+/// `P(d) = Σ x[d + m] * x[d + m + L]` and `R(d) = ½ Σ (x[d + m]² + x[d + m + L]²)` summed over `m` in `0..L`,
+/// where `L` is `half_length`.
+/// Normalizing by the energy of both halves keeps the metric within `0.0..=1.0`,
+/// even at the edges of a signal surrounded by silence.
+/// Windows with less than a millionth of the mean energy of a window, e.g. in exact silence, get a metric of `0.0`.
+/// It approaches `1.0` where two identical halves of `half_length` samples start at `d`
+/// and stays close to `0.0` for noise or regular OFDM symbols.
+///
+/// Returns `samples.len() - 2 * half_length + 1` values, or none if `samples` is shorter than `2 * half_length`.
+///
+/// # Example
+/// ```
+/// use software_modem::ofdm::preamble::timing_metric;
+///
+/// let half: Vec<f32> = (0..16).map(|i| ((i * 5) % 7) as f32 - 3.0).collect();
+/// let samples = [vec![0.0; 8], half.clone(), half, vec![0.0; 8]].concat();
+///
+/// let metric = timing_metric(&samples, 16);
+/// let peak = (0..metric.len()).max_by(|&a, &b| metric[a].total_cmp(&metric[b])).unwrap();
+///
+/// assert_eq!(peak, 8);
+/// assert!((metric[peak] - 1.0).abs() < 1e-6);
+///
+/// // exact silence after a signal stays at zero
+/// let signal: Vec<f32> = (0..1000).map(|i| ((i * 37 % 101) as f32 - 50.0) * 0.37).collect();
+/// let samples = [signal, vec![0.0; 500]].concat();
+/// let metric = timing_metric(&samples, 64);
+/// assert!(metric.iter().all(|&m| (0.0..=1.0).contains(&m)));
+/// assert!(metric[1000..].iter().all(|&m| m == 0.0));
+/// ```
+pub fn timing_metric<T: Float>(samples: &[T], half_length: usize) -> Vec<T> {
+    if half_length == 0 || samples.len() < 2 * half_length {
+        return Vec::new();
+    }
+
+    let num_positions = samples.len() - 2 * half_length + 1;
+
+    // prefix sums in f64 instead of running sums, which would leave residue behind a signal,
+    // a window of exact zeros gives exactly zero energy
+    let mut correlation = Vec::with_capacity(samples.len() - half_length + 1);
+    correlation.push(0.0);
+    for m in 0..samples.len() - half_length {
+        let product = samples[m].to_f64().unwrap() * samples[m + half_length].to_f64().unwrap();
+        correlation.push(correlation[m] + product);
+    }
+    let mut energy = Vec::with_capacity(samples.len() + 1);
+    energy.push(0.0);
+    for (m, &sample) in samples.iter().enumerate() {
+        energy.push(energy[m] + sample.to_f64().unwrap().powi(2));
+    }
+
+    // windows far below the mean power of the samples hold rounding errors rather than signal
+    let energy_floor =
+        ENERGY_FLOOR * half_length as f64 * energy[samples.len()] / samples.len() as f64;
+
+    (0..num_positions)
+        .map(|d| {
+            let p = correlation[d + half_length] - correlation[d];
+            let r = 0.5 * (energy[d + 2 * half_length] - energy[d]);
+            if r > energy_floor {
+                cast((p * p / (r * r)).min(1.0))
+            } else {
+                T::zero()
+            }
+        })
+        .collect()
+}
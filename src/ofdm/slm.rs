@@ -0,0 +1,49 @@
+//! This module provides the phase sequences for selective mapping (SLM).
+//!
+//! The time domain signal of an OFDM symbol occasionally adds up to peaks far above its average power.
+//! Selective mapping rotates the data subcarriers with one of several pseudo random phase sequences,
+//! the modulator transmits the candidate with the lowest peak and signals its index on dedicated subcarriers,
+//! so the demodulator can undo the rotation.
+//!
+//! See [num_slm_candidates](crate::ofdm::modulator::OFDMModulatorConfig::num_slm_candidates) to enable it.
+
+use realfft::num_complex::Complex;
+
+use crate::float::Float;
+
+/// Highest number of candidates, limited by the distinct seeds of the LFSR.
+pub const MAX_SLM_CANDIDATES: u32 = 128;
+
+/// Builds the phase rotation of every data subcarrier for the given candidate.
+///
+/// Candidate `0` leaves the subcarriers untouched, the others rotate every subcarrier by a multiple of 90°
+/// chosen by two bits of the LFSR `x^7 + x^4 + 1`, which is seeded with the candidate index.
+pub(super) fn phase_sequence<T: Float>(
+    candidate: u32,
+    num_data_subcarriers: usize,
+) -> Vec<Complex<T>> {
+    let one = Complex::new(T::one(), T::zero());
+    if candidate == 0 {
+        return vec![one; num_data_subcarriers];
+    }
+
+    let mut lfsr = candidate as u8;
+    (0..num_data_subcarriers)
+        .map(|_| {
+            let mut quarter_turns = 0;
+            for _ in 0..2 {
+                // x^7 + x^4 + 1
+                let bit = ((lfsr >> 6) ^ (lfsr >> 3)) & 1;
+                lfsr = ((lfsr << 1) | bit) & 0x7f;
+                quarter_turns = (quarter_turns << 1) | bit;
+            }
+
+            match quarter_turns {
+                0 => one,
+                1 => Complex::new(T::zero(), T::one()),
+                2 => -one,
+                _ => Complex::new(T::zero(), -T::one()),
+            }
+        })
+        .collect()
+}
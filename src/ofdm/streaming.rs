@@ -0,0 +1,261 @@
+//! This module provides demodulation of a continuous stream of samples.
+//!
+//! The [StreamingDemodulator] accepts samples in chunks of any size, e.g. straight from an audio callback,
+//! searches them for the [preamble](crate::ofdm::preamble) and demodulates the
+//! [framed packet](crate::ofdm::modulator::OFDMModulator::modulate_framed_packet) following it
+//! as soon as all of its symbols arrived.
+
+use std::collections::VecDeque;
+
+use crate::{float::Float, frame, ofdm::demodulator::OFDMDemodulator};
+
+/// Demodulates framed packets from a stream of samples.
+///
+/// Every packet is expected to be sent as the [preamble](crate::ofdm::modulator::OFDMModulator::generate_preamble)
+/// directly followed by the output of [modulate_framed_packet](crate::ofdm::modulator::OFDMModulator::modulate_framed_packet).
+/// Once a preamble is found, the first symbols are demodulated to read the length of the frame,
+/// and the whole packet is demodulated when its last symbol arrived.
+/// Packets whose checksum does not match or which announce more than `max_payload_length` bytes are dropped,
+/// and the search continues after their preamble.
+///
+/// # Memory
+/// The samples are kept in a single buffer, which is drained in place and keeps its capacity.
+/// While searching it holds about two symbols, while receiving it grows to the length of the packet,
+/// which is bounded by `max_payload_length`.
+/// Received payloads are queued until they are taken with [payloads](StreamingDemodulator::payloads),
+/// so take them regularly to keep the queue short.
+///
+/// # Example
+/// ```
+/// use software_modem::ofdm::CyclicPrefix;
+/// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+/// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+/// use software_modem::ofdm::streaming::StreamingDemodulator;
+///
+/// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+///     num_subcarriers: 64,
+///     cyclic_prefix: CyclicPrefix::Samples(16),
+///     convolutional_coding: true,
+///     ..Default::default()
+/// })
+/// .unwrap();
+/// let demodulator = || {
+///     OFDMDemodulator::new(OFDMDemodulatorConfig {
+///         num_subcarriers: 64,
+///         cyclic_prefix: CyclicPrefix::Samples(16),
+///         convolutional_coding: true,
+///         ..Default::default()
+///     })
+///     .unwrap()
+/// };
+///
+/// // simple deterministic random source
+/// let mut state: u32 = 1;
+/// let mut random = move || {
+///     state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+///     state >> 8
+/// };
+///
+/// let payloads: [&[u8]; 2] = [b"Hello, streaming OFDM!", &[42; 300]];
+/// let mut samples = Vec::new();
+/// let mut packets = Vec::new();
+/// for (seq, payload) in payloads.into_iter().enumerate() {
+///     let packet = modulator.modulate_framed_packet(payload, seq as u16).unwrap();
+///     samples.extend((0..500).map(|_| (random() % 1000) as f32 / 1000.0 - 0.5));
+///     samples.extend(modulator.generate_preamble());
+///     samples.extend(&packet);
+///     packets.push(packet);
+/// }
+/// samples.extend((0..500).map(|_| (random() % 1000) as f32 / 1000.0 - 0.5));
+///
+/// let mut streaming = StreamingDemodulator::new(demodulator(), 1024);
+/// let mut received = Vec::new();
+/// let mut position = 0;
+/// while position < samples.len() {
+///     let end = (position + 1 + random() as usize % 300).min(samples.len());
+///     streaming.push(&samples[position..end]);
+///     received.extend(streaming.payloads());
+///     position = end;
+/// }
+///
+/// // the same bytes as demodulating every packet at once
+/// let one_shot: Vec<(u16, Vec<u8>)> = packets
+///     .iter()
+///     .map(|packet| demodulator().demodulate_framed_packet(packet).unwrap())
+///     .collect();
+/// assert_eq!(received, one_shot);
+/// assert_eq!(received, [(0, payloads[0].to_vec()), (1, payloads[1].to_vec())]);
+/// ```
+///
+/// Every capture can be pushed at once, exact silence and the [trailing gap](crate::ofdm::modulator::OFDMModulatorConfig::trailing_gap)
+/// of the packets included:
+/// ```
+/// use software_modem::ofdm::CyclicPrefix;
+/// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+/// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+/// use software_modem::ofdm::streaming::StreamingDemodulator;
+///
+/// let modulator = OFDMModulator::new(OFDMModulatorConfig {
+///     num_subcarriers: 64,
+///     cyclic_prefix: CyclicPrefix::Samples(16),
+///     trailing_gap: 300,
+///     ..Default::default()
+/// })
+/// .unwrap();
+/// let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+///     num_subcarriers: 64,
+///     cyclic_prefix: CyclicPrefix::Samples(16),
+///     trailing_gap: 300,
+///     ..Default::default()
+/// })
+/// .unwrap();
+///
+/// let capture = |payload: &[u8], seq: u16| {
+///     let mut samples = vec![0.0; 500];
+///     samples.extend(modulator.generate_preamble());
+///     samples.extend(modulator.modulate_framed_packet(payload, seq).unwrap());
+///     samples.extend(vec![0.0; 500]);
+///     samples
+/// };
+///
+/// let mut streaming = StreamingDemodulator::new(demodulator, 1024);
+/// streaming.push(&capture(&[42; 300], 1));
+/// streaming.push(&capture(b"Hello, gap!", 2));
+/// assert_eq!(
+///     streaming.payloads().collect::<Vec<_>>(),
+///     [(1, vec![42; 300]), (2, b"Hello, gap!".to_vec())]
+/// );
+/// ```
+pub struct StreamingDemodulator<T: Float = f32> {
+    demodulator: OFDMDemodulator<T>,
+    max_payload_length: usize,
+    buffer: Vec<T>,
+    state: State,
+    payloads: VecDeque<(u16, Vec<u8>)>,
+}
+
+/// Progress of the reception of a packet.
+enum State {
+    /// Looking for a preamble in the buffer.
+    Searching,
+    /// The first data symbol starts at `data_start`,
+    /// `num_symbols` is known once the length of the frame was demodulated.
+    Receiving {
+        data_start: usize,
+        num_symbols: Option<usize>,
+    },
+}
+
+impl<T: Float> StreamingDemodulator<T> {
+    /// Creates a streaming demodulator receiving packets with the given demodulator.
+    ///
+    /// Frames announcing a payload longer than `max_payload_length` bytes are treated as corrupted.
+    pub fn new(demodulator: OFDMDemodulator<T>, max_payload_length: usize) -> Self {
+        StreamingDemodulator {
+            demodulator,
+            max_payload_length,
+            buffer: Vec::new(),
+            state: State::Searching,
+            payloads: VecDeque::new(),
+        }
+    }
+
+    /// Appends a chunk of samples to the stream and demodulates every packet it completes.
+    pub fn push(&mut self, chunk: &[T]) {
+        self.buffer.extend_from_slice(chunk);
+        while self.process() {}
+    }
+
+    /// Takes the payloads received so far together with their sequence numbers, oldest first.
+    pub fn payloads(&mut self) -> impl Iterator<Item = (u16, Vec<u8>)> + '_ {
+        self.payloads.drain(..)
+    }
+
+    /// Advances the state with the buffered samples, returns whether it can advance further.
+    fn process(&mut self) -> bool {
+        let symbol_length = self.demodulator.get_symbol_length();
+
+        match self.state {
+            State::Searching => {
+                let Some(start) = self.demodulator.find_frame_start(&self.buffer) else {
+                    // a preamble may begin within the last samples
+                    let keep = 2 * symbol_length;
+                    self.buffer.drain(..self.buffer.len().saturating_sub(keep));
+                    return false;
+                };
+
+                // the end of the buffer cuts into the preamble, wait for the rest of it
+                if start + 2 * symbol_length > self.buffer.len() {
+                    self.buffer.drain(..start.saturating_sub(symbol_length));
+                    return false;
+                }
+
+                self.state = State::Receiving {
+                    data_start: start + symbol_length,
+                    num_symbols: None,
+                };
+                true
+            }
+            State::Receiving {
+                data_start,
+                num_symbols,
+            } => {
+                let Some(num_symbols) = num_symbols.or_else(|| self.read_num_symbols(data_start))
+                else {
+                    return false;
+                };
+                self.state = State::Receiving {
+                    data_start,
+                    num_symbols: Some(num_symbols),
+                };
+
+                let max_frame_length = self.max_payload_length.saturating_add(frame::OVERHEAD);
+                if num_symbols > self.demodulator.num_packet_symbols(max_frame_length) {
+                    self.skip_packet(data_start);
+                    return true;
+                }
+                if self.num_available_symbols(data_start) < num_symbols {
+                    return false;
+                }
+
+                let end =
+                    data_start + num_symbols * symbol_length + self.demodulator.window_length();
+                // the span ends with the fade out of the last symbol, before the trailing gap
+                let data = self.demodulator.demodulate_packet_symbols(
+                    &self.buffer[data_start..end],
+                    0,
+                    None,
+                );
+                match frame::deframe(&data) {
+                    Ok(frame) => {
+                        self.payloads.push_back(frame);
+                        self.buffer.drain(..end);
+                        self.state = State::Searching;
+                    }
+                    // most likely a false detection of the preamble
+                    Err(_) => self.skip_packet(data_start),
+                }
+                true
+            }
+        }
+    }
+
+    /// Number of complete data symbols buffered after `data_start`.
+    fn num_available_symbols(&self, data_start: usize) -> usize {
+        let end = data_start + self.demodulator.window_length();
+        self.buffer.len().saturating_sub(end) / self.demodulator.get_symbol_length()
+    }
+
+    /// Demodulates the first symbols to read the length of the frame,
+    /// returns the number of symbols of the packet once they were received.
+    fn read_num_symbols(&self, data_start: usize) -> Option<usize> {
+        self.demodulator
+            .read_frame_symbols(&self.buffer[data_start..])
+    }
+
+    /// Drops the packet starting at `data_start` and searches for the next preamble behind it.
+    fn skip_packet(&mut self, data_start: usize) {
+        self.buffer.drain(..data_start);
+        self.state = State::Searching;
+    }
+}
@@ -1,47 +1,214 @@
 //! This module provides the QAM (Quadrature Amplitude Modulation) implementation.
 //!
-//! Use the [QAMModem] struct to modulate and demodulate data into QAM symbols.
-//! See the [QAMOrder] enum for supported QAM orders.
+//! Use the [QAMModem] struct to modulate and demodulate data into QAM symbols,
+//! or the [DifferentialQAMModem] if the channel provides no phase reference.
+//! See the [QAMOrder] enum for supported QAM orders,
+//! or [from_constellation](QAMModem::from_constellation) for custom constellations.
 
 use core::panic;
-use std::fmt::Display;
+use std::{borrow::Cow, cmp::Ordering, fmt::Display};
 
-use realfft::num_complex::Complex32;
+use realfft::num_complex::{Complex, Complex32};
 
-// QAM-16 lookup table
+use crate::{
+    bits::{BitOrder, BitReader, BitWriter},
+    error::DemodulationError,
+    float::{Float, cast},
+};
+
+// QAM-16 lookup table, Gray coded
+//
+// The upper two bits select the in-phase level and the lower two bits the quadrature level.
+// Both axes use the same Gray code, so horizontally or vertically adjacent points differ in exactly one bit:
+//
+//          I: -3    -1    +1    +3
+//  Q: +3     1101  1001  0001  0101
+//  Q: +1     1100  1000  0000  0100
+//  Q: -1     1110  1010  0010  0110
+//  Q: -3     1111  1011  0011  0111
 const QAM16_LOOKUP: [Complex32; 16] = [
     Complex32::new(1.0, 1.0),   // 0000
     Complex32::new(1.0, 3.0),   // 0001
-    Complex32::new(3.0, 1.0),   // 0010
-    Complex32::new(3.0, 3.0),   // 0011
-    Complex32::new(1.0, -1.0),  // 0100
-    Complex32::new(1.0, -3.0),  // 0101
+    Complex32::new(1.0, -1.0),  // 0010
+    Complex32::new(1.0, -3.0),  // 0011
+    Complex32::new(3.0, 1.0),   // 0100
+    Complex32::new(3.0, 3.0),   // 0101
     Complex32::new(3.0, -1.0),  // 0110
     Complex32::new(3.0, -3.0),  // 0111
     Complex32::new(-1.0, 1.0),  // 1000
     Complex32::new(-1.0, 3.0),  // 1001
-    Complex32::new(-3.0, 1.0),  // 1010
-    Complex32::new(-3.0, 3.0),  // 1011
-    Complex32::new(-1.0, -1.0), // 1100
-    Complex32::new(-1.0, -3.0), // 1101
+    Complex32::new(-1.0, -1.0), // 1010
+    Complex32::new(-1.0, -3.0), // 1011
+    Complex32::new(-3.0, 1.0),  // 1100
+    Complex32::new(-3.0, 3.0),  // 1101
     Complex32::new(-3.0, -1.0), // 1110
     Complex32::new(-3.0, -3.0), // 1111
 ];
 
-#[derive(Default, Copy, Clone, Debug)]
+// BPSK lookup table
+const BPSK_LOOKUP: [Complex32; 2] = [
+    Complex32::new(1.0, 0.0),  // 0
+    Complex32::new(-1.0, 0.0), // 1
+];
+
+// Gray codes of the levels of one axis, from the lowest to the highest level.
+//
+// The square constellations use the same code on both axes, see the lookup tables.
+const TWO_LEVEL_CODES: [u32; 2] = [0b1, 0b0];
+const FOUR_LEVEL_CODES: [u32; 4] = [0b11, 0b10, 0b00, 0b01];
+
+// QAM-4 (QPSK) lookup table, Gray coded
+const QAM4_LOOKUP: [Complex32; 4] = [
+    Complex32::new(1.0, 1.0),   // 00
+    Complex32::new(1.0, -1.0),  // 01
+    Complex32::new(-1.0, 1.0),  // 10
+    Complex32::new(-1.0, -1.0), // 11
+];
+
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// Represents the QAM order for modulation.
 pub enum QAMOrder {
+    /// BPSK, 1 bit per symbol on the two points `+1` and `-1`.
+    ///
+    /// The points are as far apart as the ones of QAM-4, but carry half the bits at half the power,
+    /// which makes BPSK the most robust order for hostile channels and control data.
+    ///
+    /// # Example
+    /// ```
+    /// use rand::{SeedableRng, rngs::StdRng};
+    /// use software_modem::channel::add_awgn;
+    /// use software_modem::metrics::bit_error_rate;
+    /// use software_modem::ofdm::CyclicPrefix;
+    /// use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+    /// use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+    /// use software_modem::qam::{ QAMModem, QAMOrder };
+    ///
+    /// // every byte takes 8 symbols
+    /// let modem = QAMModem::new(QAMOrder::BPSK);
+    /// let symbols = modem.modulate(b"BPSK");
+    /// assert_eq!(symbols.len(), 32);
+    /// assert_eq!(modem.demodulate(&symbols), b"BPSK");
+    ///
+    /// let bit_errors = |qam_order: QAMOrder, snr_db: f32| {
+    ///     let modulator = OFDMModulator::new(OFDMModulatorConfig {
+    ///         num_subcarriers: 64,
+    ///         cyclic_prefix: CyclicPrefix::Samples(4),
+    ///         qam_order,
+    ///         ..Default::default()
+    ///     })
+    ///     .unwrap();
+    ///     let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+    ///         num_subcarriers: 64,
+    ///         cyclic_prefix: CyclicPrefix::Samples(4),
+    ///         qam_order,
+    ///         ..Default::default()
+    ///     })
+    ///     .unwrap();
+    ///
+    ///     let payload: Vec<u8> = (0..240).map(|i| (i * 37 % 251) as u8).collect();
+    ///     let (mut samples, padding) = modulator.modulate_packet(&payload).unwrap();
+    ///     add_awgn(&mut samples, snr_db, &mut StdRng::seed_from_u64(5));
+    ///     bit_error_rate(&payload, &demodulator.demodulate_packet(&samples, padding))
+    /// };
+    ///
+    /// // at 12 dB SNR, QAM-16 is lost while BPSK gets through
+    /// assert!(bit_errors(QAMOrder::QAM16, 12.0) > 0.01);
+    /// assert_eq!(bit_errors(QAMOrder::BPSK, 12.0), 0.0);
+    /// ```
+    BPSK,
+    /// QAM-4 (QPSK), 2 bits per symbol.
+    QAM4,
+    /// QAM-16, 4 bits per symbol.
+    ///
+    /// The constellation is Gray coded, so a symbol error to a neighbouring point usually causes only one bit error.
+    /// Note that this mapping replaces the plain binary mapping of earlier releases,
+    /// which is a breaking change of the wire format: signals produced by older releases can not be demodulated anymore.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::qam::{ QAMModem, QAMOrder };
+    ///
+    /// let modem = QAMModem::new(QAMOrder::QAM16);
+    ///
+    /// // every nibble as the upper and lower half of a byte
+    /// let nibbles: Vec<u8> = (0..16).collect();
+    /// let data: Vec<u8> = nibbles.iter().map(|n| (n << 4) | n).collect();
+    /// let points: Vec<_> = modem.modulate(&data).into_iter().step_by(2).collect();
+    ///
+    /// for (a, point_a) in points.iter().enumerate() {
+    ///     for (b, point_b) in points.iter().enumerate() {
+    ///         // horizontally or vertically adjacent points are 2.0 apart
+    ///         if ((point_a - point_b).norm() - 2.0).abs() < 1e-6 {
+    ///             assert_eq!((nibbles[a] ^ nibbles[b]).count_ones(), 1);
+    ///         }
+    ///     }
+    /// }
+    /// ```
     #[default]
     QAM16,
 }
+impl QAMOrder {
+    /// Every QAM order, from the most robust to the one carrying the most bits per symbol.
+    ///
+    /// New orders must be listed here to be returned by [all](QAMOrder::all).
+    const ALL: [QAMOrder; 3] = [QAMOrder::BPSK, QAMOrder::QAM4, QAMOrder::QAM16];
+
+    /// Returns an iterator over every supported QAM order, from the fewest to the most bits per symbol.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::qam::{ QAMModem, QAMOrder };
+    ///
+    /// assert_eq!(QAMOrder::all().next(), Some(QAMOrder::BPSK));
+    ///
+    /// for qam_order in QAMOrder::all() {
+    ///     // every byte value visits every point
+    ///     let data: Vec<u8> = (0..=255).collect();
+    ///     let mut points = QAMModem::new(qam_order).modulate(&data);
+    ///     points.sort_by(|a, b| (a.re, a.im).partial_cmp(&(b.re, b.im)).unwrap());
+    ///     points.dedup();
+    ///
+    ///     assert!(points.len().is_power_of_two());
+    ///     assert_eq!(points.len(), 1 << qam_order.bits_per_symbol());
+    /// }
+    /// ```
+    pub fn all() -> impl Iterator<Item = QAMOrder> {
+        Self::ALL.into_iter()
+    }
+
+    /// Returns the number of bits one symbol of this order carries.
+    pub fn bits_per_symbol(self) -> u32 {
+        match self {
+            QAMOrder::BPSK => 1,  // BPSK uses 1 bit per symbol
+            QAMOrder::QAM4 => 2,  // QAM-4 uses 2 bits per symbol
+            QAMOrder::QAM16 => 4, // QAM-16 uses 4 bits per symbol
+        }
+    }
+}
 impl Display for QAMOrder {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
+            QAMOrder::BPSK => write!(f, "BPSK"),
+            QAMOrder::QAM4 => write!(f, "QAM-4"),
             QAMOrder::QAM16 => write!(f, "QAM-16"),
         }
     }
 }
 
+/// How [try_demodulate](QAMModem::try_demodulate) handles symbols which do not make up whole bytes.
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Strictness {
+    /// Drops the bits after the last whole byte, like [demodulate](QAMModem::demodulate).
+    #[default]
+    Lenient,
+    /// Accepts only symbol counts [modulate](QAMModem::modulate) can produce.
+    ///
+    /// The bits after the last whole byte must be fewer than the bits of one symbol, i.e. the zero padding of the last symbol.
+    /// Any more bits mean that symbols were lost, e.g. because the capture was truncated.
+    Strict,
+}
+
 /// A modulator and demodulator for Quadrature Amplitude Modulation (QAM).
 ///
 /// This struct allows modulating and demodulating data (byte slices) into QAM symbols.
@@ -58,20 +225,227 @@ impl Display for QAMOrder {
 ///
 /// assert_eq!(data, demodulated_data);
 /// ```
-pub struct QAMModem {
-    qam_order: QAMOrder,
+///
+/// The modem works with `f32` by default, see [from_order](QAMModem::from_order) for other [float types](Float).
+pub struct QAMModem<T: Float = f32> {
+    bits_per_symbol: u32,
+    /// Constellation point of every symbol value.
+    lookup: Vec<Complex<T>>,
+    /// Hard decision of square constellations, custom ones search the closest point instead.
+    slicer: Option<AxisSlicer<T>>,
+    bit_order: BitOrder,
+    /// Whether received symbols are scaled to the power of the constellation before they are sliced.
+    scale_correction: bool,
 }
 
-impl QAMModem {
+impl QAMModem<f32> {
     /// Create a new QAMModem for the specified QAM order.
     pub fn new(qam_order: QAMOrder) -> Self {
-        QAMModem { qam_order }
+        Self::from_order(qam_order)
+    }
+
+    /// Create a new QAMModem for the specified QAM order with a constellation normalized to unit average power.
+    ///
+    /// Every constellation point is scaled by `1 / sqrt(avg_power)`,
+    /// so the average symbol energy over all points is 1.0.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::qam::{ QAMModem, QAMOrder };
+    ///
+    /// let data: Vec<u8> = (0..=255).collect();
+    /// let modem = QAMModem::new_normalized(QAMOrder::QAM16);
+    /// let symbols = modem.modulate(&data);
+    ///
+    /// let mean_power = symbols.iter().map(|s| s.norm_sqr()).sum::<f32>() / symbols.len() as f32;
+    /// assert!((mean_power - 1.0).abs() < 1e-5);
+    ///
+    /// assert_eq!(modem.demodulate(&symbols), data);
+    /// ```
+    pub fn new_normalized(qam_order: QAMOrder) -> Self {
+        Self::from_order_normalized(qam_order)
+    }
+}
+
+impl<T: Float> QAMModem<T> {
+    /// Create a new QAMModem for the specified QAM order, working with the float type `T`.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::qam::{ QAMModem, QAMOrder };
+    ///
+    /// let data = "Hello, world!".as_bytes();
+    /// let modem = QAMModem::<f64>::from_order(QAMOrder::QAM16);
+    ///
+    /// let symbols = modem.modulate(data);
+    /// let _: f64 = symbols[0].re;
+    ///
+    /// assert_eq!(modem.demodulate(&symbols), data);
+    /// ```
+    pub fn from_order(qam_order: QAMOrder) -> Self {
+        QAMModem {
+            bits_per_symbol: qam_order.bits_per_symbol(),
+            lookup: lookup_table(qam_order),
+            slicer: Some(AxisSlicer::new(qam_order, T::one())),
+            bit_order: BitOrder::MsbFirst,
+            scale_correction: false,
+        }
+    }
+
+    /// Create a new QAMModem with a constellation normalized to unit average power, working with the float type `T`.
+    ///
+    /// See [new_normalized](QAMModem::new_normalized) for details on the normalization.
+    pub fn from_order_normalized(qam_order: QAMOrder) -> Self {
+        let table: Vec<Complex<T>> = lookup_table(qam_order);
+        let avg_power =
+            table.iter().map(|point| point.norm_sqr()).sum::<T>() / cast(table.len() as f64);
+        let scale = T::one() / avg_power.sqrt();
+
+        QAMModem {
+            bits_per_symbol: qam_order.bits_per_symbol(),
+            lookup: table.iter().map(|&point| point * scale).collect(),
+            slicer: Some(AxisSlicer::new(qam_order, scale)),
+            bit_order: BitOrder::MsbFirst,
+            scale_correction: false,
+        }
+    }
+
+    /// Create a new QAMModem with a custom constellation, e.g. for amplitude and phase shift keying.
+    ///
+    /// `points[i]` is the constellation point of the symbol value `i`,
+    /// so a symbol carries `log2(points.len())` bits and the bit mapping (e.g. Gray coding) is up to the caller.
+    /// Symbols are demodulated to the closest point.
+    ///
+    /// # Panics
+    /// If the number of points is not a power of two between 2 and 256.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::qam::QAMModem;
+    /// use realfft::num_complex::Complex32;
+    ///
+    /// // Gray coded 8-PSK, neighboring points differ in one bit
+    /// let gray = [0b000, 0b001, 0b011, 0b010, 0b110, 0b111, 0b101, 0b100];
+    /// let mut points = vec![Complex32::default(); 8];
+    /// for (position, &value) in gray.iter().enumerate() {
+    ///     points[value] = Complex32::from_polar(1.0, position as f32 * std::f32::consts::PI / 4.0);
+    /// }
+    ///
+    /// let modem = QAMModem::from_constellation(points);
+    /// assert_eq!(modem.bits_per_symbol(), 3);
+    ///
+    /// let data = b"Hello, 8-PSK!";
+    /// let symbols = modem.modulate(data);
+    /// assert_eq!(symbols.len(), (data.len() * 8).div_ceil(3));
+    /// assert!(symbols.iter().all(|s| (s.norm() - 1.0).abs() < 1e-6));
+    ///
+    /// // a small rotation stays closest to the transmitted points
+    /// let rotated: Vec<_> = symbols.iter().map(|s| s * Complex32::from_polar(1.0, 0.2)).collect();
+    /// assert_eq!(modem.demodulate(&rotated), data);
+    /// ```
+    pub fn from_constellation(points: Vec<Complex<T>>) -> Self {
+        if !points.len().is_power_of_two() || !(2..=256).contains(&points.len()) {
+            panic!(
+                "Number of constellation points must be a power of two between 2 and 256, but got {}",
+                points.len()
+            );
+        }
+
+        QAMModem {
+            bits_per_symbol: points.len().trailing_zeros(),
+            lookup: points,
+            slicer: None,
+            bit_order: BitOrder::MsbFirst,
+            scale_correction: false,
+        }
+    }
+
+    /// Splits bytes into symbols in the given [bit order](BitOrder) instead of most significant bit first.
+    ///
+    /// With [BitOrder::LsbFirst], the first symbol of a byte carries its lowest bits,
+    /// e.g. the low nibble for QAM-16, and the lowest bit of a byte is the lowest bit of its symbol value.
+    /// This matches modems which pack bytes least significant bit first.
+    /// [modulate](QAMModem::modulate), [demodulate](QAMModem::demodulate) and the bit order of
+    /// [demodulate_bits](QAMModem::demodulate_bits) and [demodulate_soft](QAMModem::demodulate_soft) all follow it,
+    /// so the demodulating modem needs the same bit order as the modulating one.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::bits::BitOrder;
+    /// use software_modem::qam::{ QAMModem, QAMOrder };
+    ///
+    /// let data = "Bit order".as_bytes();
+    /// let msb_first = QAMModem::new(QAMOrder::QAM16);
+    /// let lsb_first = QAMModem::new(QAMOrder::QAM16).with_bit_order(BitOrder::LsbFirst);
+    ///
+    /// let msb_symbols = msb_first.modulate(data);
+    /// let lsb_symbols = lsb_first.modulate(data);
+    /// assert_eq!(msb_first.demodulate(&msb_symbols), data);
+    /// assert_eq!(lsb_first.demodulate(&lsb_symbols), data);
+    ///
+    /// // the nibbles of every byte swap places
+    /// assert_ne!(msb_symbols, lsb_symbols);
+    /// for (msb, lsb) in msb_symbols.chunks(2).zip(lsb_symbols.chunks(2)) {
+    ///     assert_eq!(msb, [lsb[1], lsb[0]]);
+    /// }
+    ///
+    /// // for QAM-4, the first symbol carries the lowest two bits with their value unchanged
+    /// let lsb_qam4 = QAMModem::new(QAMOrder::QAM4).with_bit_order(BitOrder::LsbFirst);
+    /// let msb_qam4 = QAMModem::new(QAMOrder::QAM4);
+    /// assert_eq!(lsb_qam4.modulate(&[0b00_00_00_01])[0], msb_qam4.modulate(&[0b01_00_00_00])[0]);
+    /// assert_eq!(lsb_qam4.demodulate(&lsb_qam4.modulate(data)), data);
+    /// ```
+    pub fn with_bit_order(mut self, bit_order: BitOrder) -> Self {
+        self.bit_order = bit_order;
+        self
+    }
+
+    /// Scales the received symbols to the average power of the constellation before they are sliced.
+    ///
+    /// The hard decision boundaries lie halfway between the ideal points,
+    /// so a residual amplitude error, e.g. of an imperfect equalizer, moves the outer points across them.
+    /// With the correction, every call estimates one scale factor for all of its symbols:
+    /// the square root of the average power of the points
+    /// divided by the mean power of the symbols.
+    /// [demodulate](QAMModem::demodulate), [demodulate_bits](QAMModem::demodulate_bits),
+    /// [demodulate_soft](QAMModem::demodulate_soft) and [demodulate_with_erasures](QAMModem::demodulate_with_erasures)
+    /// all apply it.
+    /// The estimate assumes the symbols visit all points about equally often, which takes a few dozen symbols
+    /// of scrambled or random data, and noise adds its power to the one of the symbols.
+    /// Correction is disabled by default.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::qam::{ QAMModem, QAMOrder };
+    /// use realfft::num_complex::Complex32;
+    ///
+    /// let data: Vec<u8> = (0..=255).collect();
+    /// let modem = QAMModem::new(QAMOrder::QAM16);
+    ///
+    /// // the received constellation shrank by 0.7 and picked up some noise
+    /// let received: Vec<Complex32> = modem
+    ///     .modulate(&data)
+    ///     .iter()
+    ///     .enumerate()
+    ///     .map(|(i, s)| s * 0.7 + Complex32::new((i % 5) as f32 * 0.075 - 0.15, (i % 3) as f32 * 0.1 - 0.1))
+    ///     .collect();
+    ///
+    /// // the outer points fall onto the inner ones
+    /// assert_ne!(modem.demodulate(&received), data);
+    ///
+    /// let corrected = QAMModem::new(QAMOrder::QAM16).with_scale_correction(true);
+    /// assert_eq!(corrected.demodulate(&received), data);
+    /// ```
+    pub fn with_scale_correction(mut self, scale_correction: bool) -> Self {
+        self.scale_correction = scale_correction;
+        self
     }
 
     /// Modulate a byte array into QAM symbols.
     ///
-    /// Each byte will result in QAMModulator.bits_per_symbol() symbols,
-    /// as the number of bits per symbol depends on the QAM order.
+    /// The bits are [grouped](crate::bits) into `bits_per_symbol()` bits per symbol, most significant bit first,
+    /// so `n` bytes result in `ceil(8 * n / bits_per_symbol())` symbols.
+    /// If the bits do not divide into whole symbols, the last symbol is padded with zero bits.
     ///
     /// # Example
     /// ```
@@ -82,27 +456,80 @@ impl QAMModem {
     /// let symbols = modem.modulate(data);
     ///
     /// assert_eq!(symbols.len(), data.len() * 2); // Each byte produces two QAM symbols for QAM-16
+    ///
+    /// let modem = QAMModem::new(QAMOrder::QAM4);
+    /// let symbols = modem.modulate(data);
+    ///
+    /// assert_eq!(symbols.len(), data.len() * 4); // Each byte produces four QAM symbols for QAM-4
+    ///
+    /// // no data, no symbols
+    /// assert!(modem.modulate(&[]).is_empty());
     /// ```
-    pub fn modulate(&self, data: &[u8]) -> Vec<Complex32> {
-        let mut symbols = Vec::new();
-        match self.qam_order {
-            QAMOrder::QAM16 => {
-                for &byte in data {
-                    let first_nibble = (byte >> 4) & 0x0f; // Get the first 4 bits
-                    let second_nibble = byte & 0x0f; // Get the last 4 bits
+    pub fn modulate(&self, data: &[u8]) -> Vec<Complex<T>> {
+        self.modulate_iter(data).collect()
+    }
 
-                    symbols.push(QAM16_LOOKUP[first_nibble as usize]);
-                    symbols.push(QAM16_LOOKUP[second_nibble as usize]);
-                }
-            }
+    /// Modulate a byte array into the start of a caller provided buffer, without allocating.
+    ///
+    /// Writes the same symbols as [modulate](QAMModem::modulate) and returns their number,
+    /// the rest of `symbols` is left untouched.
+    ///
+    /// # Panics
+    /// If `symbols` holds fewer than [symbol_count](QAMModem::symbol_count)`(data.len())` values.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::qam::{ QAMModem, QAMOrder };
+    /// use realfft::num_complex::Complex32;
+    ///
+    /// let data = "Hello, world!".as_bytes();
+    /// let modem = QAMModem::new(QAMOrder::QAM16);
+    ///
+    /// let mut symbols = [Complex32::default(); 32];
+    /// let num_symbols = modem.modulate_into(data, &mut symbols);
+    ///
+    /// assert_eq!(num_symbols, 26);
+    /// assert_eq!(symbols[..num_symbols], modem.modulate(data));
+    /// assert_eq!(modem.modulate_iter(data).collect::<Vec<_>>(), modem.modulate(data));
+    /// ```
+    pub fn modulate_into(&self, data: &[u8], symbols: &mut [Complex<T>]) -> usize {
+        let iter = self.modulate_iter(data);
+        let num_symbols = iter.len();
+        if symbols.len() < num_symbols {
+            panic!(
+                "Symbol buffer must hold at least {} symbols, but holds {}",
+                num_symbols,
+                symbols.len()
+            );
         }
-        symbols
+
+        for (symbol, point) in symbols.iter_mut().zip(iter) {
+            *symbol = point;
+        }
+        num_symbols
+    }
+
+    /// Modulate a byte array into QAM symbols lazily, see [modulate](QAMModem::modulate).
+    pub fn modulate_iter<'a>(
+        &'a self,
+        data: &'a [u8],
+    ) -> impl ExactSizeIterator<Item = Complex<T>> + 'a {
+        BitReader::new(data, self.bits_per_symbol())
+            .with_bit_order(self.bit_order)
+            .map(|bits| self.point(bits))
     }
 
     /// Demodulate QAM symbols back into bytes.
     ///
     /// Each symbol will be converted back to its corresponding number of bits,
     /// and then grouped into bytes.
+    /// Bits left over after the last whole byte are treated as the padding of [modulate](QAMModem::modulate) and dropped.
+    /// See [try_demodulate](QAMModem::try_demodulate) to detect symbols lost from the end instead.
+    ///
+    /// The square constellations of the [QAM orders](QAMOrder) are sliced in constant time per symbol,
+    /// by rounding the in-phase and the quadrature component to the closest level on their own.
+    /// Only [custom constellations](QAMModem::from_constellation) compare every symbol with all points.
+    /// Both find the closest point, only symbols exactly on a decision boundary may go to different neighbours.
     ///
     /// # Example
     /// ```
@@ -114,49 +541,550 @@ impl QAMModem {
     /// let demodulated_data = modem.demodulate(&symbols);
     ///
     /// assert_eq!(data, demodulated_data);
+    ///
+    /// let modem = QAMModem::new(QAMOrder::QAM4);
+    /// let symbols = modem.modulate(data);
+    /// let demodulated_data = modem.demodulate(&symbols);
+    ///
+    /// assert_eq!(data, demodulated_data);
+    ///
+    /// // no symbols, no data
+    /// assert!(modem.demodulate(&[]).is_empty());
     /// ```
-    pub fn demodulate(&self, symbols: &[Complex32]) -> Vec<u8> {
-        match self.qam_order {
-            QAMOrder::QAM16 => {
-                let mut nibbles = Vec::new();
-                // demulation
-                for symbol in symbols {
-                    QAM16_LOOKUP
-                        .iter()
-                        .enumerate()
-                        .min_by(|(_, a), (_, b)| {
-                            distance(symbol, a)
-                                .partial_cmp(&distance(symbol, b))
-                                .unwrap()
-                        })
-                        .map(|(index, _)| {
-                            nibbles.push(index as u8);
-                        })
-                        .unwrap_or_else(|| panic!("Symbol not found in QAM-16 lookup table"));
-                }
-                // nubbles to bytes
-                let mut bytes = Vec::new();
-                for chunk in nibbles.chunks(2) {
-                    if chunk.len() == 2 {
-                        let byte = (chunk[0] << 4) | chunk[1]; // Combine two nibbles into a byte
-                        bytes.push(byte);
+    ///
+    /// The slicing of a QAM order decides like the search over the same points as a custom constellation:
+    /// ```
+    /// use software_modem::qam::{ QAMModem, QAMOrder };
+    /// use realfft::num_complex::Complex32;
+    ///
+    /// for qam_order in QAMOrder::all() {
+    ///     for modem in [QAMModem::new(qam_order), QAMModem::new_normalized(qam_order)] {
+    ///         // the point of every symbol value, as the first symbol of a byte
+    ///         let bits = modem.bits_per_symbol();
+    ///         let points: Vec<Complex32> = (0..1u8 << bits)
+    ///             .map(|value| modem.modulate(&[value << (8 - bits)])[0])
+    ///             .collect();
+    ///         let searching = QAMModem::from_constellation(points.clone());
+    ///         assert_eq!(modem.demodulate(&points), searching.demodulate(&points));
+    ///
+    ///         // noisy points and points far outside of the constellation
+    ///         let symbols: Vec<Complex32> = (0..4000)
+    ///             .map(|i| Complex32::new((i % 83) as f32 * 0.11 - 4.5, (i % 71) as f32 * 0.13 - 4.6))
+    ///             .collect();
+    ///         assert_eq!(modem.demodulate(&symbols), searching.demodulate(&symbols));
+    ///     }
+    /// }
+    /// ```
+    pub fn demodulate(&self, symbols: &[Complex<T>]) -> Vec<u8> {
+        let symbols = self.scale_corrected(symbols);
+        let bits_per_symbol = self.bits_per_symbol();
+        let mut writer = BitWriter::with_capacity(symbols.len() * bits_per_symbol as usize)
+            .with_bit_order(self.bit_order);
+        for symbol in symbols.iter() {
+            writer.write(self.decide(symbol), bits_per_symbol);
+        }
+
+        // the bits of an incomplete last byte are the padding of modulate
+        writer.into_whole_bytes()
+    }
+
+    /// Demodulate QAM symbols back into bytes, checking that they make up whole bytes.
+    ///
+    /// With [Strictness::Lenient], this is the same as [demodulate](QAMModem::demodulate) and never fails.
+    /// With [Strictness::Strict], a symbol count [modulate](QAMModem::modulate) can not produce is reported
+    /// as [DemodulationError::IncompleteByte] with the number of bits after the last whole byte.
+    ///
+    /// # Errors
+    /// If `strictness` is [Strictness::Strict] and the symbols end with at least a whole symbol of an incomplete byte.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::error::DemodulationError;
+    /// use software_modem::qam::{ QAMModem, QAMOrder, Strictness };
+    ///
+    /// let modem = QAMModem::new(QAMOrder::QAM16);
+    /// let symbols = modem.modulate(b"truncated");
+    ///
+    /// // the capture lost the last symbol
+    /// let truncated = &symbols[..symbols.len() - 1];
+    /// assert_eq!(
+    ///     modem.try_demodulate(truncated, Strictness::Strict),
+    ///     Err(DemodulationError::IncompleteByte { dropped_bits: 4 })
+    /// );
+    /// assert_eq!(
+    ///     modem.try_demodulate(truncated, Strictness::Lenient).unwrap(),
+    ///     b"truncate"
+    /// );
+    ///
+    /// // the padding of a whole transmission is no error
+    /// assert_eq!(
+    ///     modem.try_demodulate(&symbols, Strictness::Strict).unwrap(),
+    ///     b"truncated"
+    /// );
+    /// ```
+    pub fn try_demodulate(
+        &self,
+        symbols: &[Complex<T>],
+        strictness: Strictness,
+    ) -> Result<Vec<u8>, DemodulationError> {
+        let bits_per_symbol = self.bits_per_symbol() as usize;
+        let dropped_bits = symbols.len() * bits_per_symbol % 8;
+        if strictness == Strictness::Strict && dropped_bits >= bits_per_symbol {
+            return Err(DemodulationError::IncompleteByte { dropped_bits });
+        }
+
+        Ok(self.demodulate(symbols))
+    }
+
+    /// Demodulate QAM symbols like [demodulate](QAMModem::demodulate)
+    /// and flag the symbols farther than `max_distance` from every constellation point as erasures.
+    ///
+    /// A symbol far from all points was most likely hit by a burst of noise or interference,
+    /// so its bits are no better than a guess. An erasure decoder can ignore them instead,
+    /// e.g. a Reed-Solomon code corrects twice as many erasures as errors of unknown location.
+    /// Returns the bytes, which still hold the closest points of erased symbols,
+    /// and one flag per symbol, `true` marking an erasure. Symbols which are not finite are always erased.
+    /// Half the minimum distance between the points is a sensible upper bound for `max_distance`,
+    /// e.g. `1.0` for the unnormalized orders.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::qam::{ QAMModem, QAMOrder };
+    /// use realfft::num_complex::Complex32;
+    ///
+    /// let modem = QAMModem::new(QAMOrder::QAM16);
+    /// let data = b"erasures";
+    /// let mut symbols = modem.modulate(data);
+    ///
+    /// // a little noise is no erasure
+    /// symbols.iter_mut().for_each(|s| *s += Complex32::new(0.2, -0.1));
+    /// let (bytes, erasures) = modem.demodulate_with_erasures(&symbols, 0.5);
+    /// assert_eq!(bytes, data);
+    /// assert_eq!(erasures, vec![false; 16]);
+    ///
+    /// // the origin lies between the four inner points, as far from the closest one as possible
+    /// symbols[5] = Complex32::new(0.0, 0.0);
+    /// let (bytes, erasures) = modem.demodulate_with_erasures(&symbols, 0.5);
+    /// assert_eq!(bytes.len(), data.len());
+    /// assert_eq!(erasures.iter().position(|&erased| erased), Some(5));
+    /// assert_eq!(erasures.iter().filter(|&&erased| erased).count(), 1);
+    /// ```
+    pub fn demodulate_with_erasures(
+        &self,
+        symbols: &[Complex<T>],
+        max_distance: T,
+    ) -> (Vec<u8>, Vec<bool>) {
+        let erasures = self
+            .scale_corrected(symbols)
+            .iter()
+            .map(|symbol| {
+                let distance = (symbol - self.point(self.decide(symbol))).norm();
+                // not finite symbols have no distance to compare
+                distance.is_nan() || distance > max_distance
+            })
+            .collect();
+
+        (self.demodulate(symbols), erasures)
+    }
+
+    /// Demodulate QAM symbols into hard decision bits.
+    ///
+    /// Returns `bits_per_symbol()` bits per symbol, most significant bit first,
+    /// in the same order as [demodulate](QAMModem::demodulate) packs them into bytes.
+    /// Nothing is dropped, so the bits of a symbol count which does not make up whole bytes are kept as well.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::qam::{ QAMModem, QAMOrder };
+    ///
+    /// let modem = QAMModem::new(QAMOrder::QAM16);
+    /// let symbols = modem.modulate(b"bits");
+    ///
+    /// let bits = modem.demodulate_bits(&symbols);
+    /// let expanded: Vec<bool> = modem
+    ///     .demodulate(&symbols)
+    ///     .iter()
+    ///     .flat_map(|byte| (0..8).rev().map(move |i| (byte >> i) & 1 == 1))
+    ///     .collect();
+    /// assert_eq!(bits, expanded);
+    ///
+    /// // three symbols hold one and a half bytes
+    /// assert_eq!(modem.demodulate_bits(&symbols[..3]).len(), 12);
+    /// assert_eq!(modem.demodulate(&symbols[..3]).len(), 1);
+    /// ```
+    pub fn demodulate_bits(&self, symbols: &[Complex<T>]) -> Vec<bool> {
+        let symbols = self.scale_corrected(symbols);
+        let bits_per_symbol = self.bits_per_symbol();
+        let mut bits = Vec::with_capacity(symbols.len() * bits_per_symbol as usize);
+        let positions = self.bit_positions();
+        for symbol in symbols.iter() {
+            let value = self.decide(symbol);
+            bits.extend(positions.iter().map(|bit| (value >> bit) & 1 == 1));
+        }
+
+        bits
+    }
+
+    /// Demodulate QAM symbols into soft bits (log-likelihood ratios).
+    ///
+    /// Returns `bits_per_symbol()` values per symbol, in the same bit order as [demodulate](QAMModem::demodulate).
+    /// Each value is the max-log approximation of `ln(P(bit = 0) / P(bit = 1))`:
+    /// the squared distance to the closest constellation point with the bit set
+    /// minus the squared distance to the closest point with the bit cleared, divided by `noise_var`.
+    /// Positive values stand for a `0` and negative values for a `1`, matching the hard decision of `demodulate`,
+    /// while the magnitude tells how reliable the decision is.
+    /// The output can be fed into the [soft decision Viterbi decoder](crate::fec::decode_soft).
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::qam::{ QAMModem, QAMOrder };
+    /// use realfft::num_complex::Complex32;
+    ///
+    /// let modem = QAMModem::new(QAMOrder::QAM16);
+    ///
+    /// // a symbol right on the constellation point of 0b0000
+    /// let llrs = modem.demodulate_soft(&[Complex32::new(1.0, 1.0)], 0.1);
+    /// assert!(llrs.iter().all(|&llr| llr > 10.0));
+    ///
+    /// // a symbol on the boundary between the left and the right half of the constellation
+    /// let llrs = modem.demodulate_soft(&[Complex32::new(0.0, 1.0)], 0.1);
+    /// assert!(llrs[0].abs() < 1e-3);
+    /// assert!(llrs[2] > 10.0 && llrs[3] > 10.0);
+    ///
+    /// // the sign follows the hard decision
+    /// let symbols = modem.modulate(b"soft");
+    /// let noisy: Vec<_> = symbols.iter().map(|s| s + Complex32::new(0.3, -0.2)).collect();
+    /// let hard: Vec<bool> = modem
+    ///     .demodulate(&noisy)
+    ///     .iter()
+    ///     .flat_map(|byte| (0..8).rev().map(move |i| (byte >> i) & 1 == 1))
+    ///     .collect();
+    /// let soft: Vec<bool> = modem.demodulate_soft(&noisy, 0.5).iter().map(|&llr| llr < 0.0).collect();
+    /// assert_eq!(hard, soft);
+    /// ```
+    pub fn demodulate_soft(&self, symbols: &[Complex<T>], noise_var: T) -> Vec<T> {
+        let symbols = self.scale_corrected(symbols);
+        let bits_per_symbol = self.bits_per_symbol();
+        let mut llrs = Vec::with_capacity(symbols.len() * bits_per_symbol as usize);
+
+        for symbol in symbols.iter() {
+            let distances: Vec<T> = self
+                .lookup
+                .iter()
+                .map(|point| (symbol - point).norm_sqr())
+                .collect();
+
+            for bit in self.bit_positions() {
+                let mut min_zero = T::infinity();
+                let mut min_one = T::infinity();
+                for (index, &distance) in distances.iter().enumerate() {
+                    if (index >> bit) & 1 == 0 {
+                        min_zero = min_zero.min(distance);
                     } else {
-                        panic!("Invalid chunk size on {} demodulation", self.qam_order);
+                        min_one = min_one.min(distance);
                     }
                 }
-                bytes
+                llrs.push((min_one - min_zero) / noise_var);
             }
         }
+
+        llrs
+    }
+
+    /// Returns the number of bits per symbol, `log2` of the number of constellation points.
+    pub fn bits_per_symbol(&self) -> u32 {
+        self.bits_per_symbol
+    }
+
+    /// Returns the number of symbols [modulate](QAMModem::modulate) produces for `data_len` bytes.
+    ///
+    /// This is `ceil(8 * data_len / bits_per_symbol())`, including the last symbol padded with zero bits.
+    ///
+    /// # Example
+    /// ```
+    /// use software_modem::qam::{ QAMModem, QAMOrder };
+    /// use realfft::num_complex::Complex32;
+    ///
+    /// let qam16 = QAMModem::new(QAMOrder::QAM16);
+    /// assert_eq!(qam16.symbol_count(13), 26);
+    /// assert_eq!(qam16.byte_count(26), 13);
+    /// // an odd number of nibbles drops the last one
+    /// assert_eq!(qam16.byte_count(27), 13);
+    ///
+    /// // 6 bits per symbol do not divide a byte
+    /// let points = (0..64).map(|i| Complex32::new((i % 8) as f32, (i / 8) as f32)).collect();
+    /// let qam64 = QAMModem::from_constellation(points);
+    /// assert_eq!(qam64.symbol_count(3), 4);
+    /// assert_eq!(qam64.byte_count(4), 3);
+    /// assert_eq!(qam64.symbol_count(4), 6);
+    /// assert_eq!(qam64.byte_count(6), 4);
+    /// assert_eq!(qam64.byte_count(5), 3);
+    ///
+    /// for modem in [&qam16, &qam64] {
+    ///     for data_len in 0..50 {
+    ///         let data = vec![0xa5; data_len];
+    ///         let symbols = modem.modulate(&data);
+    ///         assert_eq!(symbols.len(), modem.symbol_count(data_len));
+    ///         assert_eq!(modem.byte_count(symbols.len()), data_len);
+    ///         assert_eq!(modem.demodulate(&symbols).len(), data_len);
+    ///     }
+    /// }
+    /// ```
+    pub fn symbol_count(&self, data_len: usize) -> usize {
+        (data_len * 8).div_ceil(self.bits_per_symbol as usize)
+    }
+
+    /// Returns the number of whole bytes [demodulate](QAMModem::demodulate) returns for `symbol_count` symbols.
+    ///
+    /// This is `floor(symbol_count * bits_per_symbol() / 8)`, the bits of an incomplete last byte are padding.
+    /// It inverts [symbol_count](QAMModem::symbol_count), see there for an example.
+    pub fn byte_count(&self, symbol_count: usize) -> usize {
+        symbol_count * self.bits_per_symbol as usize / 8
+    }
+
+    /// Returns the positions of the bits of a symbol value in the order they appear in the byte stream.
+    fn bit_positions(&self) -> Vec<u32> {
+        match self.bit_order {
+            BitOrder::MsbFirst => (0..self.bits_per_symbol).rev().collect(),
+            BitOrder::LsbFirst => (0..self.bits_per_symbol).collect(),
+        }
+    }
+
+    /// Returns the symbols scaled to the average power of the constellation if [scale correction](QAMModem::with_scale_correction) is enabled.
+    fn scale_corrected<'a>(&self, symbols: &'a [Complex<T>]) -> Cow<'a, [Complex<T>]> {
+        if !self.scale_correction || symbols.is_empty() {
+            return Cow::Borrowed(symbols);
+        }
+
+        let received_power =
+            symbols.iter().map(|symbol| symbol.norm_sqr()).sum::<T>() / cast(symbols.len() as f64);
+        let scale = (self.average_power() / received_power).sqrt();
+        // silence or symbols which are not finite leave nothing to correct
+        if !scale.is_finite() {
+            return Cow::Borrowed(symbols);
+        }
+
+        Cow::Owned(symbols.iter().map(|&symbol| symbol * scale).collect())
+    }
+
+    /// Returns the mean energy of the constellation points.
+    pub(crate) fn average_power(&self) -> T {
+        self.lookup.iter().map(|point| point.norm_sqr()).sum::<T>() / cast(self.lookup.len() as f64)
+    }
+
+    /// Returns the constellation point of the symbol with the given bits.
+    pub(crate) fn point(&self, bits: u32) -> Complex<T> {
+        self.lookup[bits as usize]
+    }
+
+    /// Returns the bits of the constellation point closest to `symbol`.
+    pub(crate) fn decide(&self, symbol: &Complex<T>) -> u32 {
+        match &self.slicer {
+            Some(slicer) => slicer.decide(symbol),
+            None => nearest_index(symbol, &self.lookup) as u32,
+        }
+    }
+}
+
+/// A differential variant of the [QAMModem].
+///
+/// Every transmitted symbol is the QAM symbol of the data rotated by the phase of the previous transmitted symbol,
+/// starting with a reference symbol of `1 + 0i`.
+/// The demodulator recovers the QAM symbols from the phase difference of consecutive received symbols,
+/// so a constant phase rotation of the channel cancels out and no phase reference (e.g. from pilots) is needed.
+/// In exchange the noise of two symbols adds up, which costs some SNR compared to coherent demodulation.
+/// The amplitude is not differential, so the channel gain still has to be close to one.
+///
+/// # Example
+/// ```
+/// use software_modem::qam::{ DifferentialQAMModem, QAMModem, QAMOrder };
+/// use realfft::num_complex::Complex32;
+///
+/// let data = "Hello, world!".as_bytes();
+/// let modem = DifferentialQAMModem::new(QAMOrder::QAM16);
+///
+/// let symbols = modem.modulate(data);
+/// assert_eq!(symbols.len(), data.len() * 2 + 1); // the reference symbol comes first
+/// assert_eq!(modem.demodulate(&symbols), data);
+///
+/// // a constant phase rotation of the channel
+/// let rotation = Complex32::from_polar(1.0, 0.7);
+/// let rotated: Vec<Complex32> = symbols.iter().map(|s| s * rotation).collect();
+/// assert_eq!(modem.demodulate(&rotated), data);
+///
+/// // breaks coherent demodulation
+/// let coherent = QAMModem::new(QAMOrder::QAM16);
+/// let rotated: Vec<Complex32> = coherent.modulate(data).iter().map(|s| s * rotation).collect();
+/// assert_ne!(coherent.demodulate(&rotated), data);
+/// ```
+pub struct DifferentialQAMModem<T: Float = f32> {
+    modem: QAMModem<T>,
+}
+
+impl DifferentialQAMModem<f32> {
+    /// Create a new DifferentialQAMModem for the specified QAM order.
+    pub fn new(qam_order: QAMOrder) -> Self {
+        Self::from_order(qam_order)
+    }
+}
+
+impl<T: Float> DifferentialQAMModem<T> {
+    /// Create a new DifferentialQAMModem for the specified QAM order, working with the float type `T`.
+    pub fn from_order(qam_order: QAMOrder) -> Self {
+        DifferentialQAMModem {
+            modem: QAMModem::from_order(qam_order),
+        }
+    }
+
+    /// Splits bytes into symbols in the given [bit order](BitOrder), see [QAMModem::with_bit_order].
+    pub fn with_bit_order(mut self, bit_order: BitOrder) -> Self {
+        self.modem = self.modem.with_bit_order(bit_order);
+        self
+    }
+
+    /// Modulate a byte array into differentially encoded QAM symbols.
+    ///
+    /// The output starts with the reference symbol, followed by `bits_per_symbol()` symbols per byte
+    /// like [QAMModem::modulate].
+    pub fn modulate(&self, data: &[u8]) -> Vec<Complex<T>> {
+        let mut previous = Complex::new(T::one(), T::zero());
+        let mut symbols = vec![previous];
+
+        for symbol in self.modem.modulate(data) {
+            // rotate by the phase of the previous symbol
+            previous = symbol * (previous / previous.norm());
+            symbols.push(previous);
+        }
+
+        symbols
+    }
+
+    /// Demodulate differentially encoded QAM symbols back into bytes.
+    ///
+    /// The first symbol is the reference and carries no data.
+    /// Bits left over after the last whole byte are dropped like by [QAMModem::demodulate].
+    pub fn demodulate(&self, symbols: &[Complex<T>]) -> Vec<u8> {
+        self.modem.demodulate(&self.differential(symbols))
+    }
+
+    /// Demodulate differentially encoded QAM symbols back into bytes, checking that they make up whole bytes.
+    ///
+    /// The reference symbol is not counted, see [QAMModem::try_demodulate].
+    ///
+    /// # Errors
+    /// If `strictness` is [Strictness::Strict] and the symbols end with at least a whole symbol of an incomplete byte.
+    pub fn try_demodulate(
+        &self,
+        symbols: &[Complex<T>],
+        strictness: Strictness,
+    ) -> Result<Vec<u8>, DemodulationError> {
+        self.modem
+            .try_demodulate(&self.differential(symbols), strictness)
+    }
+
+    /// Returns the phase differences of consecutive symbols, which carry the data.
+    fn differential(&self, symbols: &[Complex<T>]) -> Vec<Complex<T>> {
+        symbols
+            .windows(2)
+            .map(|pair| {
+                let magnitude = pair[0].norm();
+                if magnitude > T::zero() {
+                    pair[1] * pair[0].conj() / magnitude
+                } else {
+                    Complex::default()
+                }
+            })
+            .collect()
     }
 
     /// Returns the number of bits per symbol for the specified QAM order.
     pub fn bits_per_symbol(&self) -> u32 {
-        match self.qam_order {
-            QAMOrder::QAM16 => 4, // QAM-16 uses 4 bits per symbol
+        self.modem.bits_per_symbol()
+    }
+}
+
+/// Returns the raw lookup table for the specified QAM order, converted to the float type `T`.
+fn lookup_table<T: Float>(qam_order: QAMOrder) -> Vec<Complex<T>> {
+    let table: &[Complex32] = match qam_order {
+        QAMOrder::BPSK => &BPSK_LOOKUP,
+        QAMOrder::QAM4 => &QAM4_LOOKUP,
+        QAMOrder::QAM16 => &QAM16_LOOKUP,
+    };
+    table
+        .iter()
+        .map(|point| Complex::new(cast(point.re as f64), cast(point.im as f64)))
+        .collect()
+}
+
+/// Hard decision of a square constellation in constant time.
+///
+/// The levels of the in-phase and the quadrature axis are equally spaced and Gray coded on their own,
+/// so the closest point follows from rounding both coordinates to the closest level,
+/// instead of comparing the distances to all points.
+struct AxisSlicer<T: Float> {
+    /// Half the distance between neighbouring levels, the levels are the odd multiples of it.
+    scale: T,
+    /// Codes of the in-phase levels, which make up the upper bits.
+    in_phase_codes: &'static [u32],
+    /// Codes of the quadrature levels, which make up the lower bits, a single code for BPSK.
+    quadrature_codes: &'static [u32],
+}
+
+impl<T: Float> AxisSlicer<T> {
+    /// Creates the slicer of the lookup table of `qam_order` scaled by `scale`.
+    fn new(qam_order: QAMOrder, scale: T) -> Self {
+        let (in_phase_codes, quadrature_codes): (&[u32], &[u32]) = match qam_order {
+            QAMOrder::BPSK => (&TWO_LEVEL_CODES, &[0]),
+            QAMOrder::QAM4 => (&TWO_LEVEL_CODES, &TWO_LEVEL_CODES),
+            QAMOrder::QAM16 => (&FOUR_LEVEL_CODES, &FOUR_LEVEL_CODES),
+        };
+
+        AxisSlicer {
+            scale,
+            in_phase_codes,
+            quadrature_codes,
         }
     }
+
+    /// Returns the bits of the constellation point closest to `symbol`.
+    fn decide(&self, symbol: &Complex<T>) -> u32 {
+        // like the search of nearest_index, a silent channel maps to the first point
+        if !symbol.re.is_finite() || !symbol.im.is_finite() {
+            return 0;
+        }
+
+        let quadrature_bits = self.quadrature_codes.len().trailing_zeros();
+        (self.slice(symbol.re, self.in_phase_codes) << quadrature_bits)
+            | self.slice(symbol.im, self.quadrature_codes)
+    }
+
+    /// Returns the code of the level closest to `value`.
+    fn slice(&self, value: T, codes: &[u32]) -> u32 {
+        // level i sits at (2 * i - (levels - 1)) * scale
+        let highest: T = cast((codes.len() - 1) as f64);
+        let level = ((value / self.scale + highest) / cast(2.0))
+            .round()
+            .max(T::zero())
+            .min(highest);
+        codes[level.to_usize().unwrap_or_default()]
+    }
+}
+
+/// Returns the index of the lookup table entry closest to `symbol`.
+fn nearest_index<T: Float>(symbol: &Complex<T>, lookup: &[Complex<T>]) -> u8 {
+    lookup
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| {
+            // a silent channel equalizes to NaN, which maps to any point
+            distance(symbol, a)
+                .partial_cmp(&distance(symbol, b))
+                .unwrap_or(Ordering::Equal)
+        })
+        .map(|(index, _)| index as u8)
+        .unwrap_or_else(|| panic!("Symbol not found in QAM lookup table"))
 }
 
-fn distance(a: &Complex32, b: &Complex32) -> f32 {
+fn distance<T: Float>(a: &Complex<T>, b: &Complex<T>) -> T {
     ((a.re - b.re).powi(2) + (a.im - b.im).powi(2)).sqrt()
 }
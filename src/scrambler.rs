@@ -0,0 +1,60 @@
+//! This module provides scrambling (whitening) of data.
+//!
+//! Long runs of identical bytes, like the zeros used as padding, put the same value on every subcarrier,
+//! which results in high signal peaks and a signal without structure for synchronization.
+//! [scramble] adds a pseudo random sequence from the LFSR `x^7 + x^4 + 1` onto the data,
+//! and [descramble] removes it again.
+//! The LFSR starts from the same seed for every call, so both ends stay in sync without exchanging a state.
+
+/// Initial state of the LFSR.
+const SEED: u8 = 0x7f;
+
+/// Scrambles the data by adding the pseudo random sequence of the LFSR, most significant bit first.
+///
+/// # Example
+/// ```
+/// use software_modem::scrambler::{descramble, scramble};
+///
+/// let data = vec![0u8; 1024];
+/// let scrambled = scramble(&data);
+///
+/// // the zeros turn into a sequence with as many ones as zeros and almost all byte values
+/// let ones: u32 = scrambled.iter().map(|byte| byte.count_ones()).sum();
+/// assert!((ones as f32 / (8.0 * 1024.0) - 0.5).abs() < 0.01);
+///
+/// let mut values = scrambled.clone();
+/// values.sort();
+/// values.dedup();
+/// assert!(values.len() > 100);
+///
+/// assert_eq!(descramble(&scrambled), data);
+/// ```
+pub fn scramble(data: &[u8]) -> Vec<u8> {
+    let mut scrambled = data.to_vec();
+    scramble_in_place(&mut scrambled);
+    scrambled
+}
+
+/// Scrambles the data like [scramble], overwriting it instead of allocating the output.
+///
+/// Scrambling is additive, so this descrambles as well.
+pub(crate) fn scramble_in_place(data: &mut [u8]) {
+    let mut lfsr = SEED;
+    for byte in data {
+        let mut sequence = 0;
+        for _ in 0..8 {
+            // x^7 + x^4 + 1
+            let bit = ((lfsr >> 6) ^ (lfsr >> 3)) & 1;
+            lfsr = ((lfsr << 1) | bit) & 0x7f;
+            sequence = (sequence << 1) | bit;
+        }
+        *byte ^= sequence;
+    }
+}
+
+/// Removes the scrambling of [scramble].
+///
+/// Scrambling is additive, so this is the same operation.
+pub fn descramble(data: &[u8]) -> Vec<u8> {
+    scramble(data)
+}
@@ -0,0 +1,124 @@
+//! Golden vectors pinning the wire format.
+//!
+//! Every vector holds fixed input bytes, a fixed configuration and the exact output expected for them,
+//! so an accidental change of the constellations, the bit order or the FFT conventions fails here.
+//! The OFDM symbol follows the usual convention of reference implementations, e.g. in MATLAB or Octave:
+//! the spectrum is made hermitian symmetric, transformed with an unscaled inverse FFT of `2 * num_subcarriers` points
+//! and the last `cyclic_prefix` samples are copied to the front.
+//!
+//! If the format changes on purpose, regenerate the vectors by printing the output of the same calls
+//! and note the change in the documentation of the affected items.
+
+use realfft::num_complex::Complex32;
+use software_modem::ofdm::CyclicPrefix;
+use software_modem::ofdm::demodulator::{OFDMDemodulator, OFDMDemodulatorConfig};
+use software_modem::ofdm::modulator::{OFDMModulator, OFDMModulatorConfig};
+use software_modem::qam::{QAMModem, QAMOrder};
+
+/// Largest difference accepted between a computed and a golden sample.
+const TOLERANCE: f32 = 1e-4;
+
+const QAM4_INPUT: &[u8] = b"Go";
+const QAM4_SYMBOLS: [(f32, f32); 8] = [
+    (1.0, -1.0),
+    (1.0, 1.0),
+    (1.0, -1.0),
+    (-1.0, -1.0),
+    (1.0, -1.0),
+    (-1.0, 1.0),
+    (-1.0, -1.0),
+    (-1.0, -1.0),
+];
+
+const QAM16_INPUT: &[u8] = b"Golden";
+const QAM16_SYMBOLS: [(f32, f32); 12] = [
+    (3.0, 1.0),
+    (3.0, -3.0),
+    (3.0, -1.0),
+    (-3.0, -3.0),
+    (3.0, -1.0),
+    (-3.0, 1.0),
+    (3.0, -1.0),
+    (3.0, 1.0),
+    (3.0, -1.0),
+    (3.0, 3.0),
+    (3.0, -1.0),
+    (-3.0, -1.0),
+];
+
+/// One QAM-16 symbol on 16 subcarriers with a pilot on every fourth one and a cyclic prefix of 4 samples.
+const OFDM_INPUT: &[u8] = b"Golden";
+const OFDM_SYMBOL: [f32; 36] = [
+    -13.313708, 23.385283, -11.089739, -4.7294493, 42.0, 10.2524395, 16.274141, 33.442516,
+    -7.6568546, -6.8874826, -19.412937, 14.964779, -22.0, 0.57610226, -8.553537, -10.365803,
+    9.313708, -38.16736, -4.223969, -1.3934867, 18.0, -4.1295047, -8.960432, -18.660446, 3.6568546,
+    21.669556, 26.726643, -8.841843, -14.0, -6.6990366, -6.7601714, -4.4162683, -13.313708,
+    23.385283, -11.089739, -4.7294493,
+];
+
+fn golden_symbols(points: &[(f32, f32)]) -> Vec<Complex32> {
+    points
+        .iter()
+        .map(|&(re, im)| Complex32::new(re, im))
+        .collect()
+}
+
+fn assert_close(actual: &[f32], expected: &[f32]) {
+    assert_eq!(actual.len(), expected.len());
+    for (i, (a, e)) in actual.iter().zip(expected).enumerate() {
+        assert!(
+            (a - e).abs() <= TOLERANCE,
+            "sample {i} is {a}, but the golden vector holds {e}"
+        );
+    }
+}
+
+#[test]
+fn qam4_modulate() {
+    let modem = QAMModem::new(QAMOrder::QAM4);
+    let symbols = golden_symbols(&QAM4_SYMBOLS);
+
+    assert_eq!(modem.modulate(QAM4_INPUT), symbols);
+    assert_eq!(modem.demodulate(&symbols), QAM4_INPUT);
+}
+
+#[test]
+fn qam16_modulate() {
+    let modem = QAMModem::new(QAMOrder::QAM16);
+    let symbols = golden_symbols(&QAM16_SYMBOLS);
+
+    assert_eq!(modem.modulate(QAM16_INPUT), symbols);
+    assert_eq!(modem.demodulate(&symbols), QAM16_INPUT);
+}
+
+#[test]
+fn ofdm_symbol() {
+    let modulator = OFDMModulator::new(OFDMModulatorConfig {
+        num_subcarriers: 16,
+        cyclic_prefix: CyclicPrefix::Samples(4),
+        pilot_subcarrier_every: 4,
+        qam_order: QAMOrder::QAM16,
+        ..Default::default()
+    })
+    .unwrap();
+    let demodulator = OFDMDemodulator::new(OFDMDemodulatorConfig {
+        num_subcarriers: 16,
+        cyclic_prefix: CyclicPrefix::Samples(4),
+        pilot_subcarrier_every: 4,
+        qam_order: QAMOrder::QAM16,
+        ..Default::default()
+    })
+    .unwrap();
+    assert_eq!(modulator.bytes_per_symbol(), OFDM_INPUT.len());
+
+    let mut symbol = vec![0.0; modulator.get_symbol_length()];
+    modulator
+        .modulate_buffer_as_symbol(OFDM_INPUT, &mut symbol)
+        .unwrap();
+
+    assert_close(&symbol, &OFDM_SYMBOL);
+    assert_eq!(
+        demodulator.demodulate_symbol_from_buffer(&OFDM_SYMBOL),
+        OFDM_INPUT
+    );
+}